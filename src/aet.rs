@@ -134,6 +134,38 @@ pub struct AetSetNode {
 	pub big_endian: bool,
 	pub is_x: bool,
 	pub scenes: Vec<AetSceneNode>,
+	pub unsaved: bool,
+	// Fps, width and height of every scene before and after the last bulk edit
+	// Only revertable while no scene was edited since, a revert would throw those edits away
+	pub scene_properties_undo: Option<[Vec<(f32, u32, u32)>; 2]>,
+	// Set when scenes were removed or duplicated, the new index of each old scene if it's kept
+	pub scene_remap: Option<Vec<Option<usize>>>,
+}
+
+#[derive(Clone, Copy)]
+struct SceneProperties {
+	set_fps: bool,
+	fps: f32,
+	set_size: bool,
+	width: u32,
+	height: u32,
+}
+
+impl SceneProperties {
+	fn apply(&self, scene: &mut AetSceneNode) {
+		if self.set_fps {
+			scene.fps = self.fps;
+		}
+		if self.set_size {
+			scene.width = self.width;
+			scene.height = self.height;
+		}
+	}
+
+	fn changes(&self, scene: &AetSceneNode) -> bool {
+		(self.set_fps && scene.fps != self.fps)
+			|| (self.set_size && (scene.width != self.width || scene.height != self.height))
+	}
 }
 
 impl TreeNode for AetSetNode {
@@ -141,6 +173,10 @@ impl TreeNode for AetSetNode {
 		&self.name
 	}
 
+	fn label_sameline(&mut self, ui: &mut egui::Ui) {
+		crate::app::unsaved_marker(ui, self.unsaved);
+	}
+
 	fn has_children(&self) -> bool {
 		true
 	}
@@ -149,6 +185,44 @@ impl TreeNode for AetSetNode {
 		for scene in &mut self.scenes {
 			f(scene);
 		}
+
+		if !self
+			.scenes
+			.iter()
+			.any(|scene| scene.want_deletion || scene.want_duplicate)
+		{
+			return;
+		}
+
+		// The old index of every scene, copies have none
+		let mut origins = (0..self.scenes.len()).map(Some).collect::<Vec<_>>();
+		for i in (0..self.scenes.len()).rev() {
+			let scene = &mut self.scenes[i];
+			if !scene.want_duplicate {
+				continue;
+			}
+			scene.want_duplicate = false;
+
+			let mut copy = scene.clone();
+			copy.name = format!("{} copy", scene.name);
+			copy.root = scene.root.deep_clone();
+			copy.frame_cache.clear();
+			self.scenes.insert(i + 1, copy);
+			origins.insert(i + 1, None);
+		}
+
+		let mut kept = self.scenes.iter().map(|scene| !scene.want_deletion);
+		origins.retain(|_| kept.next().unwrap_or(true));
+		self.scenes.retain(|scene| !scene.want_deletion);
+
+		let mut remap = vec![None; origins.iter().flatten().max().map_or(0, |i| i + 1)];
+		for (new, old) in origins.iter().enumerate() {
+			if let Some(old) = old {
+				remap[*old] = Some(new);
+			}
+		}
+		self.scene_properties_undo = None;
+		self.scene_remap = Some(remap);
 	}
 
 	fn display_opts(&mut self, ui: &mut egui::Ui, _frame: &mut eframe::Frame) {
@@ -166,6 +240,22 @@ impl TreeNode for AetSetNode {
 					});
 				});
 
+				body.row(height, |mut row| {
+					row.col(|ui| {
+						ui.label("Game looks for");
+					});
+					row.col(|ui| match self.companion_names() {
+						Some(names) => {
+							ui.label(names.join(", "));
+						}
+						None => {
+							ui.label(format!("{ICON_WARNING} Nothing")).on_hover_text(
+								"The game only loads aet sets named aet_*.bin or *.aet",
+							);
+						}
+					});
+				});
+
 				body.row(height, |mut row| {
 					row.col(|ui| {
 						ui.label("Modern");
@@ -195,6 +285,122 @@ impl TreeNode for AetSetNode {
 			});
 	}
 
+	fn has_context_menu(&self) -> bool {
+		true
+	}
+
+	fn display_ctx_menu(&mut self, ui: &mut egui::Ui) {
+		if ui.button("Add scene").clicked() {
+			let name = format!("Scene {}", self.scenes.len());
+			self.scenes.push(AetSceneNode::new(name));
+			self.scene_properties_undo = None;
+		}
+
+		if ui
+			.button("Clamp keys to layers")
+			.on_hover_text(
+				"Move keys before or after their layer onto its start or end, keeping the nearest one on each",
+			)
+			.clicked()
+		{
+			self.clamp_keys();
+		}
+
+		ui.menu_button("Set on all scenes", |ui| {
+			let id = egui::Id::new("SceneProperties");
+			let mut properties = ui
+				.data(|data| data.get_temp::<SceneProperties>(id))
+				.unwrap_or_else(|| {
+					let first = self.scenes.first();
+					SceneProperties {
+						set_fps: false,
+						fps: first.map_or(60.0, |scene| scene.fps),
+						set_size: false,
+						width: first.map_or(1920, |scene| scene.width),
+						height: first.map_or(1080, |scene| scene.height),
+					}
+				});
+
+			egui::Grid::new("ScenePropertiesGrid").show(ui, |ui| {
+				ui.checkbox(&mut properties.set_fps, "FPS");
+				crate::app::num_edit(ui, &mut properties.fps, 0);
+				ui.end_row();
+
+				ui.checkbox(&mut properties.set_size, "Size");
+				ui.horizontal(|ui| {
+					crate::app::num_edit(ui, &mut properties.width, 0);
+					ui.label("x");
+					crate::app::num_edit(ui, &mut properties.height, 0);
+				});
+				ui.end_row();
+			});
+
+			ui.data_mut(|data| data.insert_temp(id, properties));
+
+			let changed = self
+				.scenes
+				.iter()
+				.filter(|scene| properties.changes(scene))
+				.map(|scene| {
+					format!(
+						"{}: {} fps, {}x{}",
+						scene.name, scene.fps, scene.width, scene.height
+					)
+				})
+				.collect::<Vec<_>>();
+
+			ui.separator();
+			if changed.is_empty() {
+				ui.label("No scenes change");
+			} else {
+				egui::ScrollArea::vertical()
+					.max_height(200.0)
+					.show(ui, |ui| {
+						for line in &changed {
+							ui.label(line);
+						}
+					});
+			}
+
+			if ui
+				.add_enabled(
+					!changed.is_empty(),
+					egui::Button::new(format!("Apply to {} scenes", changed.len())),
+				)
+				.clicked()
+			{
+				let before = self.scene_properties();
+				for scene in &mut self.scenes {
+					properties.apply(scene);
+				}
+				self.scene_properties_undo = Some([before, self.scene_properties()]);
+				ui.close();
+			}
+
+			if self
+				.scene_properties_undo
+				.as_ref()
+				.is_some_and(|[_, after]| *after != self.scene_properties())
+			{
+				self.scene_properties_undo = None;
+			}
+			if let Some([undo, _]) = &self.scene_properties_undo
+				&& ui
+					.button("Revert last change")
+					.on_hover_text("Restore the fps and size every scene had before")
+					.clicked()
+			{
+				for (scene, (fps, width, height)) in self.scenes.iter_mut().zip(undo) {
+					scene.fps = *fps;
+					scene.width = *width;
+					scene.height = *height;
+				}
+				self.scene_properties_undo = None;
+				ui.close();
+			}
+		});
+	}
+
 	fn raw_data(&self) -> Vec<u8> {
 		let set = aet::Set {
 			modern: self.modern,
@@ -237,10 +443,66 @@ impl TreeNode for AetSetNode {
 }
 
 impl AetSetNode {
+	fn scene_properties(&self) -> Vec<(f32, u32, u32)> {
+		self.scenes
+			.iter()
+			.map(|scene| (scene.fps, scene.width, scene.height))
+			.collect()
+	}
+
 	pub fn name_pattern() -> Regex {
 		Regex::new(r"(^aet_.*\.bin)|(.aet)$").unwrap()
 	}
 
+	// Sprite set and sprite database the game loads along with an aet set of this name
+	pub fn companion_names(&self) -> Option<[String; 2]> {
+		if !Self::name_pattern().is_match(&self.name) {
+			return None;
+		}
+
+		let stem = self.name.trim_end_matches(".bin").trim_end_matches(".aet");
+		let stem = stem.strip_prefix("aet_").unwrap_or(stem);
+		Some(if self.modern {
+			[format!("spr_{stem}.spr"), format!("spr_{stem}.spi")]
+		} else {
+			[format!("spr_{stem}.bin"), String::from("spr_db.bin")]
+		})
+	}
+
+	// One line per layer with keys the playback slider can't reach
+	pub fn keys_out_of_bounds(&self) -> Vec<String> {
+		let mut report = Vec::new();
+		for scene in &self.scenes {
+			for layer in &scene.root.layers {
+				layer
+					.try_lock()
+					.unwrap()
+					.keys_out_of_bounds(&scene.name, &mut report);
+			}
+		}
+		report
+	}
+
+	// Sound indices of every audio layer, sorted
+	#[cfg(feature = "audio")]
+	pub fn sound_indices(&self) -> Vec<u32> {
+		let mut indices = Vec::new();
+		for scene in &self.scenes {
+			scene.root.collect_sound_indices(&mut indices);
+		}
+		indices.sort();
+		indices.dedup();
+		indices
+	}
+
+	pub fn clamp_keys(&mut self) {
+		for scene in &self.scenes {
+			for layer in &scene.root.layers {
+				layer.try_lock().unwrap().clamp_keys();
+			}
+		}
+	}
+
 	pub fn read(name: &str, data: &[u8]) -> Self {
 		let set = aet::Set::from_buf(data, name.ends_with("aet"));
 
@@ -271,11 +533,31 @@ impl AetSetNode {
 					height: scene.height,
 					camera: scene.camera,
 					root,
+					note: String::new(),
 
 					current_time: scene.start_time,
 					playing: false,
 					display_placeholders: false,
 					centered: false,
+					perspective: false,
+					depth_sort: false,
+					show_hierarchy: false,
+					isolate_comp: false,
+					isolated_time: 0.0,
+					looping: false,
+					playback_speed: 1.0,
+					scrub_cache: false,
+					snap_to_keys: false,
+					pixel_scale: None,
+					backdrop: None,
+					blend_backdrop: false,
+					want_deletion: false,
+					want_duplicate: false,
+					frame_cache: std::collections::HashMap::new(),
+					want_strip_export: None,
+					strip_render: None,
+					confirm_removal: false,
+					error: None,
 
 					selected_curve: None,
 					gizmo: Gizmo::default(),
@@ -289,6 +571,56 @@ impl AetSetNode {
 			big_endian: set.big_endian,
 			is_x: set.is_x,
 			scenes,
+			unsaved: false,
+			scene_properties_undo: None,
+			scene_remap: None,
+		}
+	}
+
+	// Notes of every scene and layer, keyed by their indices joined with /
+	// Names aren't unique, so siblings sharing one would swap notes
+	pub fn notes(&self) -> std::collections::BTreeMap<String, String> {
+		let mut notes = std::collections::BTreeMap::new();
+		for (i, scene) in self.scenes.iter().enumerate() {
+			let path = i.to_string();
+			if !scene.note.is_empty() {
+				notes.insert(path.clone(), scene.note.clone());
+			}
+			for (i, layer) in scene.root.layers.iter().enumerate() {
+				layer
+					.try_lock()
+					.unwrap()
+					.collect_notes(&format!("{path}/{i}"), &mut notes);
+			}
+		}
+		notes
+	}
+
+	// Keys with a NaN or infinite frame, value or tangent, which the game fails to load
+	// With zero set, those numbers are replaced by 0
+	pub fn non_finite_keys(&mut self, zero: bool) -> Vec<String> {
+		let mut found = Vec::new();
+		for scene in &self.scenes {
+			for layer in &scene.root.layers {
+				layer
+					.try_lock()
+					.unwrap()
+					.non_finite_keys(&scene.name, zero, &mut found);
+			}
+		}
+		found
+	}
+
+	pub fn set_notes(&mut self, notes: &std::collections::BTreeMap<String, String>) {
+		for (i, scene) in self.scenes.iter_mut().enumerate() {
+			let path = i.to_string();
+			scene.note = notes.get(&path).cloned().unwrap_or_default();
+			for (i, layer) in scene.root.layers.iter().enumerate() {
+				layer
+					.try_lock()
+					.unwrap()
+					.set_notes(&format!("{path}/{i}"), notes);
+			}
 		}
 	}
 
@@ -308,6 +640,96 @@ impl AetSetNode {
 	}
 }
 
+// Notes are saved with projects rather than the aet set
+fn note_edit(ui: &mut egui::Ui, note: &mut String) {
+	ui.separator();
+	ui.label("Note");
+	ui.add(
+		egui::TextEdit::multiline(note)
+			.desired_width(f32::INFINITY)
+			.hint_text("Saved with the project"),
+	);
+}
+
+// Dir and rot are both rotations in degrees, dir is applied first so it orients the axes rot turns around
+const DIR_HOVER_TEXT: &str = "Orientation in degrees, applied before rotation";
+
+// Find and replace over the sprite sources of every video layer in a set
+#[derive(Clone, Default)]
+pub struct SourceReplace {
+	pub find: String,
+	pub replace: String,
+	pub id_range: [u32; 2],
+	pub id_offset: i32,
+}
+
+pub struct SourceChange {
+	pub layer: String,
+	pub old: (String, u32),
+	pub new: (String, u32),
+}
+
+impl SourceReplace {
+	fn replaced(&self, source: &AetVideoSourceNode) -> Option<(String, u32)> {
+		let name = if !self.find.is_empty() && source.name.contains(&self.find) {
+			source.name.replace(&self.find, &self.replace)
+		} else {
+			source.name.clone()
+		};
+		let id =
+			if self.id_offset != 0 && (self.id_range[0]..=self.id_range[1]).contains(&source.id) {
+				source.id.wrapping_add_signed(self.id_offset)
+			} else {
+				source.id
+			};
+
+		(name != source.name || id != source.id).then_some((name, id))
+	}
+
+	pub fn preview(&self, aet_set: &AetSetNode) -> Vec<SourceChange> {
+		let mut changes = Vec::new();
+		for scene in &aet_set.scenes {
+			scene.root.for_each_source(&mut |layer, source| {
+				if let Some(new) = self.replaced(source) {
+					changes.push(SourceChange {
+						layer: layer.to_string(),
+						old: (source.name.clone(), source.id),
+						new,
+					});
+				}
+			});
+		}
+		changes
+	}
+
+	// Changed sources lose their sprite so they can be linked again by id
+	pub fn apply(&self, aet_set: &mut AetSetNode) {
+		for scene in &mut aet_set.scenes {
+			scene.root.for_each_source(&mut |_, source| {
+				if let Some((name, id)) = self.replaced(source) {
+					source.name = name;
+					source.id = id;
+					source.sprite = None;
+				}
+			});
+		}
+	}
+}
+
+// Marker names that bound looped playback
+const LOOP_IN: &str = "loop_in";
+const LOOP_OUT: &str = "loop_out";
+
+const SCENE_SIZES: [(&str, u32, u32); 7] = [
+	("1920x1080 (16:9)", 1920, 1080),
+	("1600x900 (16:9)", 1600, 900),
+	("1280x720 (16:9)", 1280, 720),
+	("960x544 (PSV)", 960, 544),
+	("1440x1080 (4:3)", 1440, 1080),
+	("1024x768 (4:3)", 1024, 768),
+	("640x480 (4:3)", 640, 480),
+];
+
 #[derive(Clone)]
 pub struct AetSceneNode {
 	pub name: String,
@@ -319,16 +741,89 @@ pub struct AetSceneNode {
 	pub height: u32,
 	pub camera: Option<aet::Camera>,
 	pub root: AetCompNode,
+	// Kept in project files, the aet format has nowhere to store it
+	pub note: String,
 
 	pub current_time: f32,
 	pub playing: bool,
 	pub display_placeholders: bool,
 	pub centered: bool,
+	pub perspective: bool,
+	pub depth_sort: bool,
+	pub show_hierarchy: bool,
+	pub isolate_comp: bool,
+	pub isolated_time: f32,
+	pub looping: bool,
+	// Preview only, negative plays backwards
+	pub playback_speed: f32,
+	pub scrub_cache: bool,
+	pub snap_to_keys: bool,
+	// Native pixels shown per screen pixel, None fits the preview to the panel
+	pub pixel_scale: Option<u32>,
+	// Layer the selected one is previewed over, its path is looked up when drawn
+	pub backdrop: Option<Rc<Mutex<AetLayerNode>>>,
+	pub blend_backdrop: bool,
+	pub want_deletion: bool,
+	// Removing a scene can't be undone, so it is asked about first
+	pub confirm_removal: bool,
+	pub want_duplicate: bool,
+	// Computed videos of each root layer keyed by frame * 100, cleared by the app on edits
+	// None marks a layer invalidated on its own, isolated compositions are cached as one entry
+	pub frame_cache: std::collections::HashMap<i64, Vec<Option<Vec<WgpuAetVideo>>>>,
+	// Maximum width of the sprite strip to render
+	pub want_strip_export: Option<u32>,
+	pub strip_render: Option<StripRender>,
+	pub error: Option<String>,
 
 	pub selected_curve: Option<CurveType>,
 	pub gizmo: Gizmo,
 }
 
+impl AetSceneNode {
+	// 1920x1080 at 60 fps, one second long and without layers
+	pub fn new(name: String) -> Self {
+		Self {
+			name,
+			start_time: 0.0,
+			end_time: 60.0,
+			fps: 60.0,
+			color: [0, 0, 0],
+			width: 1920,
+			height: 1080,
+			camera: None,
+			root: AetCompNode { layers: Vec::new() },
+			note: String::new(),
+
+			current_time: 0.0,
+			playing: false,
+			display_placeholders: false,
+			centered: false,
+			perspective: false,
+			depth_sort: false,
+			show_hierarchy: false,
+			isolate_comp: false,
+			isolated_time: 0.0,
+			looping: false,
+			playback_speed: 1.0,
+			scrub_cache: false,
+			snap_to_keys: false,
+			pixel_scale: None,
+			backdrop: None,
+			blend_backdrop: false,
+			want_deletion: false,
+			want_duplicate: false,
+			frame_cache: std::collections::HashMap::new(),
+			want_strip_export: None,
+			strip_render: None,
+			confirm_removal: false,
+			error: None,
+
+			selected_curve: None,
+			gizmo: Gizmo::default(),
+		}
+	}
+}
+
 impl PartialEq for AetSceneNode {
 	fn eq(&self, other: &Self) -> bool {
 		self.name == other.name
@@ -339,6 +834,7 @@ impl PartialEq for AetSceneNode {
 			&& self.width == other.width
 			&& self.height == other.height
 			&& self.camera == other.camera
+			&& self.note == other.note
 			&& self.root == other.root
 	}
 }
@@ -371,16 +867,28 @@ impl TreeNode for AetSceneNode {
 		frame: &mut eframe::Frame,
 		undoer: &mut crate::app::LayerUndoer,
 	) -> egui::Response {
-		let resp = crate::app::collapsing_selectable_label(
-			ui,
-			&self.name,
-			path,
-			path == *selected,
-			|ui| {
+		let label = if self.note.is_empty() {
+			self.name.clone()
+		} else {
+			format!("{} {ICON_STICKY_NOTE_2}", self.name)
+		};
+		let mut resp =
+			crate::app::collapsing_selectable_label(ui, label, path, path == *selected, |ui| {
 				self.root.display_tree(ui, path, selected, frame, undoer);
-			},
-		)
-		.header_response;
+			})
+			.header_response;
+		if !self.note.is_empty() {
+			resp = resp.on_hover_text(&self.note);
+		}
+
+		if selected.len() > path.len()
+			&& selected.starts_with(path)
+			&& ui
+				.data_mut(|data| data.remove_temp::<bool>(egui::Id::new(SET_BACKDROP)))
+				.is_some()
+		{
+			self.backdrop = self.selected_layer(selected);
+		}
 
 		if self.has_context_menu() {
 			let menu = egui::Popup::context_menu(&resp).show(|ui| self.display_ctx_menu(ui));
@@ -395,31 +903,33 @@ impl TreeNode for AetSceneNode {
 			*selected = path.to_vec();
 		}
 
+		if self.confirm_removal {
+			let modal = egui::Modal::new(egui::Id::new("RemoveScenePrompt")).show(ui.ctx(), |ui| {
+				ui.heading(format!("Remove {}?", self.name));
+				ui.label("The scene and its layers are removed, this can't be undone");
+				ui.horizontal(|ui| {
+					if ui.button("Remove").clicked() {
+						self.want_deletion = true;
+						ui.close();
+					}
+					if ui.button("Cancel").clicked() {
+						ui.close();
+					}
+				});
+			});
+
+			if modal.should_close() {
+				self.confirm_removal = false;
+			}
+		}
+
 		if self.root.layers.iter().any(|layer| {
 			let layer = layer.try_lock().unwrap();
-			layer.want_deletion || layer.want_duplicate
+			layer.want_deletion || layer.want_duplicate || layer.precompose.is_some()
 		}) {
 			*selected = path.to_vec();
 			undoer.add_undo(
-				AetLayerNode {
-					name: String::from("DUMMY"),
-					start_time: 0.0,
-					end_time: 0.0,
-					offset_time: 0.0,
-					time_scale: 1.0,
-					flags: kkdlib::aet::LayerFlags::new(),
-					quality: kkdlib::aet::LayerQuality::None,
-					item: AetItemNode::Comp(self.root.clone()),
-					markers: Vec::new(),
-					video: None,
-					parent: None,
-					audio: None,
-					sprites: Rc::new(Mutex::new(Vec::new())),
-					visible: false,
-					selected_key: 0,
-					want_deletion: false,
-					want_duplicate: false,
-				},
+				AetLayerNode::root_snapshot(self.root.clone()),
 				path.to_vec(),
 			);
 		}
@@ -428,26 +938,29 @@ impl TreeNode for AetSceneNode {
 			.layers
 			.retain(|layer| !layer.try_lock().unwrap().want_deletion);
 
-		for i in self
-			.root
-			.layers
-			.iter()
-			.enumerate()
-			.filter(|(_, layer)| layer.try_lock().unwrap().want_duplicate)
-			.map(|(i, _)| i)
-			.collect::<Vec<_>>()
-		{
-			self.root.layers.insert(i, self.root.layers[i].clone());
-		}
-
-		for layer in &mut self.root.layers {
-			layer.try_lock().unwrap().want_duplicate = false;
-		}
+		self.root.apply_duplicates();
+		self.root.apply_precompose();
 
 		resp
 	}
 
 	fn display_opts(&mut self, ui: &mut egui::Ui, _frame: &mut eframe::Frame) {
+		if let Some(error) = &self.error {
+			let modal = egui::Modal::new(egui::Id::new("AetSceneError")).show(ui.ctx(), |ui| {
+				ui.heading("An error has occured");
+				ui.vertical_centered(|ui| {
+					ui.label(error);
+					if ui.button("Ok").clicked() {
+						ui.close();
+					}
+				});
+			});
+
+			if modal.should_close() {
+				self.error = None;
+			}
+		}
+
 		let height = ui.text_style_height(&egui::TextStyle::Body);
 		egui_extras::TableBuilder::new(ui)
 			.column(egui_extras::Column::remainder())
@@ -467,7 +980,7 @@ impl TreeNode for AetSceneNode {
 						ui.label("Start time");
 					});
 					row.col(|ui| {
-						crate::app::num_edit(ui, &mut self.start_time, 2);
+						crate::app::time_edit(ui, &mut self.start_time);
 					});
 				});
 
@@ -476,7 +989,16 @@ impl TreeNode for AetSceneNode {
 						ui.label("End time");
 					});
 					row.col(|ui| {
-						crate::app::num_edit(ui, &mut self.end_time, 2);
+						ui.horizontal(|ui| {
+							crate::app::time_edit(ui, &mut self.end_time);
+							if self.end_time <= self.start_time {
+								ui.label(
+									egui::RichText::new(ICON_WARNING)
+										.color(ui.visuals().warn_fg_color),
+								)
+								.on_hover_text("Scene ends before it starts, nothing is shown");
+							}
+						});
 					});
 				});
 
@@ -506,7 +1028,54 @@ impl TreeNode for AetSceneNode {
 						crate::app::num_edit(ui, &mut self.height, 0);
 					});
 				});
+
+				body.row(height, |mut row| {
+					row.col(|ui| {
+						ui.label("Size preset");
+					});
+					row.col(|ui| {
+						let current = SCENE_SIZES
+							.iter()
+							.find(|(_, width, height)| {
+								*width == self.width && *height == self.height
+							})
+							.map_or("Custom", |(name, _, _)| *name);
+						egui::ComboBox::from_id_salt("SceneSizeComboBox")
+							.selected_text(current)
+							.show_ui(ui, |ui| {
+								for (name, width, height) in SCENE_SIZES {
+									if ui.selectable_label(current == name, name).clicked() {
+										self.width = width;
+										self.height = height;
+									}
+								}
+							});
+					});
+				});
+
+				body.row(height, |mut row| {
+					row.col(|ui| {
+						ui.label("Texture memory");
+					});
+					row.col(|ui| {
+						let formats = self.texture_memory();
+						let total = formats.iter().map(|(_, size)| size).sum::<usize>();
+						let breakdown = formats
+							.iter()
+							.map(|(format, size)| format!("{format}: {}", format_bytes(*size)))
+							.collect::<Vec<_>>()
+							.join("\n");
+						ui.label(format_bytes(total))
+							.on_hover_text(if breakdown.is_empty() {
+								String::from("No textures are used")
+							} else {
+								breakdown
+							});
+					});
+				});
 			});
+
+		note_edit(ui, &mut self.note);
 	}
 
 	fn has_context_menu(&self) -> bool {
@@ -514,38 +1083,408 @@ impl TreeNode for AetSceneNode {
 	}
 
 	fn display_ctx_menu(&mut self, ui: &mut egui::Ui) {
+		let copied = ui
+			.data(|data| data.get_temp::<bool>(egui::Id::new(crate::app::LAYER_COPIED)))
+			.unwrap_or(false);
+		if ui
+			.add_enabled(copied, egui::Button::new("Paste layer"))
+			.clicked()
+		{
+			ui.data_mut(|data| data.insert_temp(egui::Id::new(crate::app::PASTE_LAYER), true));
+		}
+		if ui
+			.add_enabled(self.backdrop.is_some(), egui::Button::new("Clear backdrop"))
+			.clicked()
+		{
+			self.backdrop = None;
+			self.blend_backdrop = false;
+		}
 		if ui.button("Hide all").clicked() {
 			for layer in &mut self.root.layers {
 				layer.try_lock().unwrap().visible = false;
 			}
 		}
+		if ui
+			.button("Collapse constant curves")
+			.on_hover_text("Reduce curves whose keys all share a value to a single key")
+			.clicked()
+		{
+			for layer in &self.root.layers {
+				layer.try_lock().unwrap().collapse_constant_curves();
+			}
+		}
+
+		ui.menu_button("Export sprite strip", |ui| {
+			let id = egui::Id::new("StripMaxWidth");
+			let mut max_width = ui
+				.data(|data| data.get_temp::<u32>(id))
+				.unwrap_or(STRIP_MAX_WIDTH);
+
+			ui.horizontal(|ui| {
+				ui.label("Max width");
+				crate::app::num_edit(ui, &mut max_width, 0);
+			});
+
+			ui.data_mut(|data| data.insert_temp(id, max_width));
+
+			if ui
+				.button("Export")
+				.on_hover_text("Render every frame into one image, wrapping rows at the max width, with a JSON file describing the cells")
+				.clicked()
+			{
+				self.want_strip_export = Some(max_width);
+				ui.close();
+			}
+		});
+
+		ui.separator();
+		if ui.button("Duplicate").clicked() {
+			self.want_duplicate = true;
+		}
+		if ui.button("Remove").clicked() {
+			self.confirm_removal = true;
+		}
 	}
 }
 
+const FRAME_CACHE_SIZE: usize = 512;
+const STRIP_MAX_WIDTH: u32 = 4096;
+// Larger images can't be created on most GPUs or opened by most viewers
+const STRIP_MAX_SIZE: u64 = 16384;
+// 256 MiB of pixels, kept in memory until the strip is saved
+const STRIP_MAX_PIXELS: u64 = 8192 * 8192;
+const STRIP_FRAMES_PER_UPDATE: u32 = 4;
+
+// A sprite strip being rendered a few frames per update so the window stays responsive
+#[derive(Clone)]
+pub struct StripRender {
+	strip: image::RgbaImage,
+	metadata: StripMetadata,
+	next: u32,
+}
+
 impl AetSceneNode {
-	pub fn display_visual(&mut self, ui: &mut egui::Ui, rect: egui::Rect, selected: &[usize]) {
+	fn base_mat(&self) -> Mat4 {
 		let mut mat = Mat4::default();
 		if self.centered {
 			mat.w.x = self.width as f32 / 2.0;
 			mat.w.y = self.height as f32 / 2.0;
 		}
-		let mut videos = WgpuAetVideos {
-			videos: Vec::new(),
-			viewport_size: [self.width as f32, self.height as f32],
-		};
+		mat
+	}
 
-		self.root.display(
-			mat,
-			self.current_time,
-			1.0,
-			self.display_placeholders,
-			&mut videos,
-		);
+	// Every frame of the scene tiled left to right and top to bottom, rendered by continue_strip
+	fn start_strip(&self, max_width: u32) -> Result<StripRender, String> {
+		if self.width == 0 || self.height == 0 {
+			return Err(String::from("Scene has no size"));
+		}
+
+		let frames = (self.end_time - self.start_time).ceil().max(0.0) as u32;
+		if frames == 0 {
+			return Err(String::from("Scene has no frames"));
+		}
+		let columns = (max_width / self.width).clamp(1, frames);
+		let rows = frames.div_ceil(columns);
+
+		let width = columns as u64 * self.width as u64;
+		let height = rows as u64 * self.height as u64;
+		if width > STRIP_MAX_SIZE || height > STRIP_MAX_SIZE {
+			return Err(format!(
+				"The strip would be {width}x{height} with {rows} rows, the largest possible is {STRIP_MAX_SIZE}x{STRIP_MAX_SIZE}"
+			));
+		}
+		if width * height > STRIP_MAX_PIXELS {
+			return Err(format!(
+				"The strip would be {width}x{height}, more than {STRIP_MAX_PIXELS} pixels"
+			));
+		}
+
+		Ok(StripRender {
+			strip: image::RgbaImage::new(width as u32, height as u32),
+			metadata: StripMetadata {
+				cell_width: self.width,
+				cell_height: self.height,
+				frame_count: frames,
+				columns,
+				rows,
+				fps: self.fps,
+			},
+			next: 0,
+		})
+	}
+
+	// Called every update, renders the next few frames of a strip export with a progress modal
+	// and saves it once all are in
+	pub fn continue_strip(&mut self, ctx: &egui::Context, frame: &eframe::Frame) {
+		if let Some(max_width) = self.want_strip_export.take() {
+			match self.start_strip(max_width) {
+				Ok(render) => self.strip_render = Some(render),
+				Err(e) => self.error = Some(e),
+			}
+		}
+		let Some(mut render) = self.strip_render.take() else {
+			return;
+		};
+		let Some(render_state) = frame.wgpu_render_state() else {
+			self.error = Some(String::from("Rendering is unavailable"));
+			return;
+		};
+
+		let frames = render.metadata.frame_count;
+		let modal = egui::Modal::new(egui::Id::new("StripRender")).show(ctx, |ui| {
+			ui.heading("Rendering sprite strip");
+			ui.add(
+				egui::ProgressBar::new(render.next as f32 / frames as f32)
+					.text(format!("{} / {frames} frames", render.next)),
+			);
+			ui.button("Cancel").clicked()
+		});
+		if modal.inner {
+			return;
+		}
+
+		let columns = render.metadata.columns;
+		let end = (render.next + STRIP_FRAMES_PER_UPDATE).min(frames);
+		for i in render.next..end {
+			let mut videos = WgpuAetVideos {
+				videos: Vec::new(),
+				viewport_size: [self.width as f32, self.height as f32],
+				perspective: self.perspective,
+				background: None,
+				target_size: [self.width, self.height],
+				nearest: false,
+			};
+			self.root.display(
+				self.base_mat(),
+				self.start_time + i as f32,
+				1.0,
+				false,
+				&mut videos,
+			);
+			if self.depth_sort {
+				videos
+					.videos
+					.sort_by(|a, b| b.depth().total_cmp(&a.depth()));
+			}
+
+			let cell = match render_offscreen(render_state, &videos) {
+				Ok(cell) => cell,
+				Err(e) => {
+					self.error = Some(e);
+					return;
+				}
+			};
+			image::imageops::replace(
+				&mut render.strip,
+				&cell,
+				(i % columns) as i64 * render.metadata.cell_width as i64,
+				(i / columns) as i64 * render.metadata.cell_height as i64,
+			);
+		}
+		render.next = end;
+
+		if render.next == frames {
+			export_strip(&self.name, render.strip, render.metadata);
+		} else {
+			self.strip_render = Some(render);
+			ctx.request_repaint();
+		}
+	}
+
+	// Drops the cached videos of a root layer and of the root layers parented to it
+	pub fn invalidate_layer(&mut self, index: usize) {
+		let Some(changed) = self.root.layers.get(index) else {
+			self.frame_cache.clear();
+			return;
+		};
+		if self.isolate_comp {
+			self.frame_cache.clear();
+			return;
+		}
+
+		let indices = self
+			.root
+			.layers
+			.iter()
+			.enumerate()
+			.filter(|(i, layer)| {
+				*i == index
+					|| layer
+						.try_lock()
+						.unwrap()
+						.parent
+						.as_ref()
+						.is_some_and(|parent| Rc::ptr_eq(parent, changed))
+			})
+			.map(|(i, _)| i)
+			.collect::<Vec<_>>();
+
+		for layers in self.frame_cache.values_mut() {
+			for i in &indices {
+				if let Some(layer) = layers.get_mut(*i) {
+					*layer = None;
+				}
+			}
+		}
+	}
+
+	// Stored size of every texture the scene's sprites use, each counted once, grouped by format
+	pub fn texture_memory(&self) -> Vec<(String, usize)> {
+		let mut textures: Vec<Rc<Mutex<TextureNode>>> = Vec::new();
+		self.root.for_each_source(&mut |_, source| {
+			let Some(sprite) = &source.sprite else {
+				return;
+			};
+			let texture = sprite.try_lock().unwrap().texture.clone();
+			if !textures.iter().any(|other| Rc::ptr_eq(other, &texture)) {
+				textures.push(texture);
+			}
+		});
+
+		let mut formats: Vec<(String, usize)> = Vec::new();
+		for texture in textures {
+			let texture = texture.try_lock().unwrap();
+			let Some(format) = texture.texture.get_mipmap(0, 0).map(|mip| mip.format()) else {
+				continue;
+			};
+			let format = format!("{:?}", format);
+			let size = texture
+				.texture
+				.mipmaps()
+				.map(|mip| mip.data().map_or(0, |data| data.len()))
+				.sum::<usize>();
+
+			match formats.iter_mut().find(|(name, _)| *name == format) {
+				Some((_, total)) => *total += size,
+				None => formats.push((format, size)),
+			}
+		}
+
+		formats
+	}
+
+	pub fn display_visual(&mut self, ui: &mut egui::Ui, rect: egui::Rect, selected: &[usize]) {
+		let mat = self.base_mat();
+		let mut videos = WgpuAetVideos {
+			videos: Vec::new(),
+			viewport_size: [self.width as f32, self.height as f32],
+			perspective: self.perspective,
+			background: Some([
+				self.color[0] as f32 / 255.0,
+				self.color[1] as f32 / 255.0,
+				self.color[2] as f32 / 255.0,
+			]),
+			target_size: [self.width, self.height],
+			nearest: false,
+		};
+
+		let isolated = if self.isolate_comp {
+			self.selected_comp_layer(selected)
+		} else {
+			None
+		};
+
+		let time = if isolated.is_some() {
+			self.isolated_time
+		} else {
+			self.current_time
+		};
+		// The backdrop layer may have been removed since it was chosen
+		let backdrop_path = self
+			.backdrop
+			.as_ref()
+			.and_then(|backdrop| self.root.path_of(backdrop));
+		if backdrop_path.is_none() {
+			self.backdrop = None;
+			self.blend_backdrop = false;
+		}
+
+		// Layers at the given paths below the root, bypassing the cache as they are not the full scene
+		let backdrop =
+			if self.blend_backdrop && isolated.is_none() && self.selected_layer(selected).is_some()
+			{
+				backdrop_path
+			} else {
+				None
+			};
+		if let Some(backdrop) = backdrop {
+			self.root.display_nested(
+				&backdrop,
+				mat,
+				time,
+				1.0,
+				self.display_placeholders,
+				&mut videos,
+			);
+			self.root.display_nested(
+				&selected[2..],
+				mat,
+				time,
+				1.0,
+				self.display_placeholders,
+				&mut videos,
+			);
+		} else {
+			let key = (time * 100.0).round() as i64;
+
+			if !self.frame_cache.contains_key(&key) && self.frame_cache.len() >= FRAME_CACHE_SIZE {
+				self.frame_cache.clear();
+			}
+			let entries = if isolated.is_some() {
+				1
+			} else {
+				self.root.layers.len()
+			};
+			let cached = self
+				.frame_cache
+				.entry(key)
+				.or_insert_with(|| vec![None; entries]);
+			if cached.len() != entries {
+				*cached = vec![None; entries];
+			}
+
+			if let Some(layer) = &isolated {
+				if cached[0].is_none() {
+					let layer = layer.try_lock().unwrap();
+					if let AetItemNode::Comp(comp) = &layer.item {
+						comp.display(mat, time, 1.0, self.display_placeholders, &mut videos);
+					}
+					cached[0] = Some(std::mem::take(&mut videos.videos));
+				}
+			} else {
+				for (i, layer) in self.root.layers.iter().enumerate() {
+					if cached[i].is_some() {
+						continue;
+					}
+					AetCompNode::display_layer(
+						&layer.try_lock().unwrap(),
+						mat,
+						time,
+						1.0,
+						self.display_placeholders,
+						&mut videos,
+					);
+					cached[i] = Some(std::mem::take(&mut videos.videos));
+				}
+			}
+			videos.videos = cached.iter().rev().flatten().flatten().cloned().collect();
+
+			if !self.scrub_cache {
+				self.frame_cache.clear();
+			}
+		}
+
+		// Stable so layers at the same depth keep their list order
+		if self.depth_sort {
+			videos
+				.videos
+				.sort_by(|a, b| b.depth().total_cmp(&a.depth()));
+		}
 
 		let w = rect.max.x - rect.min.x;
 		let h = rect.max.y - rect.min.y;
 		let ar = w / h;
-		let rect = if ar > self.width as f32 / self.height as f32 {
+		let fitted = if ar > self.width as f32 / self.height as f32 {
 			let adjusted_w = h / self.height as f32 * self.width as f32;
 			let remaining_w = w - adjusted_w;
 			egui::Rect {
@@ -573,10 +1512,64 @@ impl AetSceneNode {
 			}
 		};
 
+		// The largest integer scale up to the chosen one that fits, otherwise the fitted size
+		let ppp = ui.ctx().pixels_per_point();
+		let integer_scale = self.pixel_scale.and_then(|scale| {
+			(1..=scale).rev().find(|scale| {
+				(self.width * scale) as f32 / ppp <= w && (self.height * scale) as f32 / ppp <= h
+			})
+		});
+		let rect = if let Some(scale) = integer_scale {
+			let size = egui::vec2((self.width * scale) as f32, (self.height * scale) as f32) / ppp;
+			// Aligned to physical pixels so every sprite pixel covers the same amount of them
+			let min = rect.center() - size / 2.0;
+			let min = egui::pos2((min.x * ppp).round() / ppp, (min.y * ppp).round() / ppp);
+			egui::Rect::from_min_size(min, size)
+		} else {
+			fitted
+		};
+
+		if integer_scale.is_some() {
+			videos.nearest = true;
+		} else {
+			// Composited at the size it is shown at so it stays sharp
+			let pixels = rect.size() * ppp;
+			videos.target_size = [pixels.x.round() as u32, pixels.y.round() as u32];
+		}
 		ui.painter()
 			.add(egui_wgpu::Callback::new_paint_callback(rect, videos));
 
-		if selected.len() >= 3 {
+		if let Some((text, until)) =
+			ui.data(|data| data.get_temp::<(String, f64)>(egui::Id::new(KEY_NOTICE)))
+		{
+			let now = ui.input(|input| input.time);
+			if now < until {
+				let galley = ui.painter().layout_no_wrap(
+					text,
+					egui::FontId::proportional(13.0),
+					egui::Color32::WHITE,
+				);
+				let notice_rect = egui::Rect::from_min_size(
+					rect.left_bottom() + egui::vec2(4.0, -galley.size().y - 12.0),
+					galley.size() + egui::vec2(8.0, 8.0),
+				);
+				ui.painter().rect_filled(
+					notice_rect,
+					4.0,
+					ui.visuals().extreme_bg_color.gamma_multiply(0.8),
+				);
+				ui.painter().galley(
+					notice_rect.min + egui::vec2(4.0, 4.0),
+					galley,
+					egui::Color32::WHITE,
+				);
+				ui.ctx().request_repaint_after_secs((until - now) as f32);
+			} else {
+				ui.data_mut(|data| data.remove_temp::<(String, f64)>(egui::Id::new(KEY_NOTICE)));
+			}
+		}
+
+		if selected.len() >= 3 && isolated.is_none() {
 			let mut frame = self.current_time;
 			let mut translation = [0.0; 3];
 			let mut rotation = [0.0; 3];
@@ -586,6 +1579,10 @@ impl AetSceneNode {
 				translation[1] = self.height as f64 / 2.0;
 			}
 
+			let show_hierarchy = self.show_hierarchy;
+			let scene_size = [self.width as f64, self.height as f64];
+			let mut hierarchy = Vec::new();
+
 			if let Some(video) = &self.root.layers[selected[2]].try_lock().unwrap().video {
 				translation[0] += scale[0] * video.pos_x.interpolate(frame) as f64;
 				translation[1] += scale[1] * video.pos_y.interpolate(frame) as f64;
@@ -613,6 +1610,15 @@ impl AetSceneNode {
 				}
 				rotation[2] += video.rot_z.interpolate(frame).to_radians() as f64;
 			}
+			if show_hierarchy {
+				hierarchy.push(HierarchyLevel::new(
+					&self.root.layers[selected[2]].try_lock().unwrap(),
+					frame,
+					translation,
+					scale,
+					scene_size,
+				));
+			}
 
 			let selected =
 				selected
@@ -623,6 +1629,9 @@ impl AetSceneNode {
 						let AetItemNode::Comp(comp) = &layer.item else {
 							panic!()
 						};
+						// Children are timed inside their composition, the selected layer's own
+						// remap is left out so frame ends as the time its keys are on
+						frame = (frame - layer.start_time) * layer.time_scale + layer.offset_time;
 
 						let layer = comp.layers[*i].try_lock().unwrap();
 
@@ -684,10 +1693,54 @@ impl AetSceneNode {
 							rotation[2] += video.rot_z.interpolate(frame).to_radians() as f64;
 						}
 
-						frame = (frame - layer.start_time) * layer.time_scale + layer.offset_time;
+						if show_hierarchy {
+							hierarchy.push(HierarchyLevel::new(
+								&layer,
+								frame,
+								translation,
+								scale,
+								scene_size,
+							));
+						}
+
 						comp.layers[*i].clone()
 					});
 
+			// Translation is the layer's origin in scene units here, rotation is not accounted for
+			let size = match &selected.try_lock().unwrap().item {
+				AetItemNode::Video(video) => Some([
+					video.width as f64 * scale[0],
+					video.height as f64 * scale[1],
+				]),
+				_ => None,
+			};
+			let mut text = format!("Position {:.1}, {:.1}", translation[0], translation[1]);
+			if let Some(size) = size {
+				text += &format!("\nSize {:.1} x {:.1}", size[0], size[1]);
+			}
+			text += &format!("\nScale {:.2}, {:.2}", scale[0], scale[1]);
+			let galley = ui.painter().layout_no_wrap(
+				text,
+				egui::FontId::monospace(12.0),
+				ui.visuals().text_color(),
+			);
+			let info_rect = egui::Rect::from_min_size(
+				rect.left_top() + egui::vec2(4.0, 4.0),
+				galley.size() + egui::vec2(8.0, 8.0),
+			);
+			ui.painter().rect_filled(
+				info_rect,
+				4.0,
+				ui.visuals().extreme_bg_color.gamma_multiply(0.8),
+			);
+			ui.painter().galley(
+				info_rect.min + egui::vec2(4.0, 4.0),
+				galley,
+				egui::Color32::WHITE,
+			);
+
+			self.draw_hierarchy(ui, rect, &hierarchy);
+
 			if let Some(video) = &mut selected.try_lock().unwrap().video {
 				translation[0] += video.anchor_x.interpolate(frame) as f64 * scale[0];
 				translation[1] += video.anchor_y.interpolate(frame) as f64 * scale[1];
@@ -702,7 +1755,12 @@ impl AetSceneNode {
 					])
 					.into(),
 					viewport: rect,
-					modes: GizmoMode::TranslateX | GizmoMode::TranslateY | GizmoMode::RotateZ,
+					modes: GizmoMode::TranslateX
+						| GizmoMode::TranslateY
+						| GizmoMode::RotateZ
+						| GizmoMode::ScaleX
+						| GizmoMode::ScaleY
+						| GizmoMode::ScaleUniform,
 					snapping: true,
 					snap_distance: 5.0,
 					..Default::default()
@@ -720,25 +1778,29 @@ impl AetSceneNode {
 						translation,
 					);
 
-				if let Some((result, _)) = self.gizmo.interact(ui, &[transform]) {
+				if let Some((result, transforms)) = self.gizmo.interact(ui, &[transform]) {
+					// Curves without keys get their first one where the user is working
+					let mut created = Vec::new();
 					match result {
 						GizmoResult::Translation { delta, total: _ } => {
 							if video.pos_x.keys.is_empty() {
 								video.pos_x.keys.push(aet::FCurveKey {
-									frame: 0.0,
+									frame,
 									value: 0.0,
 									tangent: 0.0,
 								});
+								created.push("Pos X");
 							}
 							for key in &mut video.pos_x.keys {
 								key.value += delta.x as f32;
 							}
 							if video.pos_y.keys.is_empty() {
 								video.pos_y.keys.push(aet::FCurveKey {
-									frame: 0.0,
+									frame,
 									value: 0.0,
 									tangent: 0.0,
 								});
+								created.push("Pos Y");
 							}
 							for key in &mut video.pos_y.keys {
 								key.value += -delta.y as f32;
@@ -753,10 +1815,11 @@ impl AetSceneNode {
 							if axis.z == 1.0 {
 								if video.rot_z.keys.is_empty() {
 									video.rot_z.keys.push(aet::FCurveKey {
-										frame: 0.0,
+										frame,
 										value: 0.0,
 										tangent: 0.0,
 									});
+									created.push("Rot Z");
 								}
 
 								for key in &mut video.rot_z.keys {
@@ -764,8 +1827,46 @@ impl AetSceneNode {
 								}
 							}
 						}
+						// The new scale over the current one is the same for the layer alone as
+						// with its parents' scale included
+						GizmoResult::Scale { total: _ } => {
+							let new_scale = transforms[0].scale;
+							for (curve, name, new, old) in [
+								(&mut video.scale_x, "Scale X", new_scale.x, scale[0]),
+								(&mut video.scale_y, "Scale Y", new_scale.y, scale[1]),
+							] {
+								if old.abs() < f64::EPSILON || new == old {
+									continue;
+								}
+								if curve.keys.is_empty() {
+									curve.keys.push(aet::FCurveKey {
+										frame,
+										value: 1.0,
+										tangent: 0.0,
+									});
+									created.push(name);
+								}
+								// Tangents are slopes so they scale along with the values
+								for key in &mut curve.keys {
+									key.value *= (new / old) as f32;
+									key.tangent *= (new / old) as f32;
+								}
+							}
+						}
 						_ => {}
 					}
+
+					if !created.is_empty() {
+						let text = format!(
+							"Created the first {} key at frame {:.0}",
+							created.join(" and "),
+							frame
+						);
+						let until = ui.input(|input| input.time) + KEY_NOTICE_SECONDS;
+						ui.data_mut(|data| {
+							data.insert_temp(egui::Id::new(KEY_NOTICE), (text, until))
+						});
+					}
 				}
 			}
 		}
@@ -773,6 +1874,195 @@ impl AetSceneNode {
 }
 
 impl AetSceneNode {
+	// Each level gets its own hue, origins are joined from the outermost composition inwards
+	fn draw_hierarchy(&self, ui: &egui::Ui, rect: egui::Rect, hierarchy: &[HierarchyLevel]) {
+		let painter = ui.painter_at(rect);
+		let to_screen = |pos: [f64; 2]| {
+			rect.min
+				+ egui::vec2(
+					pos[0] as f32 / self.width as f32 * rect.width(),
+					pos[1] as f32 / self.height as f32 * rect.height(),
+				)
+		};
+
+		let mut previous = None;
+		for (i, level) in hierarchy.iter().enumerate() {
+			let color: egui::Color32 =
+				egui::ecolor::Hsva::new((i as f32 * 0.15).fract(), 0.8, 1.0, 1.0).into();
+			let stroke = egui::Stroke::new(1.0, color);
+			let origin = to_screen(level.origin);
+
+			if let Some(size) = level.size {
+				let min = to_screen(level.top_left);
+				let max = to_screen([level.top_left[0] + size[0], level.top_left[1] + size[1]]);
+				painter.rect_stroke(
+					egui::Rect::from_two_pos(min, max),
+					0.0,
+					stroke,
+					egui::StrokeKind::Middle,
+				);
+			}
+
+			painter.line_segment(
+				[origin - egui::vec2(6.0, 0.0), origin + egui::vec2(6.0, 0.0)],
+				stroke,
+			);
+			painter.line_segment(
+				[origin - egui::vec2(0.0, 6.0), origin + egui::vec2(0.0, 6.0)],
+				stroke,
+			);
+			painter.text(
+				origin + egui::vec2(4.0, 4.0),
+				egui::Align2::LEFT_TOP,
+				&level.name,
+				egui::FontId::proportional(11.0),
+				color,
+			);
+
+			if let Some(previous) = previous {
+				painter.extend(egui::Shape::dashed_line(
+					&[previous, origin],
+					stroke,
+					4.0,
+					4.0,
+				));
+			}
+			previous = Some(origin);
+		}
+	}
+
+	pub fn selected_layer(&self, selected: &[usize]) -> Option<Rc<Mutex<AetLayerNode>>> {
+		if selected.len() < 3 || selected[0] != 0 {
+			return None;
+		}
+
+		let mut layer = self.root.layers.get(selected[2])?.clone();
+		for i in selected.iter().skip(3) {
+			let child = {
+				let lock = layer.try_lock().unwrap();
+				let AetItemNode::Comp(comp) = &lock.item else {
+					return None;
+				};
+				comp.layers.get(*i)?.clone()
+			};
+			layer = child;
+		}
+
+		Some(layer)
+	}
+
+	// Returns the selected layer if it holds a composition
+	pub fn selected_comp_layer(&self, selected: &[usize]) -> Option<Rc<Mutex<AetLayerNode>>> {
+		let layer = self.selected_layer(selected)?;
+		if let AetItemNode::Comp(_) = &layer.try_lock().unwrap().item {
+			Some(layer.clone())
+		} else {
+			None
+		}
+	}
+
+	// Loop markers of the previewed composition, the isolated one if any
+	pub fn loop_range(&self, selected: &[usize]) -> Option<(f32, f32)> {
+		let isolated = if self.isolate_comp {
+			self.selected_comp_layer(selected)
+		} else {
+			None
+		};
+
+		let Some(layer) = isolated else {
+			return self.root.loop_markers();
+		};
+		let layer = layer.try_lock().unwrap();
+		let AetItemNode::Comp(comp) = &layer.item else {
+			return None;
+		};
+		comp.loop_markers()
+	}
+
+	// Audio layers heard at the current time, including those in nested compositions
+	#[cfg(feature = "audio")]
+	pub fn sounds_at(&self) -> Vec<SoundAt> {
+		let mut sounds = Vec::new();
+		self.root.collect_sounds(self.current_time, &mut sounds);
+		sounds
+	}
+
+	// Key frames on the previewed timeline, those of the selected layer (and curve) if any
+	// An isolated composition or nothing being selected gives the keys of every layer shown
+	pub fn key_times(&self, selected: &[usize]) -> Vec<f32> {
+		let isolated = if self.isolate_comp {
+			self.selected_comp_layer(selected)
+		} else {
+			None
+		};
+
+		if let Some(layer) = isolated {
+			let layer = layer.try_lock().unwrap();
+			let AetItemNode::Comp(comp) = &layer.item else {
+				return Vec::new();
+			};
+			return comp
+				.layers
+				.iter()
+				.flat_map(|layer| layer.try_lock().unwrap().key_frames(None))
+				.collect();
+		}
+
+		if selected.len() < 3 || selected[0] != 0 {
+			return self
+				.root
+				.layers
+				.iter()
+				.flat_map(|layer| layer.try_lock().unwrap().key_frames(None))
+				.collect();
+		}
+
+		let Some(mut layer) = self.root.layers.get(selected[2]).cloned() else {
+			return Vec::new();
+		};
+		let mut parents = Vec::new();
+		for i in selected.iter().skip(3) {
+			let child = {
+				let lock = layer.try_lock().unwrap();
+				let AetItemNode::Comp(comp) = &lock.item else {
+					return Vec::new();
+				};
+				let Some(child) = comp.layers.get(*i) else {
+					return Vec::new();
+				};
+				child.clone()
+			};
+			parents.push(layer);
+			layer = child;
+		}
+
+		let mut times = layer.try_lock().unwrap().key_frames(self.selected_curve);
+		// Undo each composition's time remapping from the innermost outwards
+		for parent in parents.iter().rev() {
+			let parent = parent.try_lock().unwrap();
+			if parent.time_scale == 0.0 {
+				return Vec::new();
+			}
+			for time in &mut times {
+				*time = (*time - parent.offset_time) / parent.time_scale + parent.start_time;
+			}
+		}
+		times
+	}
+
+	pub fn isolated_duration(&self, selected: &[usize]) -> Option<f32> {
+		if !self.isolate_comp {
+			return None;
+		}
+
+		let layer = self.selected_comp_layer(selected)?;
+		let layer = layer.try_lock().unwrap();
+		let AetItemNode::Comp(comp) = &layer.item else {
+			return None;
+		};
+		Some(comp.duration())
+	}
+
 	pub fn update_from(&mut self, other: &Self) {
 		self.name = other.name.clone();
 		self.start_time = other.start_time;
@@ -782,6 +2072,7 @@ impl AetSceneNode {
 		self.width = other.width;
 		self.height = other.height;
 		self.camera = other.camera.clone();
+		self.note = other.note.clone();
 
 		if self.root.layers.len() == other.root.layers.len() {
 			for (a, b) in self.root.layers.iter_mut().zip(other.root.layers.iter()) {
@@ -793,6 +2084,55 @@ impl AetSceneNode {
 	}
 }
 
+// Set by a layer's context menu, taken by the scene it is in
+const SET_BACKDROP: &str = "SetBackdrop";
+
+// Shown over the preview when the gizmo gives a curve its first key
+const KEY_NOTICE: &str = "GizmoKeyNotice";
+const KEY_NOTICE_SECONDS: f64 = 2.5;
+
+// A layer of the selected path in scene units, rotation is not accounted for
+struct HierarchyLevel {
+	name: String,
+	origin: [f64; 2],
+	top_left: [f64; 2],
+	size: Option<[f64; 2]>,
+}
+
+impl HierarchyLevel {
+	// Translation is expected to already have the layer's anchor subtracted
+	fn new(
+		layer: &AetLayerNode,
+		frame: f32,
+		translation: [f64; 3],
+		scale: [f64; 3],
+		scene_size: [f64; 2],
+	) -> Self {
+		let anchor = layer.video.as_ref().map_or([0.0, 0.0], |video| {
+			[
+				video.anchor_x.interpolate(frame) as f64,
+				video.anchor_y.interpolate(frame) as f64,
+			]
+		});
+		// Compositions span the whole scene
+		let size = match &layer.item {
+			AetItemNode::Video(video) => Some([video.width as f64, video.height as f64]),
+			AetItemNode::Comp(_) => Some(scene_size),
+			_ => None,
+		};
+
+		Self {
+			name: layer.name.clone(),
+			origin: [
+				translation[0] + anchor[0] * scale[0],
+				translation[1] + anchor[1] * scale[1],
+			],
+			top_left: [translation[0], translation[1]],
+			size: size.map(|size| [size[0] * scale[0], size[1] * scale[1]]),
+		}
+	}
+}
+
 pub fn calc_mat(m: &mut Mat4, opacity: &mut f32, video: &aet::LayerVideo, frame: f32) {
 	let mut pos = [0.0; 3];
 	let mut scale = [1.0; 3];
@@ -888,43 +2228,274 @@ impl PartialEq for AetCompNode {
 }
 
 impl AetCompNode {
-	fn create(
-		comp: &aet::Composition,
-	) -> (Self, Vec<(Rc<Mutex<aet::Layer>>, Rc<Mutex<AetLayerNode>>)>) {
-		let mut map = Vec::new();
-		let layers = comp
+	// Copies the layers themselves instead of sharing them, parents within the comp are remapped to the copies
+	pub fn deep_clone(&self) -> Self {
+		let layers = self
 			.layers
 			.iter()
-			.map(|layer_rc| {
-				let layer = layer_rc.try_lock().unwrap();
-				let item = match &layer.item {
-					aet::Item::None => AetItemNode::None,
-					aet::Item::Video(video) => AetItemNode::Video(AetVideoNode {
-						color: video.color,
-						width: video.width,
-						height: video.height,
-						fpf: video.fpf,
-						sources: video
-							.sources
-							.iter()
-							.map(|source| AetVideoSourceNode {
-								name: source.name.clone(),
-								id: source.id,
-								sprite: None,
-							})
-							.collect(),
-					}),
-					aet::Item::Audio(audio) => AetItemNode::Audio(AetAudioNode {
-						sound_index: audio.sound_index,
-					}),
-					aet::Item::Composition(comp) => {
-						let (comp, new_map) = Self::create(comp);
-						map.extend(new_map);
-						AetItemNode::Comp(comp)
-					}
-				};
+			.map(|layer| Rc::new(Mutex::new(layer.try_lock().unwrap().deep_clone())))
+			.collect::<Vec<_>>();
 
-				let rc = Rc::new(Mutex::new(AetLayerNode {
+		for layer in &layers {
+			let mut layer = layer.try_lock().unwrap();
+			let Some(parent) = &layer.parent else {
+				continue;
+			};
+			if let Some(i) = self.layers.iter().position(|old| Rc::ptr_eq(old, parent)) {
+				layer.parent = Some(layers[i].clone());
+			}
+		}
+
+		Self { layers }
+	}
+
+	fn apply_duplicates(&mut self) {
+		for i in (0..self.layers.len()).rev() {
+			let layer = self.layers[i].try_lock().unwrap();
+			if !layer.want_duplicate {
+				continue;
+			}
+
+			let Some(offset) = layer.duplicate_offset else {
+				drop(layer);
+				self.layers.insert(i, self.layers[i].clone());
+				continue;
+			};
+
+			let copies = (1..=offset.count)
+				.map(|n| {
+					let mut copy = layer.deep_clone();
+					copy.apply_offset(&offset, n as f32);
+					Rc::new(Mutex::new(copy))
+				})
+				.collect::<Vec<_>>();
+			drop(layer);
+			self.layers.splice(i + 1..i + 1, copies);
+		}
+
+		for layer in &mut self.layers {
+			let mut layer = layer.try_lock().unwrap();
+			layer.want_duplicate = false;
+			layer.duplicate_offset = None;
+		}
+	}
+
+	#[cfg(feature = "audio")]
+	fn collect_sound_indices(&self, indices: &mut Vec<u32>) {
+		for layer in &self.layers {
+			match &layer.try_lock().unwrap().item {
+				AetItemNode::Audio(audio) => indices.push(audio.sound_index),
+				AetItemNode::Comp(comp) => comp.collect_sound_indices(indices),
+				_ => {}
+			}
+		}
+	}
+
+	#[cfg(feature = "audio")]
+	fn collect_sounds(&self, frame: f32, sounds: &mut Vec<SoundAt>) {
+		for layer in &self.layers {
+			let key = Rc::as_ptr(layer) as usize;
+			let layer = layer.try_lock().unwrap();
+			if frame < layer.start_time || frame >= layer.end_time || !layer.visible {
+				continue;
+			}
+
+			let local_frame = (frame - layer.start_time) * layer.time_scale + layer.offset_time;
+			match &layer.item {
+				AetItemNode::Audio(audio) => sounds.push(SoundAt {
+					key,
+					sound_index: audio.sound_index,
+					frame: local_frame,
+					volume: layer.audio.as_ref().map_or([1.0, 1.0], |audio| {
+						[
+							audio.volume_l.interpolate(frame),
+							audio.volume_r.interpolate(frame),
+						]
+					}),
+				}),
+				AetItemNode::Comp(comp) => comp.collect_sounds(local_frame, sounds),
+				_ => {}
+			}
+		}
+	}
+
+	// Visits the sources of every video layer, including those in nested compositions
+	fn for_each_source(&self, f: &mut dyn FnMut(&str, &mut AetVideoSourceNode)) {
+		for layer in &self.layers {
+			let mut layer = layer.try_lock().unwrap();
+			let layer = &mut *layer;
+			match &mut layer.item {
+				AetItemNode::Video(video) => {
+					for source in &mut video.sources {
+						f(&layer.name, source);
+					}
+				}
+				AetItemNode::Comp(comp) => comp.for_each_source(f),
+				_ => {}
+			}
+		}
+	}
+
+	// Markers named LOOP_IN and LOOP_OUT on any direct child, in the composition's time
+	pub fn loop_markers(&self) -> Option<(f32, f32)> {
+		let mut loop_in = None;
+		let mut loop_out = None;
+		for layer in &self.layers {
+			let layer = layer.try_lock().unwrap();
+			for (name, time) in &layer.markers {
+				if name.eq_ignore_ascii_case(LOOP_IN) {
+					loop_in = Some(*time);
+				} else if name.eq_ignore_ascii_case(LOOP_OUT) {
+					loop_out = Some(*time);
+				}
+			}
+		}
+
+		match (loop_in, loop_out) {
+			(Some(loop_in), Some(loop_out)) if loop_in < loop_out => Some((loop_in, loop_out)),
+			_ => None,
+		}
+	}
+
+	// Moves the marked layer and the requested number of following siblings into a new composition
+	fn apply_precompose(&mut self) {
+		let Some(i) = self
+			.layers
+			.iter()
+			.position(|layer| layer.try_lock().unwrap().precompose.is_some())
+		else {
+			return;
+		};
+
+		let mut first = self.layers[i].try_lock().unwrap();
+		let count = first.precompose.take().unwrap_or(0) as usize;
+		let sprites = first.sprites.clone();
+		let visible = first.visible;
+		drop(first);
+
+		let end = (i + count + 1).min(self.layers.len());
+		let layers = self.layers.drain(i..end).collect::<Vec<_>>();
+		let start_time = layers
+			.iter()
+			.map(|layer| layer.try_lock().unwrap().start_time)
+			.fold(f32::MAX, f32::min);
+		let end_time = layers
+			.iter()
+			.map(|layer| layer.try_lock().unwrap().end_time)
+			.fold(f32::MIN, f32::max);
+
+		// Parents can't be in another composition, links across the new one are dropped
+		let moved = |parent: &Option<Rc<Mutex<AetLayerNode>>>| {
+			parent
+				.as_ref()
+				.is_some_and(|parent| layers.iter().any(|layer| Rc::ptr_eq(layer, parent)))
+		};
+		for layer in &self.layers {
+			let mut layer = layer.try_lock().unwrap();
+			if moved(&layer.parent) {
+				layer.parent = None;
+			}
+		}
+		for layer in &layers {
+			let mut layer = layer.try_lock().unwrap();
+			if layer.parent.is_some() && !moved(&layer.parent) {
+				layer.parent = None;
+			}
+		}
+
+		let constant = |value: f32| aet::FCurve {
+			keys: vec![aet::FCurveKey {
+				frame: 0.0,
+				value,
+				tangent: 0.0,
+			}],
+		};
+
+		// Offsetting by the start keeps the time inside the composition equal to the parent's,
+		// so the moved layers keep their times and curves
+		let layer = AetLayerNode {
+			name: String::from("Pre-comp"),
+			start_time,
+			end_time,
+			offset_time: start_time,
+			time_scale: 1.0,
+			flags: aet::LayerFlags::new().with_video_active(true),
+			quality: aet::LayerQuality::Best,
+			item: AetItemNode::Comp(AetCompNode { layers }),
+			markers: Vec::new(),
+			video: Some(aet::LayerVideo {
+				transfer_mode: aet::TransferMode {
+					mode: aet::BlendMode::Normal,
+					flag: 0,
+					matte: 0,
+				},
+				anchor_x: aet::FCurve { keys: Vec::new() },
+				anchor_y: aet::FCurve { keys: Vec::new() },
+				pos_x: aet::FCurve { keys: Vec::new() },
+				pos_y: aet::FCurve { keys: Vec::new() },
+				rot_z: aet::FCurve { keys: Vec::new() },
+				scale_x: constant(1.0),
+				scale_y: constant(1.0),
+				opacity: constant(1.0),
+				_3d: None,
+			}),
+			parent: None,
+			audio: None,
+			note: String::new(),
+			sprites,
+			visible,
+			selected_key: 0,
+			selected_keys: Vec::new(),
+			want_deletion: false,
+			want_duplicate: false,
+			precompose: None,
+			duplicate_offset: None,
+			dragging_tangent: false,
+			dragging_key: false,
+			error: None,
+			curve_file_picker_result: None,
+		};
+		self.layers.insert(i, Rc::new(Mutex::new(layer)));
+	}
+
+	fn create(
+		comp: &aet::Composition,
+	) -> (Self, Vec<(Rc<Mutex<aet::Layer>>, Rc<Mutex<AetLayerNode>>)>) {
+		let mut map = Vec::new();
+		let layers = comp
+			.layers
+			.iter()
+			.map(|layer_rc| {
+				let layer = layer_rc.try_lock().unwrap();
+				// kkdlib only models these item kinds, anything else is already None here
+				let item = match &layer.item {
+					aet::Item::None => AetItemNode::None,
+					aet::Item::Video(video) => AetItemNode::Video(AetVideoNode {
+						color: video.color,
+						width: video.width,
+						height: video.height,
+						fpf: video.fpf,
+						sources: video
+							.sources
+							.iter()
+							.map(|source| AetVideoSourceNode {
+								name: source.name.clone(),
+								id: source.id,
+								sprite: None,
+							})
+							.collect(),
+					}),
+					aet::Item::Audio(audio) => AetItemNode::Audio(AetAudioNode {
+						sound_index: audio.sound_index,
+					}),
+					aet::Item::Composition(comp) => {
+						let (comp, new_map) = Self::create(comp);
+						map.extend(new_map);
+						AetItemNode::Comp(comp)
+					}
+				};
+
+				let rc = Rc::new(Mutex::new(AetLayerNode {
 					name: layer.name.clone(),
 					start_time: layer.start_time,
 					end_time: layer.end_time,
@@ -937,14 +2508,22 @@ impl AetCompNode {
 					video: layer.video.clone(),
 					parent: unsafe { std::mem::transmute(layer.parent.clone()) },
 					audio: layer.audio.clone(),
+					note: String::new(),
 
 					sprites: Rc::new(Mutex::new(Vec::new())),
 
-					visible: layer.flags.video_active(),
+					visible: true,
 					selected_key: 0,
+					selected_keys: Vec::new(),
 
 					want_deletion: false,
 					want_duplicate: false,
+					precompose: None,
+					duplicate_offset: None,
+					dragging_tangent: false,
+					dragging_key: false,
+					error: None,
+					curve_file_picker_result: None,
 				}));
 
 				map.push((layer_rc.clone(), rc.clone()));
@@ -955,6 +2534,13 @@ impl AetCompNode {
 		(Self { layers }, map)
 	}
 
+	pub fn duration(&self) -> f32 {
+		self.layers
+			.iter()
+			.map(|layer| layer.try_lock().unwrap().end_time)
+			.fold(0.0, f32::max)
+	}
+
 	pub fn get_sprite_id(&self) -> Option<u32> {
 		for layer in &self.layers {
 			let layer = layer.try_lock().unwrap();
@@ -1025,92 +2611,187 @@ impl AetCompNode {
 		videos: &mut WgpuAetVideos,
 	) {
 		for layer in self.layers.iter().rev() {
-			let layer = layer.try_lock().unwrap();
-			if frame < layer.start_time
-				|| frame >= layer.end_time
-				|| !layer.flags.video_active()
-				|| !layer.visible
-			{
-				continue;
-			}
+			Self::display_layer(
+				&layer.try_lock().unwrap(),
+				mat,
+				frame,
+				opacity,
+				display_placeholders,
+				videos,
+			);
+		}
+	}
 
-			let mut m = mat;
-			let mut opacity = opacity;
-			if let Some(parent) = &layer.parent
-				&& let Some(video) = &parent.try_lock().unwrap().video
-			{
-				calc_mat(&mut m, &mut opacity, video, frame);
+	// Indices from this composition down to the layer, if it is in it or a nested one
+	pub fn path_of(&self, target: &Rc<Mutex<AetLayerNode>>) -> Option<Vec<usize>> {
+		for (i, layer) in self.layers.iter().enumerate() {
+			if Rc::ptr_eq(layer, target) {
+				return Some(vec![i]);
 			}
-			if let Some(video) = &layer.video {
-				calc_mat(&mut m, &mut opacity, video, frame);
+			if let AetItemNode::Comp(comp) = &layer.try_lock().unwrap().item
+				&& let Some(mut path) = comp.path_of(target)
+			{
+				path.insert(0, i);
+				return Some(path);
 			}
+		}
+		None
+	}
 
-			match &layer.item {
-				AetItemNode::None => {}
-				AetItemNode::Video(video) => {
-					let Some(source) = video.sources.first() else {
-						if display_placeholders {
-							videos.videos.push(WgpuAetVideo {
-								is_ycbcr: false,
-								is_empty: true,
-								texture_coords: [0.0, 0.0, 0.0, 0.0],
-								source_size: [video.width as f32, video.height as f32],
-								texture_index: 0,
-								mat: m,
-								color: [
-									video.color[0] as f32 / 255.0,
-									video.color[1] as f32 / 255.0,
-									video.color[2] as f32 / 255.0,
-									opacity,
-								],
-								blend_mode: layer
-									.video
-									.as_ref()
-									.map_or(aet::BlendMode::Normal, |video| {
-										video.transfer_mode.mode
-									}),
-							});
-						}
-						continue;
-					};
-					let Some(sprite) = &source.sprite else {
-						continue;
-					};
+	// Only the layer at path, with the transforms and timing of the compositions it sits in
+	fn display_nested(
+		&self,
+		path: &[usize],
+		mat: Mat4,
+		frame: f32,
+		opacity: f32,
+		display_placeholders: bool,
+		videos: &mut WgpuAetVideos,
+	) {
+		let Some((index, rest)) = path.split_first() else {
+			return;
+		};
+		let Some(layer) = self.layers.get(*index) else {
+			return;
+		};
+		let layer = layer.try_lock().unwrap();
+		if rest.is_empty() {
+			Self::display_layer(&layer, mat, frame, opacity, display_placeholders, videos);
+			return;
+		}
 
-					let sprite = sprite.try_lock().unwrap();
-					let texture = sprite.texture.try_lock().unwrap();
-					let mip = texture.texture.get_mipmap(0, 0).unwrap();
-					let x = sprite.info.px() / mip.width() as f32;
-					let y = (mip.height() as f32 - sprite.info.py() - sprite.info.height())
-						/ mip.height() as f32;
-					let w = (sprite.info.px() + sprite.info.width()) / mip.width() as f32;
-					let h = (mip.height() as f32 - sprite.info.py()) / mip.height() as f32;
-
-					let video = WgpuAetVideo {
-						is_ycbcr: texture.texture.is_ycbcr(),
-						is_empty: false,
-						texture_coords: [x, y, w, h],
-						source_size: [video.width as f32, video.height as f32],
-						texture_index: sprite.info.texid() as usize,
-						mat: m,
-						color: [1.0, 1.0, 1.0, opacity],
-						blend_mode: layer
-							.video
-							.as_ref()
-							.map_or(aet::BlendMode::Normal, |video| video.transfer_mode.mode),
-					};
+		if frame < layer.start_time || frame >= layer.end_time || !layer.flags.video_active() {
+			return;
+		}
+		let AetItemNode::Comp(comp) = &layer.item else {
+			return;
+		};
 
-					videos.videos.push(video);
-				}
-				AetItemNode::Audio(_) => {}
-				AetItemNode::Comp(comp) => comp.display(
-					m,
-					(frame - layer.start_time) * layer.time_scale + layer.offset_time,
-					opacity,
-					display_placeholders,
-					videos,
-				),
+		let mut m = mat;
+		let mut opacity = opacity;
+		if let Some(parent) = &layer.parent
+			&& let Some(video) = &parent.try_lock().unwrap().video
+		{
+			calc_mat(&mut m, &mut opacity, video, frame);
+		}
+		if let Some(video) = &layer.video {
+			calc_mat(&mut m, &mut opacity, video, frame);
+		}
+
+		comp.display_nested(
+			rest,
+			m,
+			(frame - layer.start_time) * layer.time_scale + layer.offset_time,
+			opacity,
+			display_placeholders,
+			videos,
+		);
+	}
+
+	fn display_layer(
+		layer: &AetLayerNode,
+		mat: Mat4,
+		frame: f32,
+		opacity: f32,
+		display_placeholders: bool,
+		videos: &mut WgpuAetVideos,
+	) {
+		if frame < layer.start_time
+			|| frame >= layer.end_time
+			|| !layer.flags.video_active()
+			|| !layer.visible
+		{
+			return;
+		}
+
+		let mut m = mat;
+		let mut opacity = opacity;
+		if let Some(parent) = &layer.parent
+			&& let Some(video) = &parent.try_lock().unwrap().video
+		{
+			calc_mat(&mut m, &mut opacity, video, frame);
+		}
+		if let Some(video) = &layer.video {
+			calc_mat(&mut m, &mut opacity, video, frame);
+		}
+
+		match &layer.item {
+			AetItemNode::None => {}
+			AetItemNode::Video(video) => {
+				let local_frame = (frame - layer.start_time) * layer.time_scale + layer.offset_time;
+				let Some(source) = video.source_at(local_frame) else {
+					if display_placeholders && video.has_size() {
+						videos.videos.push(WgpuAetVideo {
+							is_ycbcr: false,
+							is_hdr: false,
+							exposure: 0.0,
+							is_empty: true,
+							texture_coords: [0.0, 0.0, 0.0, 0.0],
+							source_size: [video.width as f32, video.height as f32],
+							texture_index: 0,
+							mat: m,
+							color: [
+								video.color[0] as f32 / 255.0,
+								video.color[1] as f32 / 255.0,
+								video.color[2] as f32 / 255.0,
+								opacity,
+							],
+							blend_mode: layer
+								.video
+								.as_ref()
+								.map_or(aet::BlendMode::Normal, |video| video.transfer_mode.mode),
+						});
+					}
+					return;
+				};
+				let Some(sprite) = &source.sprite else {
+					return;
+				};
+
+				let sprite = sprite.try_lock().unwrap();
+				let texture = sprite.texture.try_lock().unwrap();
+				let Some(mip) = texture.texture.get_mipmap(0, 0) else {
+					return;
+				};
+				let x = sprite.info.px() / mip.width() as f32;
+				let y = (mip.height() as f32 - sprite.info.py() - sprite.info.height())
+					/ mip.height() as f32;
+				let w = (sprite.info.px() + sprite.info.width()) / mip.width() as f32;
+				let h = (mip.height() as f32 - sprite.info.py()) / mip.height() as f32;
+
+				// Sizeless videos are drawn at their sprite's size instead
+				let source_size = if video.has_size() {
+					[video.width as f32, video.height as f32]
+				} else {
+					[sprite.info.width(), sprite.info.height()]
+				};
+
+				let video = WgpuAetVideo {
+					is_ycbcr: texture.texture.is_ycbcr(),
+					is_hdr: texture.is_hdr(),
+					exposure: texture.exposure,
+					is_empty: false,
+					texture_coords: [x, y, w, h],
+					source_size,
+					texture_index: sprite.info.texid() as usize,
+					mat: m,
+					color: [1.0, 1.0, 1.0, opacity],
+					blend_mode: layer
+						.video
+						.as_ref()
+						.map_or(aet::BlendMode::Normal, |video| video.transfer_mode.mode),
+				};
+
+				videos.videos.push(video);
 			}
+			AetItemNode::Audio(_) => {}
+			AetItemNode::Comp(comp) => comp.display(
+				m,
+				(frame - layer.start_time) * layer.time_scale + layer.offset_time,
+				opacity,
+				display_placeholders,
+				videos,
+			),
 		}
 	}
 
@@ -1302,6 +2983,31 @@ impl AetCompNode {
 	}
 }
 
+// Key of the sound index to name map in egui's persisted memory
+const SOUND_NAMES: &str = "SoundNames";
+
+// Indexed by TransferMode::matte, matching After Effects' track matte options
+const TRACK_MATTES: [&str; 5] = ["None", "Alpha", "Alpha inverted", "Luma", "Luma inverted"];
+
+#[derive(Clone, Copy)]
+pub struct DuplicateOffset {
+	pub count: u32,
+	pub time: f32,
+	pub x: f32,
+	pub y: f32,
+}
+
+impl Default for DuplicateOffset {
+	fn default() -> Self {
+		Self {
+			count: 1,
+			time: 0.0,
+			x: 0.0,
+			y: 0.0,
+		}
+	}
+}
+
 #[derive(Clone, Copy, PartialEq)]
 pub enum CurveType {
 	// Audio
@@ -1329,6 +3035,142 @@ pub enum CurveType {
 	ScaleZ,
 }
 
+impl CurveType {
+	pub fn name(&self) -> &'static str {
+		match self {
+			CurveType::VolumeL => "Volume L",
+			CurveType::VolumeR => "Volume R",
+			CurveType::PanL => "Pan L",
+			CurveType::PanR => "Pan R",
+			CurveType::AnchorX => "Anchor X",
+			CurveType::AnchorY => "Anchor Y",
+			CurveType::PosX => "Pos X",
+			CurveType::PosY => "Pos Y",
+			CurveType::RotZ => "Rot Z",
+			CurveType::ScaleX => "Scale X",
+			CurveType::ScaleY => "Scale Y",
+			CurveType::Opacity => "Opacity",
+			CurveType::AnchorZ => "Anchor Z",
+			CurveType::PosZ => "Pos Z",
+			CurveType::DirX => "Dir X",
+			CurveType::DirY => "Dir Y",
+			CurveType::DirZ => "Dir Z",
+			CurveType::RotX => "Rot X",
+			CurveType::RotY => "Rot Y",
+			CurveType::ScaleZ => "Scale Z",
+		}
+	}
+}
+
+// How a curve gets from a key to the next one. The aet format only stores a tangent per key,
+// so modes are written into the keys and read back from their shape
+#[derive(Clone, Copy, PartialEq)]
+pub enum InterpMode {
+	// Holds the value until one frame before the next key
+	Hold,
+	Linear,
+	Hermite,
+}
+
+impl InterpMode {
+	pub const ALL: [InterpMode; 3] = [InterpMode::Hold, InterpMode::Linear, InterpMode::Hermite];
+
+	pub fn name(&self) -> &'static str {
+		match self {
+			InterpMode::Hold => "Hold",
+			InterpMode::Linear => "Linear",
+			InterpMode::Hermite => "Hermite",
+		}
+	}
+
+	// Mode between key i and the next, None for the last key
+	// Holds are a flat segment ending in a helper key exactly one frame before a change of value,
+	// the step from the helper is flat on both ends too
+	pub fn of(keys: &[aet::FCurveKey], i: usize) -> Option<Self> {
+		let (key, next) = (keys.get(i)?, keys.get(i + 1)?);
+		let slope = slope(key, next);
+		if key.value == next.value
+			&& key.tangent == 0.0
+			&& next.tangent == 0.0
+			&& keys.get(i + 2).is_some_and(|after| {
+				after.frame - 1.0 == next.frame && after.value != next.value && after.tangent == 0.0
+			}) {
+			Some(InterpMode::Hold)
+		} else if (key.tangent - slope).abs() < 1e-4 && (next.tangent - slope).abs() < 1e-4 {
+			Some(InterpMode::Linear)
+		} else {
+			Some(InterpMode::Hermite)
+		}
+	}
+
+	// A hold needs room for its helper key and a change of value, otherwise it is a linear segment
+	pub fn available(&self, keys: &[aet::FCurveKey], i: usize) -> bool {
+		match self {
+			InterpMode::Hold => {
+				Self::of(keys, i) == Some(InterpMode::Hold)
+					|| keys.get(i).zip(keys.get(i + 1)).is_some_and(|(key, next)| {
+						next.frame - key.frame > 1.0 && next.value != key.value
+					})
+			}
+			InterpMode::Linear | InterpMode::Hermite => true,
+		}
+	}
+
+	// Tangents are shared by both sides of a key, so this also shapes the neighbouring segments
+	// Returns whether a hold's helper key was added (1) or removed (-1) at i + 1
+	pub fn apply(&self, keys: &mut Vec<aet::FCurveKey>, i: usize) -> isize {
+		if i + 1 >= keys.len() || Self::of(keys, i) == Some(*self) || !self.available(keys, i) {
+			return 0;
+		}
+		let mut added = 0;
+		// Only the helper key is removed, of guarantees it is the one holding the value
+		if Self::of(keys, i) == Some(InterpMode::Hold) {
+			keys.remove(i + 1);
+			added = -1;
+		}
+
+		match self {
+			InterpMode::Hold => {
+				let next = keys[i + 1].frame;
+				keys[i].tangent = 0.0;
+				keys[i + 1].tangent = 0.0;
+				keys.insert(
+					i + 1,
+					aet::FCurveKey {
+						frame: next - 1.0,
+						value: keys[i].value,
+						tangent: 0.0,
+					},
+				);
+				added += 1;
+			}
+			InterpMode::Linear => {
+				let slope = slope(&keys[i], &keys[i + 1]);
+				keys[i].tangent = slope;
+				keys[i + 1].tangent = slope;
+			}
+			InterpMode::Hermite => {
+				// Catmull-Rom tangents, one sided at the ends of the curve
+				for j in [i, i + 1] {
+					let prev = &keys[j.saturating_sub(1)];
+					let next = &keys[(j + 1).min(keys.len() - 1)];
+					keys[j].tangent = slope(prev, next);
+				}
+			}
+		}
+		added
+	}
+}
+
+// Keys on the same frame jump rather than slope, they are treated as flat
+fn slope(a: &aet::FCurveKey, b: &aet::FCurveKey) -> f32 {
+	if b.frame == a.frame {
+		0.0
+	} else {
+		(b.value - a.value) / (b.frame - a.frame)
+	}
+}
+
 #[derive(Clone)]
 pub struct AetLayerNode {
 	pub name: String,
@@ -1343,14 +3185,24 @@ pub struct AetLayerNode {
 	pub video: Option<aet::LayerVideo>,
 	pub parent: Option<Rc<Mutex<AetLayerNode>>>,
 	pub audio: Option<aet::LayerAudio>,
+	// Kept in project files, the aet format has nowhere to store it
+	pub note: String,
 
 	pub sprites: Rc<Mutex<Vec<Rc<Mutex<crate::spr::SpriteInfoNode>>>>>,
 
 	pub visible: bool,
 	pub selected_key: usize,
+	// Every selected key, selected_key among them is the one the arrows and tangent handles follow
+	pub selected_keys: Vec<usize>,
 
 	pub want_deletion: bool,
 	pub want_duplicate: bool,
+	pub precompose: Option<u32>,
+	pub duplicate_offset: Option<DuplicateOffset>,
+	pub dragging_tangent: bool,
+	pub dragging_key: bool,
+	pub error: Option<String>,
+	pub curve_file_picker_result: Option<Rc<mpsc::Receiver<Option<(std::path::PathBuf, Vec<u8>)>>>>,
 }
 
 impl std::hash::Hash for AetLayerNode {
@@ -1372,6 +3224,7 @@ impl PartialEq for AetLayerNode {
 			&& self.markers == other.markers
 			&& self.video == other.video
 			&& self.audio == other.audio
+			&& self.note == other.note
 	}
 }
 
@@ -1381,12 +3234,25 @@ impl TreeNode for AetLayerNode {
 	}
 
 	fn label_sameline(&mut self, ui: &mut egui::Ui) {
+		if self.end_time <= self.start_time {
+			ui.label(egui::RichText::new(ICON_WARNING).color(ui.visuals().warn_fg_color))
+				.on_hover_text("Layer ends before it starts, it is never shown");
+		}
+
+		if !self.note.is_empty() {
+			ui.label(ICON_STICKY_NOTE_2).on_hover_text(&self.note);
+		}
+
 		let icon = if self.visible {
 			ICON_VISIBILITY
 		} else {
 			ICON_VISIBILITY_OFF
 		};
-		if ui.button(icon).clicked() {
+		if ui
+			.button(icon)
+			.on_hover_text("Show in the preview, not saved")
+			.clicked()
+		{
 			self.visible = !self.visible;
 		}
 	}
@@ -1439,7 +3305,8 @@ impl TreeNode for AetLayerNode {
 
 						if comp.layers.iter().any(|layer| {
 							let layer = layer.try_lock().unwrap();
-							layer.want_deletion || layer.want_duplicate
+							layer.want_deletion
+								|| layer.want_duplicate || layer.precompose.is_some()
 						}) {
 							*selected = path.to_vec();
 							undoer.add_undo(self.clone(), path.to_vec());
@@ -1452,20 +3319,8 @@ impl TreeNode for AetLayerNode {
 						comp.layers
 							.retain(|layer| !layer.try_lock().unwrap().want_deletion);
 
-						for i in comp
-							.layers
-							.iter()
-							.enumerate()
-							.filter(|(_, layer)| layer.try_lock().unwrap().want_duplicate)
-							.map(|(i, _)| i)
-							.collect::<Vec<_>>()
-						{
-							comp.layers.insert(i, comp.layers[i].clone());
-						}
-
-						for layer in &mut comp.layers {
-							layer.try_lock().unwrap().want_duplicate = false;
-						}
+						comp.apply_duplicates();
+						comp.apply_precompose();
 					},
 				)
 			})
@@ -1508,7 +3363,7 @@ impl TreeNode for AetLayerNode {
 						ui.label("Start time");
 					});
 					row.col(|ui| {
-						crate::app::num_edit(ui, &mut self.start_time, 2);
+						crate::app::time_edit(ui, &mut self.start_time);
 					});
 				});
 
@@ -1517,10 +3372,90 @@ impl TreeNode for AetLayerNode {
 						ui.label("End time");
 					});
 					row.col(|ui| {
-						crate::app::num_edit(ui, &mut self.end_time, 2);
-					});
+						ui.horizontal(|ui| {
+							crate::app::time_edit(ui, &mut self.end_time);
+							if self.end_time <= self.start_time {
+								ui.label(
+									egui::RichText::new(ICON_WARNING)
+										.color(ui.visuals().warn_fg_color),
+								)
+								.on_hover_text("Layer ends before it starts, it is never shown");
+							}
+						});
+					});
+				});
+
+				body.row(height, |mut row| {
+					row.col(|ui| {
+						ui.label("Offset time")
+							.on_hover_text("Time of the layer's contents when the layer starts");
+					});
+					row.col(|ui| {
+						crate::app::time_edit(ui, &mut self.offset_time);
+					});
+				});
+
+				body.row(height, |mut row| {
+					row.col(|ui| {
+						ui.label("Time scale")
+							.on_hover_text("Frames of the layer's contents played per frame");
+					});
+					row.col(|ui| {
+						egui::DragValue::new(&mut self.time_scale)
+							.speed(0.01)
+							.max_decimals(3)
+							.ui(ui);
+					});
 				});
 
+				// Where the layer's span lands on its contents' own timeline
+				if matches!(self.item, AetItemNode::Comp(_) | AetItemNode::Video(_))
+					&& self.end_time > self.start_time
+				{
+					body.row(height, |mut row| {
+						row.col(|ui| {
+							ui.label("Contents played");
+						});
+						row.col(|ui| {
+							let end = self.offset_time
+								+ (self.end_time - self.start_time) * self.time_scale;
+							ui.label(format!("{:.1} to {:.1}", self.offset_time, end))
+								.on_hover_text(format!(
+									"Frame {:.1} of the parent shows frame {:.1} of the contents",
+									self.start_time, self.offset_time
+								));
+						});
+					});
+				}
+
+				if self.end_time > self.start_time {
+					body.row(height, |mut row| {
+						row.col(|ui| {
+							ui.label("Stretch").on_hover_text(
+								"Change the duration and retime the keys and contents along with it",
+							);
+						});
+						row.col(|ui| {
+							let mut duration = self.end_time - self.start_time;
+							let resp = egui::DragValue::new(&mut duration)
+								.range(1.0..=f32::MAX)
+								.speed(0.25)
+								.max_decimals(1)
+								.ui(ui);
+							if resp.changed() {
+								self.stretch(duration / (self.end_time - self.start_time));
+							}
+							if resp.dragged() || resp.hovered() {
+								resp.on_hover_text(format!(
+									"Duration {:.1}, time scale {:.3}",
+									self.end_time - self.start_time,
+									self.time_scale
+								));
+							}
+						});
+					});
+				}
+
 				if let Some(parent) = &self.parent {
 					let parent = parent.try_lock().unwrap();
 					body.row(height, |mut row| {
@@ -1533,6 +3468,20 @@ impl TreeNode for AetLayerNode {
 					});
 				}
 
+				body.row(height, |mut row| {
+					row.col(|ui| {
+						ui.label("Active").on_hover_text(
+							"Saved with the aet, inactive layers are not drawn in game or in the preview",
+						);
+					});
+					row.col(|ui| {
+						let mut active = self.flags.video_active();
+						if egui::Checkbox::without_text(&mut active).ui(ui).changed() {
+							self.flags.set_video_active(active);
+						}
+					});
+				});
+
 				let mut has_audio = self.audio.is_some();
 				let mut has_video = self.video.is_some();
 				let mut has_3d = self
@@ -1587,7 +3536,7 @@ impl TreeNode for AetLayerNode {
 							if self.video.is_none() {
 								self.video = Some(aet::LayerVideo {
 									transfer_mode: aet::TransferMode {
-										mode: aet::BlendMode::Add,
+										mode: aet::BlendMode::Normal,
 										flag: 0,
 										matte: 0,
 									},
@@ -1679,6 +3628,27 @@ impl TreeNode for AetLayerNode {
 								});
 						});
 					});
+
+					body.row(height, |mut row| {
+						row.col(|ui| {
+							ui.label("Track matte").on_hover_text(
+								"Masks the layer with the layer above it, not shown in the preview",
+							);
+						});
+						row.col(|ui| {
+							let mut matte = video.transfer_mode.matte as usize;
+							egui::ComboBox::from_id_salt("TrackMatteComboBox")
+								.selected_text(
+									TRACK_MATTES.get(matte).copied().unwrap_or("Unknown"),
+								)
+								.show_ui(ui, |ui| {
+									for (i, name) in TRACK_MATTES.iter().enumerate() {
+										ui.selectable_value(&mut matte, i, *name);
+									}
+								});
+							video.transfer_mode.matte = matte as _;
+						});
+					});
 				}
 
 				body.row(height, |mut row| {
@@ -1748,6 +3718,29 @@ impl TreeNode for AetLayerNode {
 							});
 						});
 
+						if !video.has_size() {
+							body.row(height, |mut row| {
+								row.col(|ui| {
+									ui.label(format!("{ICON_WARNING} No size")).on_hover_text(
+										"Drawn at the sprite's size, placeholders are hidden",
+									);
+								});
+								row.col(|ui| {
+									let sprite_size = video.sprite_size();
+									if ui
+										.add_enabled(
+											sprite_size.is_some(),
+											egui::Button::new("Use sprite size"),
+										)
+										.clicked() && let Some((width, height)) = sprite_size
+									{
+										video.width = width;
+										video.height = height;
+									}
+								});
+							});
+						}
+
 						body.row(height, |mut row| {
 							row.col(|ui| {
 								ui.label("FPF");
@@ -1762,15 +3755,8 @@ impl TreeNode for AetLayerNode {
 								ui.label("Color");
 							});
 							row.col(|ui| {
-								let mut rgb = [
-									video.color[0] as f32 / 255.0,
-									video.color[1] as f32 / 255.0,
-									video.color[2] as f32 / 255.0,
-								];
-								ui.color_edit_button_rgb(&mut rgb);
-								video.color[0] = (rgb[0] * 255.0) as u8;
-								video.color[1] = (rgb[1] * 255.0) as u8;
-								video.color[2] = (rgb[2] * 255.0) as u8;
+								// Edit the bytes directly so opening the picker can't change them
+								ui.color_edit_button_srgb(&mut video.color);
 							});
 						});
 
@@ -1857,7 +3843,60 @@ impl TreeNode for AetLayerNode {
 								ui.label("Sound index");
 							});
 							row.col(|ui| {
-								crate::app::num_edit(ui, &mut audio.sound_index, 0);
+								ui.horizontal(|ui| {
+									crate::app::num_edit(ui, &mut audio.sound_index, 0);
+
+									let names = ui
+										.data(|data| {
+											data.get_persisted::<std::collections::BTreeMap<u32, String>>(
+											egui::Id::new(SOUND_NAMES),
+										)
+										})
+										.unwrap_or_default();
+									egui::ComboBox::from_id_salt("SoundNameComboBox")
+										.selected_text(
+											names
+												.get(&audio.sound_index)
+												.map_or("Unnamed", |name| name.as_str()),
+										)
+										.show_ui(ui, |ui| {
+											for (index, name) in &names {
+												ui.selectable_value(
+													&mut audio.sound_index,
+													*index,
+													format!("{index}: {name}"),
+												);
+											}
+										});
+								});
+							});
+						});
+
+						body.row(height, |mut row| {
+							row.col(|ui| {
+								ui.label("Sound name").on_hover_text(
+									"Remembered by this app for the index, not saved to the file",
+								);
+							});
+							row.col(|ui| {
+								let id = egui::Id::new(SOUND_NAMES);
+								let mut names = ui
+									.data(|data| {
+										data.get_persisted::<std::collections::BTreeMap<u32, String>>(
+											id,
+										)
+									})
+									.unwrap_or_default();
+								let mut name =
+									names.get(&audio.sound_index).cloned().unwrap_or_default();
+								if ui.text_edit_singleline(&mut name).changed() {
+									if name.is_empty() {
+										names.remove(&audio.sound_index);
+									} else {
+										names.insert(audio.sound_index, name);
+									}
+									ui.data_mut(|data| data.insert_persisted(id, names));
+								}
 							});
 						});
 					}
@@ -1881,11 +3920,27 @@ impl TreeNode for AetLayerNode {
 							ui.text_edit_singleline(name);
 						});
 						row.col(|ui| {
-							crate::app::num_edit(ui, value, 2);
+							ui.horizontal(|ui| {
+								crate::app::time_edit(ui, value);
+								ui.menu_button(ICON_REPEAT, |ui| {
+									if ui.button("Use as loop start").clicked() {
+										*name = String::from(LOOP_IN);
+										ui.close();
+									}
+									if ui.button("Use as loop end").clicked() {
+										*name = String::from(LOOP_OUT);
+										ui.close();
+									}
+								})
+								.response
+								.on_hover_text("Looped playback repeats between these markers");
+							});
 						});
 					});
 				}
 			});
+
+		note_edit(ui, &mut self.note);
 	}
 
 	fn has_context_menu(&self) -> bool {
@@ -1895,10 +3950,17 @@ impl TreeNode for AetLayerNode {
 	fn display_ctx_menu(&mut self, ui: &mut egui::Ui) {
 		if let AetItemNode::Comp(comp) = &mut self.item {
 			if ui.button("Add").clicked() {
+				let defaults = crate::settings::Defaults::get(ui.ctx());
+				let end_time = if defaults.layer_duration > 0.0 {
+					defaults.layer_duration
+				} else {
+					self.end_time - self.start_time
+				};
+
 				comp.layers.push(Rc::new(Mutex::new(AetLayerNode {
-					name: String::from("DUMMY"),
+					name: defaults.layer_name,
 					start_time: 0.0,
-					end_time: self.end_time - self.start_time,
+					end_time,
 					offset_time: 0.0,
 					time_scale: 1.0,
 					flags: self.flags,
@@ -1908,19 +3970,157 @@ impl TreeNode for AetLayerNode {
 					video: None,
 					parent: None,
 					audio: None,
+					note: String::new(),
 					sprites: self.sprites.clone(),
 					visible: self.visible,
 					selected_key: 0,
+					selected_keys: Vec::new(),
 					want_deletion: false,
 					want_duplicate: false,
+					precompose: None,
+					duplicate_offset: None,
+					dragging_tangent: false,
+					dragging_key: false,
+					error: None,
+					curve_file_picker_result: None,
 				})))
 			}
+
+			let copied = ui
+				.data(|data| data.get_temp::<bool>(egui::Id::new(crate::app::LAYER_COPIED)))
+				.unwrap_or(false);
+			if ui
+				.add_enabled(copied, egui::Button::new("Paste layer"))
+				.clicked()
+			{
+				ui.data_mut(|data| data.insert_temp(egui::Id::new(crate::app::PASTE_LAYER), true));
+			}
 		};
 
+		if ui.button("Copy").clicked() {
+			ui.data_mut(|data| data.insert_temp(egui::Id::new(crate::app::COPY_LAYER), true));
+		}
+
+		if ui
+			.button("Use as backdrop")
+			.on_hover_text("Preview other layers' blending over only this one")
+			.clicked()
+		{
+			ui.data_mut(|data| data.insert_temp(egui::Id::new(SET_BACKDROP), true));
+		}
+
 		if ui.button("Duplicate").clicked() {
 			self.want_duplicate = true;
 		}
 
+		ui.menu_button("Duplicate with offset", |ui| {
+			let id = egui::Id::new("DuplicateOffset");
+			let mut offset = ui
+				.data(|data| data.get_temp::<DuplicateOffset>(id))
+				.unwrap_or_default();
+
+			egui::Grid::new("DuplicateOffsetGrid").show(ui, |ui| {
+				ui.label("Copies");
+				crate::app::num_edit(ui, &mut offset.count, 0);
+				ui.end_row();
+
+				ui.label("Time step");
+				crate::app::time_edit(ui, &mut offset.time);
+				ui.end_row();
+
+				ui.label("X step");
+				crate::app::num_edit(ui, &mut offset.x, 2);
+				ui.end_row();
+
+				ui.label("Y step");
+				crate::app::num_edit(ui, &mut offset.y, 2);
+				ui.end_row();
+			});
+
+			ui.data_mut(|data| data.insert_temp(id, offset));
+
+			if ui.button("Duplicate").clicked() {
+				self.want_duplicate = true;
+				self.duplicate_offset = Some(offset);
+				ui.close();
+			}
+		});
+
+		ui.menu_button("Pre-compose", |ui| {
+			let id = egui::Id::new("PrecomposeCount");
+			let mut count = ui.data(|data| data.get_temp::<u32>(id)).unwrap_or(0);
+
+			ui.horizontal(|ui| {
+				ui.label("Following layers");
+				crate::app::num_edit(ui, &mut count, 0);
+			});
+
+			ui.data_mut(|data| data.insert_temp(id, count));
+
+			if ui
+				.button("Pre-compose")
+				.on_hover_text(
+					"Move this layer and the following ones into a new composition, parents across it are removed",
+				)
+				.clicked()
+			{
+				self.precompose = Some(count);
+				ui.close();
+			}
+		});
+
+		if ui
+			.button("Collapse constant curves")
+			.on_hover_text("Reduce curves whose keys all share a value to a single key")
+			.clicked()
+		{
+			self.collapse_constant_curves();
+		}
+
+		if ui
+			.button("Clamp keys to layer")
+			.on_hover_text(
+				"Move keys before or after the layer onto its start or end, keeping the nearest one on each",
+			)
+			.clicked()
+		{
+			self.clamp_keys();
+		}
+
+		if matches!(&self.item, AetItemNode::Video(video) if video.sources.len() == 1) {
+			ui.menu_button("Make animation", |ui| {
+				let frames = self.numbered_sprites();
+				if frames.len() < 2 {
+					ui.label("No sprites numbered after this one");
+					return;
+				}
+
+				let id = egui::Id::new("AnimationFpf");
+				let mut fpf = ui.data(|data| data.get_temp::<f32>(id)).unwrap_or(1.0);
+
+				ui.horizontal(|ui| {
+					ui.label("FPF");
+					crate::app::num_edit(ui, &mut fpf, 2);
+				});
+
+				ui.data_mut(|data| data.insert_temp(id, fpf));
+
+				let names = frames
+					.iter()
+					.map(|sprite| sprite.try_lock().unwrap().name.clone())
+					.collect::<Vec<_>>()
+					.join("\n");
+				if ui
+					.button(format!("Use {} sprites", frames.len()))
+					.on_hover_text(names)
+					.clicked()
+				{
+					self.make_animation(fpf.max(0.01));
+					ui.close();
+				}
+			});
+		}
+
 		if ui.button("Remove").clicked() {
 			self.want_deletion = true;
 		}
@@ -1928,47 +4128,450 @@ impl TreeNode for AetLayerNode {
 }
 
 impl AetLayerNode {
+	// Stand-in layer holding a scene's root, undoing it swaps the whole layer list back
+	pub fn root_snapshot(root: AetCompNode) -> Self {
+		Self {
+			name: String::from("DUMMY"),
+			start_time: 0.0,
+			end_time: 0.0,
+			offset_time: 0.0,
+			time_scale: 1.0,
+			flags: aet::LayerFlags::new(),
+			quality: aet::LayerQuality::None,
+			item: AetItemNode::Comp(root),
+			markers: Vec::new(),
+			video: None,
+			parent: None,
+			audio: None,
+			note: String::new(),
+			sprites: Rc::new(Mutex::new(Vec::new())),
+			visible: false,
+			selected_key: 0,
+			selected_keys: Vec::new(),
+			want_deletion: false,
+			want_duplicate: false,
+			precompose: None,
+			duplicate_offset: None,
+			dragging_tangent: false,
+			dragging_key: false,
+			error: None,
+			curve_file_picker_result: None,
+		}
+	}
+
+	// The path is this layer's own, its children add their index to it
+	fn collect_notes(&self, path: &str, notes: &mut std::collections::BTreeMap<String, String>) {
+		if !self.note.is_empty() {
+			notes.insert(path.to_string(), self.note.clone());
+		}
+		if let AetItemNode::Comp(comp) = &self.item {
+			for (i, layer) in comp.layers.iter().enumerate() {
+				layer
+					.try_lock()
+					.unwrap()
+					.collect_notes(&format!("{path}/{i}"), notes);
+			}
+		}
+	}
+
+	fn set_notes(&mut self, path: &str, notes: &std::collections::BTreeMap<String, String>) {
+		self.note = notes.get(path).cloned().unwrap_or_default();
+		if let AetItemNode::Comp(comp) = &self.item {
+			for (i, layer) in comp.layers.iter().enumerate() {
+				layer
+					.try_lock()
+					.unwrap()
+					.set_notes(&format!("{path}/{i}"), notes);
+			}
+		}
+	}
+
+	fn non_finite_keys(&mut self, path: &str, zero: bool, found: &mut Vec<String>) {
+		let path = format!("{path}/{}", self.name);
+		for (curve, fcurve) in self.curves_mut() {
+			for (i, key) in fcurve.keys.iter_mut().enumerate() {
+				if key.frame.is_finite() && key.value.is_finite() && key.tangent.is_finite() {
+					continue;
+				}
+
+				found.push(format!("{path}: {} key {}", curve.name(), i + 1));
+				if zero {
+					for number in [&mut key.frame, &mut key.value, &mut key.tangent] {
+						if !number.is_finite() {
+							*number = 0.0;
+						}
+					}
+				}
+			}
+		}
+
+		if let AetItemNode::Comp(comp) = &self.item {
+			for layer in &comp.layers {
+				layer
+					.try_lock()
+					.unwrap()
+					.non_finite_keys(&path, zero, found);
+			}
+		}
+	}
+
+	// Frames of the given curve's keys, or of every curve when there is no such curve
+	fn key_frames(&mut self, curve: Option<CurveType>) -> Vec<f32> {
+		let mut curves = self.curves_mut();
+		if curves
+			.iter()
+			.any(|(curve_type, _)| Some(*curve_type) == curve)
+		{
+			curves.retain(|(curve_type, _)| Some(*curve_type) == curve);
+		}
+		curves
+			.iter()
+			.flat_map(|(_, curve)| curve.keys.iter().map(|key| key.frame))
+			.collect()
+	}
+
+	pub fn deep_clone(&self) -> Self {
+		let mut layer = self.clone();
+		if let AetItemNode::Comp(comp) = &self.item {
+			layer.item = AetItemNode::Comp(comp.deep_clone());
+		}
+		layer
+	}
+
+	pub fn default_curve(&self) -> Option<CurveType> {
+		if self.video.is_some() {
+			Some(CurveType::PosX)
+		} else if self.audio.is_some() {
+			Some(CurveType::VolumeL)
+		} else {
+			None
+		}
+	}
+
+	pub fn curves_mut(&mut self) -> Vec<(CurveType, &mut aet::FCurve)> {
+		let mut curves = Vec::new();
+
+		if let Some(audio) = &mut self.audio {
+			curves.push((CurveType::VolumeL, &mut audio.volume_l));
+			curves.push((CurveType::VolumeR, &mut audio.volume_r));
+			curves.push((CurveType::PanL, &mut audio.pan_l));
+			curves.push((CurveType::PanR, &mut audio.pan_r));
+		}
+
+		if let Some(video) = &mut self.video {
+			curves.push((CurveType::AnchorX, &mut video.anchor_x));
+			curves.push((CurveType::AnchorY, &mut video.anchor_y));
+			curves.push((CurveType::PosX, &mut video.pos_x));
+			curves.push((CurveType::PosY, &mut video.pos_y));
+			curves.push((CurveType::RotZ, &mut video.rot_z));
+			curves.push((CurveType::ScaleX, &mut video.scale_x));
+			curves.push((CurveType::ScaleY, &mut video.scale_y));
+			curves.push((CurveType::Opacity, &mut video.opacity));
+
+			if let Some(_3d) = &mut video._3d {
+				curves.push((CurveType::AnchorZ, &mut _3d.anchor_z));
+				curves.push((CurveType::PosZ, &mut _3d.pos_z));
+				curves.push((CurveType::DirX, &mut _3d.dir_x));
+				curves.push((CurveType::DirY, &mut _3d.dir_y));
+				curves.push((CurveType::DirZ, &mut _3d.dir_z));
+				curves.push((CurveType::RotX, &mut _3d.rot_x));
+				curves.push((CurveType::RotY, &mut _3d.rot_y));
+				curves.push((CurveType::ScaleZ, &mut _3d.scale_z));
+			}
+		}
+
+		curves
+	}
+
+	// Sprites named like this layer's only source but with a higher trailing number, in order
+	fn numbered_sprites(&self) -> Vec<Rc<Mutex<crate::spr::SpriteInfoNode>>> {
+		let AetItemNode::Video(video) = &self.item else {
+			return Vec::new();
+		};
+		let [source] = video.sources.as_slice() else {
+			return Vec::new();
+		};
+		let Some(sprite) = &source.sprite else {
+			return Vec::new();
+		};
+
+		let split = |name: &str| {
+			let prefix = name.trim_end_matches(|c: char| c.is_ascii_digit());
+			let number = name[prefix.len()..].parse::<u32>().ok()?;
+			Some((prefix.to_string(), number))
+		};
+		let Some((prefix, first)) = split(&sprite.try_lock().unwrap().name) else {
+			return Vec::new();
+		};
+
+		let mut sprites = self
+			.sprites
+			.try_lock()
+			.unwrap()
+			.iter()
+			.filter_map(|other| {
+				let (other_prefix, number) = split(&other.try_lock().unwrap().name)?;
+				(other_prefix == prefix && number >= first).then(|| (number, other.clone()))
+			})
+			.collect::<Vec<_>>();
+		sprites.sort_by_key(|(number, _)| *number);
+		sprites.into_iter().map(|(_, sprite)| sprite).collect()
+	}
+
+	// Replaces the only source with the numbered sprites following it
+	fn make_animation(&mut self, fpf: f32) {
+		let sprites = self.numbered_sprites();
+		let AetItemNode::Video(video) = &mut self.item else {
+			return;
+		};
+
+		video.sources = sprites
+			.into_iter()
+			.map(|sprite| {
+				let (name, id) = {
+					let sprite = sprite.try_lock().unwrap();
+					let id = sprite
+						.db_entry
+						.as_ref()
+						.map_or(0, |entry| entry.try_lock().unwrap().id);
+					(sprite.name.clone(), id)
+				};
+				AetVideoSourceNode {
+					name,
+					id,
+					sprite: Some(sprite),
+				}
+			})
+			.collect();
+		video.fpf = fpf;
+	}
+
+	// Also applies to the layers of a composition item
+	pub fn collapse_constant_curves(&mut self) {
+		const TOLERANCE: f32 = 0.0001;
+
+		for (_, curve) in self.curves_mut() {
+			let Some(first) = curve.keys.first() else {
+				continue;
+			};
+			if curve.keys.len() > 1
+				&& curve
+					.keys
+					.iter()
+					.all(|key| (key.value - first.value).abs() <= TOLERANCE)
+			{
+				curve.keys = vec![aet::FCurveKey {
+					frame: 0.0,
+					value: first.value,
+					tangent: 0.0,
+				}];
+			}
+		}
+
+		if let AetItemNode::Comp(comp) = &self.item {
+			for layer in &comp.layers {
+				layer.try_lock().unwrap().collapse_constant_curves();
+			}
+		}
+	}
+
+	fn keys_out_of_bounds(&mut self, path: &str, report: &mut Vec<String>) {
+		let (start, end) = (self.start_time, self.end_time);
+		let path = format!("{path}/{}", self.name);
+		let count = self
+			.curves_mut()
+			.iter()
+			.map(|(_, curve)| {
+				curve
+					.keys
+					.iter()
+					.filter(|key| key.frame < start || key.frame > end)
+					.count()
+			})
+			.sum::<usize>();
+		if count > 0 {
+			report.push(format!("{path}: {count} keys outside {start} to {end}"));
+		}
+
+		if let AetItemNode::Comp(comp) = &self.item {
+			for layer in &comp.layers {
+				layer.try_lock().unwrap().keys_out_of_bounds(&path, report);
+			}
+		}
+	}
+
+	pub fn clamp_keys(&mut self) {
+		let (start, end) = (self.start_time, self.end_time);
+		for (_, curve) in self.curves_mut() {
+			clamp_curve(curve, start, end);
+		}
+
+		if let AetItemNode::Comp(comp) = &self.item {
+			for layer in &comp.layers {
+				layer.try_lock().unwrap().clamp_keys();
+			}
+		}
+	}
+
+	// Scales the duration around the start, keys and markers move with it and the contents play slower or faster
+	fn stretch(&mut self, factor: f32) {
+		if factor <= 0.0 || !factor.is_finite() {
+			return;
+		}
+
+		let start = self.start_time;
+		let retime = |frame: f32| start + (frame - start) * factor;
+		self.end_time = retime(self.end_time);
+		for (_, marker) in &mut self.markers {
+			*marker = retime(*marker);
+		}
+		for (_, curve) in self.curves_mut() {
+			for key in &mut curve.keys {
+				key.frame = retime(key.frame);
+				key.tangent /= factor;
+			}
+		}
+
+		if matches!(self.item, AetItemNode::Comp(_) | AetItemNode::Video(_)) {
+			self.time_scale /= factor;
+		}
+	}
+
+	// Shifts the layer by `steps` times the offset, keys are in parent time so they move with the layer
+	fn apply_offset(&mut self, offset: &DuplicateOffset, steps: f32) {
+		let time = offset.time * steps;
+		self.start_time += time;
+		self.end_time += time;
+		for (_, marker) in &mut self.markers {
+			*marker += time;
+		}
+
+		for (curve_type, curve) in self.curves_mut() {
+			let value = match curve_type {
+				CurveType::PosX => offset.x * steps,
+				CurveType::PosY => offset.y * steps,
+				_ => 0.0,
+			};
+
+			if curve.keys.is_empty() && value != 0.0 {
+				curve.keys.push(aet::FCurveKey {
+					frame: 0.0,
+					value: 0.0,
+					tangent: 0.0,
+				});
+			}
+
+			for key in &mut curve.keys {
+				key.frame += time;
+				key.value += value;
+			}
+		}
+	}
+
 	pub fn display_curve_editor(
 		&mut self,
 		ui: &mut egui::Ui,
 		selected_curve: &mut Option<CurveType>,
 		frame: f32,
 	) {
-		egui::SidePanel::left("CurveSelector")
-			.resizable(true)
-			.show_inside(ui, |ui| {
-				egui::ScrollArea::vertical().show(ui, |ui| {
-					if self.audio.is_some() {
-						if ui
-							.selectable_label(
-								*selected_curve == Some(CurveType::VolumeL),
-								"Volume L",
-							)
-							.clicked()
+		if let Some(error) = &self.error {
+			let modal = egui::Modal::new(egui::Id::new("AetLayerError")).show(ui.ctx(), |ui| {
+				ui.heading("An error has occured");
+				ui.vertical_centered(|ui| {
+					ui.label(error);
+					if ui.button("Ok").clicked() {
+						ui.close();
+					}
+				});
+			});
+
+			if modal.should_close() {
+				self.error = None;
+			}
+		}
+
+		let curve_before = *selected_curve;
+		egui::SidePanel::left("CurveSelector")
+			.resizable(true)
+			.show_inside(ui, |ui| {
+				let id = egui::Id::new("AnimatedCurvesOnly");
+				let mut animated_only = ui.data(|data| data.get_temp(id)).unwrap_or(false);
+				if ui
+					.checkbox(&mut animated_only, "Show only animated curves")
+					.changed()
+				{
+					ui.data_mut(|data| data.insert_temp(id, animated_only));
+				}
+
+				if let Some(audio) = &self.audio {
+					let id = egui::Id::new("VolumeMeter");
+					let mut meter = ui.data(|data| data.get_temp(id)).unwrap_or(false);
+					if ui
+						.checkbox(&mut meter, "Show volume meter")
+						.on_hover_text("Levels of the volume curves at the current frame")
+						.changed()
+					{
+						ui.data_mut(|data| data.insert_temp(id, meter));
+					}
+					if meter {
+						volume_meter(
+							ui,
+							audio.volume_l.interpolate(frame),
+							audio.volume_r.interpolate(frame),
+						);
+					}
+				}
+
+				let animated = self
+					.curves_mut()
+					.into_iter()
+					.filter(|(curve_type, curve)| is_animated(*curve_type, curve))
+					.map(|(curve_type, _)| curve_type)
+					.collect::<Vec<_>>();
+				// The selected curve stays listed even when it is not animated
+				let selected_before = *selected_curve;
+				let shown = |curve_type: CurveType| {
+					!animated_only
+						|| animated.contains(&curve_type)
+						|| selected_before == Some(curve_type)
+				};
+
+				egui::ScrollArea::vertical().show(ui, |ui| {
+					if self.audio.is_some() {
+						if shown(CurveType::VolumeL)
+							&& ui
+								.selectable_label(
+									*selected_curve == Some(CurveType::VolumeL),
+									"Volume L",
+								)
+								.clicked()
 						{
 							*selected_curve = Some(CurveType::VolumeL);
 							self.selected_key = 0;
 						}
-						if ui
-							.selectable_label(
-								*selected_curve == Some(CurveType::VolumeR),
-								"Volume R",
-							)
-							.clicked()
+						if shown(CurveType::VolumeR)
+							&& ui
+								.selectable_label(
+									*selected_curve == Some(CurveType::VolumeR),
+									"Volume R",
+								)
+								.clicked()
 						{
 							*selected_curve = Some(CurveType::VolumeR);
 							self.selected_key = 0;
 						}
-						if ui
-							.selectable_label(*selected_curve == Some(CurveType::PanL), "Pan L")
-							.clicked()
+						if shown(CurveType::PanL)
+							&& ui
+								.selectable_label(*selected_curve == Some(CurveType::PanL), "Pan L")
+								.clicked()
 						{
 							*selected_curve = Some(CurveType::PanL);
 							self.selected_key = 0;
 						}
-						if ui
-							.selectable_label(*selected_curve == Some(CurveType::PanR), "Pan R")
-							.clicked()
+						if shown(CurveType::PanR)
+							&& ui
+								.selectable_label(*selected_curve == Some(CurveType::PanR), "Pan R")
+								.clicked()
 						{
 							*selected_curve = Some(CurveType::PanR);
 							self.selected_key = 0;
@@ -1977,27 +4580,30 @@ impl AetLayerNode {
 
 					if self.video.is_some() {
 						let has_3d = self.video.as_ref().unwrap()._3d.is_some();
-						if ui
-							.selectable_label(
-								*selected_curve == Some(CurveType::AnchorX),
-								"Anchor X",
-							)
-							.clicked()
+						if shown(CurveType::AnchorX)
+							&& ui
+								.selectable_label(
+									*selected_curve == Some(CurveType::AnchorX),
+									"Anchor X",
+								)
+								.clicked()
 						{
 							*selected_curve = Some(CurveType::AnchorX);
 							self.selected_key = 0;
 						}
-						if ui
-							.selectable_label(
-								*selected_curve == Some(CurveType::AnchorY),
-								"Anchor Y",
-							)
-							.clicked()
+						if shown(CurveType::AnchorY)
+							&& ui
+								.selectable_label(
+									*selected_curve == Some(CurveType::AnchorY),
+									"Anchor Y",
+								)
+								.clicked()
 						{
 							*selected_curve = Some(CurveType::AnchorY);
 							self.selected_key = 0;
 						}
 						if has_3d
+							&& shown(CurveType::AnchorZ)
 							&& ui
 								.selectable_label(
 									*selected_curve == Some(CurveType::AnchorZ),
@@ -2008,21 +4614,24 @@ impl AetLayerNode {
 							*selected_curve = Some(CurveType::AnchorZ);
 							self.selected_key = 0;
 						}
-						if ui
-							.selectable_label(*selected_curve == Some(CurveType::PosX), "Pos X")
-							.clicked()
+						if shown(CurveType::PosX)
+							&& ui
+								.selectable_label(*selected_curve == Some(CurveType::PosX), "Pos X")
+								.clicked()
 						{
 							*selected_curve = Some(CurveType::PosX);
 							self.selected_key = 0;
 						}
-						if ui
-							.selectable_label(*selected_curve == Some(CurveType::PosY), "Pos Y")
-							.clicked()
+						if shown(CurveType::PosY)
+							&& ui
+								.selectable_label(*selected_curve == Some(CurveType::PosY), "Pos Y")
+								.clicked()
 						{
 							*selected_curve = Some(CurveType::PosY);
 							self.selected_key = 0;
 						}
 						if has_3d
+							&& shown(CurveType::PosZ)
 							&& ui
 								.selectable_label(*selected_curve == Some(CurveType::PosZ), "Pos Z")
 								.clicked()
@@ -2031,30 +4640,37 @@ impl AetLayerNode {
 							self.selected_key = 0;
 						}
 						if has_3d
+							&& shown(CurveType::DirX)
 							&& ui
 								.selectable_label(*selected_curve == Some(CurveType::DirX), "Dir X")
+								.on_hover_text(DIR_HOVER_TEXT)
 								.clicked()
 						{
 							*selected_curve = Some(CurveType::DirX);
 							self.selected_key = 0;
 						}
 						if has_3d
+							&& shown(CurveType::DirY)
 							&& ui
 								.selectable_label(*selected_curve == Some(CurveType::DirY), "Dir Y")
+								.on_hover_text(DIR_HOVER_TEXT)
 								.clicked()
 						{
 							*selected_curve = Some(CurveType::DirY);
 							self.selected_key = 0;
 						}
 						if has_3d
+							&& shown(CurveType::DirZ)
 							&& ui
 								.selectable_label(*selected_curve == Some(CurveType::DirZ), "Dir Z")
+								.on_hover_text(DIR_HOVER_TEXT)
 								.clicked()
 						{
 							*selected_curve = Some(CurveType::DirZ);
 							self.selected_key = 0;
 						}
 						if has_3d
+							&& shown(CurveType::RotX)
 							&& ui
 								.selectable_label(*selected_curve == Some(CurveType::RotX), "Rot X")
 								.clicked()
@@ -2063,6 +4679,7 @@ impl AetLayerNode {
 							self.selected_key = 0;
 						}
 						if has_3d
+							&& shown(CurveType::RotY)
 							&& ui
 								.selectable_label(*selected_curve == Some(CurveType::RotY), "Rot Y")
 								.clicked()
@@ -2070,28 +4687,38 @@ impl AetLayerNode {
 							*selected_curve = Some(CurveType::RotY);
 							self.selected_key = 0;
 						}
-						if ui
-							.selectable_label(*selected_curve == Some(CurveType::RotZ), "Rot Z")
-							.clicked()
+						if shown(CurveType::RotZ)
+							&& ui
+								.selectable_label(*selected_curve == Some(CurveType::RotZ), "Rot Z")
+								.clicked()
 						{
 							*selected_curve = Some(CurveType::RotZ);
 							self.selected_key = 0;
 						}
-						if ui
-							.selectable_label(*selected_curve == Some(CurveType::ScaleX), "Scale X")
-							.clicked()
+						if shown(CurveType::ScaleX)
+							&& ui
+								.selectable_label(
+									*selected_curve == Some(CurveType::ScaleX),
+									"Scale X",
+								)
+								.clicked()
 						{
 							*selected_curve = Some(CurveType::ScaleX);
 							self.selected_key = 0;
 						}
-						if ui
-							.selectable_label(*selected_curve == Some(CurveType::ScaleY), "Scale Y")
-							.clicked()
+						if shown(CurveType::ScaleY)
+							&& ui
+								.selectable_label(
+									*selected_curve == Some(CurveType::ScaleY),
+									"Scale Y",
+								)
+								.clicked()
 						{
 							*selected_curve = Some(CurveType::ScaleY);
 							self.selected_key = 0;
 						}
 						if has_3d
+							&& shown(CurveType::ScaleZ)
 							&& ui
 								.selectable_label(
 									*selected_curve == Some(CurveType::ScaleZ),
@@ -2102,12 +4729,13 @@ impl AetLayerNode {
 							*selected_curve = Some(CurveType::ScaleZ);
 							self.selected_key = 0;
 						}
-						if ui
-							.selectable_label(
-								*selected_curve == Some(CurveType::Opacity),
-								"Opacity",
-							)
-							.clicked()
+						if shown(CurveType::Opacity)
+							&& ui
+								.selectable_label(
+									*selected_curve == Some(CurveType::Opacity),
+									"Opacity",
+								)
+								.clicked()
 						{
 							*selected_curve = Some(CurveType::Opacity);
 							self.selected_key = 0;
@@ -2118,6 +4746,10 @@ impl AetLayerNode {
 				});
 			});
 
+		if *selected_curve != curve_before {
+			self.selected_keys.clear();
+		}
+
 		let Some(selected_curve) = &selected_curve else {
 			return;
 		};
@@ -2183,6 +4815,22 @@ impl AetLayerNode {
 			return;
 		};
 
+		if let Some(rx) = &self.curve_file_picker_result
+			&& let Ok(res) = rx.try_recv()
+		{
+			if let Some((path, data)) = res {
+				match curve_from_csv(&String::from_utf8_lossy(&data)) {
+					Ok(keys) => {
+						curve.keys = keys;
+						self.selected_key = 0;
+						self.selected_keys.clear();
+					}
+					Err(e) => self.error = Some(format!("Could not import {:?}: {e}", path)),
+				}
+			}
+			self.curve_file_picker_result = None;
+		}
+
 		if curve.keys.is_empty() {
 			curve.keys.push(aet::FCurveKey {
 				frame: 0.0,
@@ -2194,17 +4842,46 @@ impl AetLayerNode {
 		if self.selected_key >= curve.keys.len() {
 			self.selected_key = curve.keys.len() - 1;
 		}
+		self.selected_keys.retain(|i| *i < curve.keys.len());
+		if !self.selected_keys.contains(&self.selected_key) {
+			self.selected_keys = vec![self.selected_key];
+		}
+
+		if ui
+			.data_mut(|data| data.remove_temp::<bool>(egui::Id::new(crate::app::ADD_KEY)))
+			.unwrap_or(false)
+		{
+			self.selected_key = add_key(curve, frame.clamp(self.start_time, self.end_time));
+			self.selected_keys = vec![self.selected_key];
+		}
+
+		let is_audio = matches!(
+			selected_curve,
+			CurveType::VolumeL | CurveType::VolumeR | CurveType::PanL | CurveType::PanR
+		);
+		let table_id = egui::Id::new("AudioCurveTable");
+		let mut table_view = is_audio && ui.data(|data| data.get_temp(table_id)).unwrap_or(false);
 
 		egui::SidePanel::right("KeyEditor")
 			.resizable(true)
 			.show_inside(ui, |ui| {
 				ui.horizontal(|ui| {
-					ui.label(format!("{}/{}", self.selected_key + 1, curve.keys.len()));
+					if self.selected_keys.len() > 1 {
+						ui.label(format!(
+							"{}/{} ({} selected)",
+							self.selected_key + 1,
+							curve.keys.len(),
+							self.selected_keys.len()
+						));
+					} else {
+						ui.label(format!("{}/{}", self.selected_key + 1, curve.keys.len()));
+					}
 					if ui
 						.add_enabled(self.selected_key != 0, egui::Button::new(ICON_ARROW_LEFT))
 						.clicked()
 					{
 						self.selected_key -= 1;
+						self.selected_keys = vec![self.selected_key];
 					}
 
 					if ui
@@ -2215,60 +4892,300 @@ impl AetLayerNode {
 						.clicked()
 					{
 						self.selected_key += 1;
+						self.selected_keys = vec![self.selected_key];
 					}
 
 					if ui.button(ICON_ADD).clicked() {
-						let f = frame.clamp(self.start_time, self.end_time);
-						curve.keys.push(aet::FCurveKey {
-							frame: f,
-							value: curve.interpolate(f),
-							tangent: 0.0,
-						});
-						curve.keys.sort_by(|a, b| a.frame.total_cmp(&b.frame));
-						self.selected_key = curve
-							.keys
-							.iter()
-							.position(|key| key.frame == f)
-							.unwrap_or(0);
+						self.selected_key =
+							add_key(curve, frame.clamp(self.start_time, self.end_time));
+						self.selected_keys = vec![self.selected_key];
 					}
 
+					// A curve always keeps at least one key
 					if ui
-						.add_enabled(curve.keys.len() != 1, egui::Button::new(ICON_REMOVE))
+						.add_enabled(
+							curve.keys.len() > self.selected_keys.len(),
+							egui::Button::new(ICON_REMOVE),
+						)
+						.on_hover_text("Remove the selected keys")
 						.clicked()
 					{
-						curve.keys.remove(self.selected_key);
-						if self.selected_key == curve.keys.len() {
-							self.selected_key -= 1;
-						}
+						let first = self.selected_keys.iter().copied().min().unwrap_or(0);
+						let mut i = 0;
+						curve.keys.retain(|_| {
+							i += 1;
+							!self.selected_keys.contains(&(i - 1))
+						});
+						self.selected_key = first.min(curve.keys.len() - 1);
+						self.selected_keys = vec![self.selected_key];
+					}
+
+					if is_audio
+						&& ui
+							.selectable_label(table_view, ICON_TABLE_ROWS)
+							.on_hover_text("Edit the keys as a table instead of a plot")
+							.clicked()
+					{
+						table_view = !table_view;
+						ui.data_mut(|data| data.insert_temp(table_id, table_view));
 					}
 				});
 
+				// Edits to the shown key are applied to the rest of the selection as offsets
 				ui.horizontal(|ui| {
 					ui.label("Frame");
-					if crate::app::num_edit(ui, &mut curve.keys[self.selected_key].frame, 2)
-						.changed()
+					let before = curve.keys[self.selected_key].frame;
+					if crate::app::time_edit(ui, &mut curve.keys[self.selected_key].frame).changed()
 					{
-						curve.keys[self.selected_key].frame = curve.keys[self.selected_key]
-							.frame
-							.clamp(self.start_time, self.end_time);
-
-						curve.keys.sort_by(|a, b| a.frame.total_cmp(&b.frame));
+						let frames = curve.keys[self.selected_key].frame - before;
+						curve.keys[self.selected_key].frame = before;
+						move_keys(
+							&mut curve.keys,
+							&self.selected_keys,
+							frames,
+							0.0,
+							(self.start_time, self.end_time),
+						);
+						sort_keys(curve, &mut self.selected_key, &mut self.selected_keys);
 					}
 				});
 
 				ui.horizontal(|ui| {
 					ui.label("Value");
-					crate::app::num_edit(ui, &mut curve.keys[self.selected_key].value, 2);
+					let before = curve.keys[self.selected_key].value;
+					if crate::app::num_edit(ui, &mut curve.keys[self.selected_key].value, 2)
+						.changed()
+					{
+						let value = curve.keys[self.selected_key].value - before;
+						curve.keys[self.selected_key].value = before;
+						move_keys(
+							&mut curve.keys,
+							&self.selected_keys,
+							0.0,
+							value,
+							(self.start_time, self.end_time),
+						);
+					}
 				});
 
 				ui.horizontal(|ui| {
 					ui.label("Tangent");
-					crate::app::num_edit(ui, &mut curve.keys[self.selected_key].tangent, 2);
+					let before = curve.keys[self.selected_key].tangent;
+					if crate::app::num_edit(ui, &mut curve.keys[self.selected_key].tangent, 2)
+						.changed()
+					{
+						let tangent = curve.keys[self.selected_key].tangent - before;
+						for i in &self.selected_keys {
+							if *i != self.selected_key {
+								curve.keys[*i].tangent += tangent;
+							}
+						}
+					}
+				});
+
+				if let Some(current) = InterpMode::of(&curve.keys, self.selected_key) {
+					ui.horizontal(|ui| {
+						ui.label("To next key");
+						egui::ComboBox::from_id_salt("InterpMode")
+							.selected_text(current.name())
+							.show_ui(ui, |ui| {
+								for mode in InterpMode::ALL {
+									let available = mode.available(&curve.keys, self.selected_key);
+									let clicked = ui
+										.add_enabled_ui(available, |ui| {
+											ui.selectable_label(current == mode, mode.name())
+												.on_disabled_hover_text(
+													"Holding needs the next key more than a frame later and at another value",
+												)
+										})
+										.inner
+										.clicked();
+									if clicked {
+										let added = mode.apply(&mut curve.keys, self.selected_key);
+										shift_selection(
+											&mut self.selected_key,
+											&mut self.selected_keys,
+											self.selected_key + 1,
+											added,
+										);
+									}
+								}
+							});
+					});
+				}
+
+				ui.separator();
+
+				ui.horizontal(|ui| {
+					let id = egui::Id::new("CurveValueOffset");
+					let mut offset = ui.data(|data| data.get_temp::<f32>(id)).unwrap_or(0.0);
+					ui.label("Offset values");
+					crate::app::num_edit(ui, &mut offset, 2);
+					ui.data_mut(|data| data.insert_temp(id, offset));
+
+					if ui.button("Apply").clicked() {
+						for key in &mut curve.keys {
+							key.value += offset;
+						}
+					}
+				});
+
+				ui.horizontal(|ui| {
+					let id = egui::Id::new("CurveValueScale");
+					let mut scale = ui.data(|data| data.get_temp::<f32>(id)).unwrap_or(1.0);
+					ui.label("Scale values");
+					crate::app::num_edit(ui, &mut scale, 2);
+					ui.data_mut(|data| data.insert_temp(id, scale));
+
+					if ui.button("Apply").clicked() {
+						// Tangents are slopes so they scale along with the values
+						for key in &mut curve.keys {
+							key.value *= scale;
+							key.tangent *= scale;
+						}
+					}
+				});
+
+				if ui
+					.button("Flatten ends")
+					.on_hover_text(
+						"Set the tangents of the first and last keys to 0 so the curve eases in and out",
+					)
+					.clicked()
+				{
+					if let Some(first) = curve.keys.first_mut() {
+						first.tangent = 0.0;
+					}
+					if let Some(last) = curve.keys.last_mut() {
+						last.tangent = 0.0;
+					}
+				}
+
+				ui.separator();
+
+				ui.horizontal(|ui| {
+					if ui.button("Export CSV").clicked() {
+						let csv = curve_to_csv(curve);
+						let name = self.name.clone();
+						std::thread::spawn(move || {
+							tokio::runtime::Builder::new_current_thread()
+								.enable_io()
+								.build()
+								.unwrap()
+								.block_on(async {
+									let Some(file) = rfd::AsyncFileDialog::new()
+										.add_filter("CSV (.csv)", &["csv"])
+										.set_file_name(format!("{name}.csv"))
+										.save_file()
+										.await
+									else {
+										return;
+									};
+
+									file.write(csv.as_bytes()).await.unwrap();
+								});
+						});
+					}
+
+					if ui
+						.add_enabled(
+							!table_view && curve.keys.len() > 1,
+							egui::Button::new("Save plot image"),
+						)
+						.on_hover_text("Save the plot as it is shown to a PNG")
+						.clicked()
+					{
+						ui.ctx()
+							.send_viewport_cmd(egui::ViewportCommand::Screenshot(
+								egui::UserData::new(PlotScreenshot),
+							));
+					}
+
+					if ui.button("Import CSV").clicked() {
+						let (tx, rx) = mpsc::channel();
+						std::thread::spawn(move || {
+							tokio::runtime::Builder::new_current_thread()
+								.enable_io()
+								.build()
+								.unwrap()
+								.block_on(async {
+									let Some(file) = rfd::AsyncFileDialog::new()
+										.add_filter("CSV (.csv)", &["csv"])
+										.pick_file()
+										.await
+									else {
+										tx.send(None).unwrap();
+										return;
+									};
+
+									let path = file.path();
+									let data = file.read().await;
+									tx.send(Some((path.to_path_buf(), data))).unwrap();
+								});
+						});
+
+						self.curve_file_picker_result = Some(Rc::new(rx));
+					}
 				});
 
 				ui.take_available_space();
 			});
 
+		if table_view {
+			let height = ui.text_style_height(&egui::TextStyle::Body);
+			let mut resort = false;
+			egui_extras::TableBuilder::new(ui)
+				.striped(true)
+				.column(egui_extras::Column::auto())
+				.columns(egui_extras::Column::remainder(), 3)
+				.header(height, |mut header| {
+					for label in ["Key", "Frame", "Value", "Tangent"] {
+						header.col(|ui| {
+							ui.strong(label);
+						});
+					}
+				})
+				.body(|body| {
+					body.rows(height, curve.keys.len(), |mut row| {
+						let i = row.index();
+						row.col(|ui| {
+							let resp = ui.selectable_label(
+								self.selected_keys.contains(&i),
+								format!("{}", i + 1),
+							);
+							if resp.clicked() {
+								if ui
+									.input(|input| input.modifiers.shift || input.modifiers.command)
+								{
+									toggle_key(&mut self.selected_key, &mut self.selected_keys, i);
+								} else {
+									self.selected_key = i;
+									self.selected_keys = vec![i];
+								}
+							}
+						});
+						row.col(|ui| {
+							let key = &mut curve.keys[i];
+							if crate::app::time_edit(ui, &mut key.frame).changed() {
+								key.frame = key.frame.clamp(self.start_time, self.end_time);
+								resort = true;
+							}
+						});
+						row.col(|ui| {
+							crate::app::num_edit(ui, &mut curve.keys[i].value, 2);
+						});
+						row.col(|ui| {
+							crate::app::num_edit(ui, &mut curve.keys[i].tangent, 2);
+						});
+					});
+				});
+
+			if resort {
+				sort_keys(curve, &mut self.selected_key, &mut self.selected_keys);
+			}
+			return;
+		}
+
 		if curve.keys.len() <= 1 {
 			return;
 		}
@@ -2276,6 +5193,11 @@ impl AetLayerNode {
 		let ids = (0..curve.keys.len())
 			.map(|i| egui::Id::new(format!("Key {}", i + 1)))
 			.collect::<Vec<_>>();
+		let tangent_ids = [egui::Id::new("TangentIn"), egui::Id::new("TangentOut")];
+		// Plot position where a box selection was started
+		let box_id = egui::Id::new("KeySelectBox");
+		let box_start = ui.data(|data| data.get_temp::<[f64; 2]>(box_id));
+		let additive = ui.input(|input| input.modifiers.shift || input.modifiers.command);
 
 		let resp = egui_plot::Plot::new("CurveViewer")
 			.allow_drag(false)
@@ -2302,25 +5224,196 @@ impl AetLayerNode {
 				}
 
 				for (i, key) in curve.keys.iter().enumerate() {
+					let color = if self.selected_keys.contains(&i) {
+						egui::Color32::from_rgba_unmultiplied(0xE0, 0xB0, 0x40, 0xD0)
+					} else {
+						egui::Color32::from_rgba_unmultiplied(0x50, 0x60, 0xD0, 0xA0)
+					};
 					plot.points(
 						egui_plot::Points::new(
 							format!("Key {}", i + 1),
 							vec![[key.frame as f64, key.value as f64]],
 						)
 						.id(ids[i])
-						.color(egui::Color32::from_rgba_unmultiplied(
-							0x50, 0x60, 0xD0, 0xA0,
-						))
+						.color(color)
 						.radius(5.0),
 					);
 				}
+
+				if let Some([x, y]) = box_start
+					&& let Some(pointer) = plot.pointer_coordinate()
+				{
+					plot.polygon(
+						egui_plot::Polygon::new(
+							"Selection",
+							egui_plot::PlotPoints::from(vec![
+								[x, y],
+								[pointer.x, y],
+								[pointer.x, pointer.y],
+								[x, pointer.y],
+							]),
+						)
+						.allow_hover(false),
+					);
+				}
+
+				// Tangent handles of the selected key, a fixed fraction of the visible width
+				let key = &curve.keys[self.selected_key];
+				let length = plot.plot_bounds().width() * 0.05;
+				let handles = [
+					[
+						key.frame as f64 - length,
+						key.value as f64 - key.tangent as f64 * length,
+					],
+					[
+						key.frame as f64 + length,
+						key.value as f64 + key.tangent as f64 * length,
+					],
+				];
+				let handle_color = egui::Color32::from_rgba_unmultiplied(0x50, 0xA0, 0x60, 0xA0);
+
+				plot.line(
+					egui_plot::Line::new("Tangent", egui_plot::PlotPoints::from(handles.to_vec()))
+						.color(handle_color)
+						.allow_hover(false),
+				);
+				for (handle, id) in handles.iter().zip(tangent_ids.iter()) {
+					plot.points(
+						egui_plot::Points::new("Tangent", vec![*handle])
+							.id(*id)
+							.color(handle_color)
+							.radius(4.0),
+					);
+				}
 			});
 
+		// Screenshots arrive a frame or more after being requested
+		let screenshot = ui.input(|input| {
+			input.raw.events.iter().find_map(|event| match event {
+				egui::Event::Screenshot {
+					user_data, image, ..
+				} if user_data
+					.data
+					.as_ref()
+					.is_some_and(|data| data.is::<PlotScreenshot>()) =>
+				{
+					Some(image.clone())
+				}
+				_ => None,
+			})
+		});
+		if let Some(image) = screenshot {
+			let region = image.region(&resp.response.rect, Some(ui.ctx().pixels_per_point()));
+			export_plot(&self.name, region);
+		}
+
 		if resp.response.clicked()
 			&& let Some(hovered) = resp.hovered_plot_item
 			&& let Some(index) = ids.iter().position(|id| *id == hovered)
 		{
-			self.selected_key = index;
+			if additive {
+				toggle_key(&mut self.selected_key, &mut self.selected_keys, index);
+			} else {
+				self.selected_key = index;
+				self.selected_keys = vec![index];
+			}
+		}
+
+		if resp.response.drag_started() {
+			let hovered = resp.hovered_plot_item;
+			let key = hovered.and_then(|hovered| ids.iter().position(|id| *id == hovered));
+			if hovered.is_some_and(|hovered| tangent_ids.contains(&hovered)) {
+				self.dragging_tangent = true;
+			} else if let Some(index) = key {
+				// Grabbing an unselected key drags only that one
+				if !self.selected_keys.contains(&index) {
+					self.selected_keys = vec![index];
+				}
+				self.selected_key = index;
+				self.dragging_key = true;
+			} else if let Some(pos) = resp.response.interact_pointer_pos() {
+				let point = resp.transform.value_from_position(pos);
+				ui.data_mut(|data| data.insert_temp(box_id, [point.x, point.y]));
+			}
+		}
+
+		// The selection follows the grabbed key
+		if self.dragging_key
+			&& resp.response.dragged()
+			&& let Some(pos) = resp.response.interact_pointer_pos()
+		{
+			let point = resp.transform.value_from_position(pos);
+			let key = &curve.keys[self.selected_key];
+			let frames = point.x as f32 - key.frame;
+			let value = point.y as f32 - key.value;
+			move_keys(
+				&mut curve.keys,
+				&self.selected_keys,
+				frames,
+				value,
+				(self.start_time, self.end_time),
+			);
+		}
+
+		if self.dragging_tangent
+			&& resp.response.dragged()
+			&& let Some(pos) = resp.response.interact_pointer_pos()
+		{
+			let point = resp.transform.value_from_position(pos);
+			let key = &mut curve.keys[self.selected_key];
+			let dx = point.x - key.frame as f64;
+			if dx.abs() > f64::EPSILON {
+				key.tangent = ((point.y - key.value as f64) / dx) as f32;
+			}
+		}
+
+		if resp.response.drag_stopped() {
+			self.dragging_tangent = false;
+
+			// Keys are only resorted once let go so the dragged ones keep their indices meanwhile
+			if self.dragging_key {
+				self.dragging_key = false;
+				sort_keys(curve, &mut self.selected_key, &mut self.selected_keys);
+			}
+
+			if let Some([x, y]) = box_start
+				&& let Some(pos) = resp
+					.response
+					.interact_pointer_pos()
+					.or(resp.response.hover_pos())
+			{
+				ui.data_mut(|data| data.remove_temp::<[f64; 2]>(box_id));
+				let point = resp.transform.value_from_position(pos);
+				let (frames, values) = (
+					x.min(point.x)..=x.max(point.x),
+					y.min(point.y)..=y.max(point.y),
+				);
+				let inside = curve
+					.keys
+					.iter()
+					.enumerate()
+					.filter(|(_, key)| {
+						frames.contains(&(key.frame as f64)) && values.contains(&(key.value as f64))
+					})
+					.map(|(i, _)| i)
+					.collect::<Vec<_>>();
+
+				if additive {
+					for i in inside {
+						if !self.selected_keys.contains(&i) {
+							self.selected_keys.push(i);
+						}
+					}
+				} else if let Some(first) = inside.first() {
+					self.selected_key = *first;
+					self.selected_keys = inside;
+				}
+			}
+		}
+
+		// Released outside of the plot
+		if box_start.is_some() && !resp.response.dragged() && !resp.response.drag_stopped() {
+			ui.data_mut(|data| data.remove_temp::<[f64; 2]>(box_id));
 		}
 	}
 
@@ -2335,7 +5428,7 @@ impl AetLayerNode {
 		self.markers = other.markers.clone();
 		self.video = other.video.clone();
 		self.audio = other.audio.clone();
-		self.audio = other.audio.clone();
+		self.note = other.note.clone();
 
 		if let AetItemNode::Comp(a) = &mut self.item
 			&& let AetItemNode::Comp(b) = &other.item
@@ -2350,6 +5443,217 @@ impl AetLayerNode {
 	}
 }
 
+// One key per line as `frame,value,tangent`
+fn curve_to_csv(curve: &aet::FCurve) -> String {
+	let mut csv = String::from("frame,value,tangent\n");
+	for key in &curve.keys {
+		csv.push_str(&format!("{},{},{}\n", key.frame, key.value, key.tangent));
+	}
+	csv
+}
+
+struct PlotScreenshot;
+
+fn export_plot(name: &str, image: egui::ColorImage) {
+	let Some(image) = image::RgbaImage::from_raw(
+		image.size[0] as u32,
+		image.size[1] as u32,
+		image
+			.pixels
+			.iter()
+			.flat_map(|pixel| pixel.to_array())
+			.collect(),
+	) else {
+		return;
+	};
+
+	let name = name.to_string();
+	std::thread::spawn(move || {
+		tokio::runtime::Builder::new_current_thread()
+			.enable_io()
+			.build()
+			.unwrap()
+			.block_on(async {
+				let Some(file) = rfd::AsyncFileDialog::new()
+					.add_filter("PNG", &["png"])
+					.set_file_name(format!("{name}.png"))
+					.save_file()
+					.await
+				else {
+					return;
+				};
+
+				let mut buf = std::io::Cursor::new(Vec::new());
+				if let Err(_) = image::DynamicImage::ImageRgba8(image)
+					.write_to(&mut buf, image::ImageFormat::Png)
+				{
+					return;
+				};
+
+				file.write(&buf.into_inner()).await.unwrap();
+			});
+	});
+}
+
+// A bar per channel, full at the resting volume of 1
+fn volume_meter(ui: &mut egui::Ui, left: f32, right: f32) {
+	for (channel, level) in [("L", left), ("R", right)] {
+		ui.add(
+			egui::ProgressBar::new(level.clamp(0.0, 1.0))
+				.desired_height(ui.spacing().interact_size.y * 0.75)
+				.text(format!("{channel} {:.0}%", level * 100.0)),
+		);
+	}
+}
+
+// More than one key, or a single key away from the curve's resting value
+fn is_animated(curve_type: CurveType, curve: &aet::FCurve) -> bool {
+	let rest = match curve_type {
+		CurveType::ScaleX
+		| CurveType::ScaleY
+		| CurveType::ScaleZ
+		| CurveType::Opacity
+		| CurveType::VolumeL
+		| CurveType::VolumeR => 1.0,
+		_ => 0.0,
+	};
+	match curve.keys.as_slice() {
+		[] => false,
+		[key] => key.value != rest,
+		_ => true,
+	}
+}
+
+// Moves the keys by the same amount, held back so none leaves the range
+fn move_keys(
+	keys: &mut [aet::FCurveKey],
+	selected: &[usize],
+	frames: f32,
+	value: f32,
+	(start, end): (f32, f32),
+) {
+	let (first, last) = selected
+		.iter()
+		.fold((f32::MAX, f32::MIN), |(first, last), i| {
+			(first.min(keys[*i].frame), last.max(keys[*i].frame))
+		});
+	let frames = frames.max(start - first).min(end - last);
+	for i in selected {
+		keys[*i].frame += frames;
+		keys[*i].value += value;
+	}
+}
+
+// Sorts the keys by frame while keeping the selection on the same keys
+fn sort_keys(curve: &mut aet::FCurve, selected_key: &mut usize, selected_keys: &mut [usize]) {
+	let mut order = (0..curve.keys.len()).collect::<Vec<_>>();
+	order.sort_by(|a, b| curve.keys[*a].frame.total_cmp(&curve.keys[*b].frame));
+	let new_index = |old: usize| order.iter().position(|i| *i == old).unwrap_or(0);
+	*selected_key = new_index(*selected_key);
+	for i in selected_keys.iter_mut() {
+		*i = new_index(*i);
+	}
+	curve.keys.sort_by(|a, b| a.frame.total_cmp(&b.frame));
+}
+
+// Keeps the selection on the same keys after some were added or removed at index
+fn shift_selection(
+	selected_key: &mut usize,
+	selected_keys: &mut Vec<usize>,
+	index: usize,
+	added: isize,
+) {
+	let removed = index..index + added.min(0).unsigned_abs();
+	let shift = |i: usize| {
+		if i >= removed.end {
+			i.saturating_add_signed(added)
+		} else {
+			i
+		}
+	};
+	selected_keys.retain(|i| !removed.contains(i));
+	for i in selected_keys.iter_mut() {
+		*i = shift(*i);
+	}
+	*selected_key = if removed.contains(selected_key) {
+		index.saturating_sub(1)
+	} else {
+		shift(*selected_key)
+	};
+	if !selected_keys.contains(selected_key) {
+		selected_keys.push(*selected_key);
+	}
+}
+
+// Adds or removes a key from the selection, the last one is always kept
+fn toggle_key(selected_key: &mut usize, selected_keys: &mut Vec<usize>, index: usize) {
+	if let Some(pos) = selected_keys.iter().position(|i| *i == index) {
+		if selected_keys.len() > 1 {
+			selected_keys.remove(pos);
+			if *selected_key == index {
+				*selected_key = selected_keys[0];
+			}
+		}
+	} else {
+		selected_keys.push(index);
+		*selected_key = index;
+	}
+}
+
+// Adds a key with the curve's current value, returns its index
+fn add_key(curve: &mut aet::FCurve, frame: f32) -> usize {
+	curve.keys.push(aet::FCurveKey {
+		frame,
+		value: curve.interpolate(frame),
+		tangent: 0.0,
+	});
+	curve.keys.sort_by(|a, b| a.frame.total_cmp(&b.frame));
+	curve
+		.keys
+		.iter()
+		.position(|key| key.frame == frame)
+		.unwrap_or(0)
+}
+
+fn curve_from_csv(csv: &str) -> Result<Vec<aet::FCurveKey>, String> {
+	let mut keys = Vec::new();
+	for (i, line) in csv.lines().enumerate() {
+		let line = line.trim();
+		if line.is_empty() || (i == 0 && line.starts_with("frame")) {
+			continue;
+		}
+
+		let values = line
+			.split(',')
+			.map(|value| value.trim().parse::<f32>())
+			.collect::<Result<Vec<_>, _>>()
+			.map_err(|e| format!("Line {}: {e}", i + 1))?;
+		let [frame, value, tangent] = values[..] else {
+			return Err(format!(
+				"Line {}: expected 3 columns, found {}",
+				i + 1,
+				values.len()
+			));
+		};
+		if !frame.is_finite() || !value.is_finite() || !tangent.is_finite() {
+			return Err(format!("Line {}: non-finite value", i + 1));
+		}
+
+		keys.push(aet::FCurveKey {
+			frame,
+			value,
+			tangent,
+		});
+	}
+
+	if keys.is_empty() {
+		return Err(String::from("No keys found"));
+	}
+
+	keys.sort_by(|a, b| a.frame.total_cmp(&b.frame));
+	Ok(keys)
+}
+
 #[derive(Clone, PartialEq)]
 pub enum AetItemNode {
 	None,
@@ -2367,6 +5671,31 @@ pub struct AetVideoNode {
 	pub sources: Vec<AetVideoSourceNode>,
 }
 
+impl AetVideoNode {
+	// Each source is shown for fpf frames, looping back to the first after the last
+	pub fn source_at(&self, frame: f32) -> Option<&AetVideoSourceNode> {
+		if self.sources.len() <= 1 || self.fpf <= 0.0 {
+			return self.sources.first();
+		}
+
+		let index = (frame / self.fpf).floor().max(0.0) as usize % self.sources.len();
+		self.sources.get(index)
+	}
+
+	// New videos start at 0x0, which would collapse their quad to nothing
+	pub fn has_size(&self) -> bool {
+		self.width != 0 && self.height != 0
+	}
+
+	fn sprite_size(&self) -> Option<(u16, u16)> {
+		let sprite = self.sources.first()?.sprite.as_ref()?.try_lock().unwrap();
+		Some((
+			sprite.info.width().round() as u16,
+			sprite.info.height().round() as u16,
+		))
+	}
+}
+
 #[derive(Clone)]
 pub struct AetVideoSourceNode {
 	pub name: String,
@@ -2391,13 +5720,32 @@ pub struct AetAudioNode {
 	pub sound_index: u32,
 }
 
+#[cfg(feature = "audio")]
+pub struct SoundAt {
+	// Address of the layer, the same for as long as it exists
+	pub key: usize,
+	pub sound_index: u32,
+	// How far into the sound, in frames
+	pub frame: f32,
+	pub volume: [f32; 2],
+}
+
 struct WgpuAetVideos {
 	viewport_size: [f32; 2],
+	perspective: bool,
+	// Color the composition is cleared to, exports leave it transparent
+	background: Option<[f32; 3]>,
+	target_size: [u32; 2],
+	// Upscale the composition without filtering, for integer scales
+	nearest: bool,
 	videos: Vec<WgpuAetVideo>,
 }
 
-struct WgpuAetVideo {
+#[derive(Clone)]
+pub struct WgpuAetVideo {
 	is_ycbcr: bool,
+	is_hdr: bool,
+	exposure: f32,
 	is_empty: bool,
 	texture_coords: [f32; 4],
 	source_size: [f32; 2],
@@ -2407,27 +5755,54 @@ struct WgpuAetVideo {
 	blend_mode: aet::BlendMode,
 }
 
+impl WgpuAetVideo {
+	// Z of the centre of the quad, larger is further away
+	fn depth(&self) -> f32 {
+		self.mat.w.z
+			+ self.mat.x.z * (self.source_size[0] / 2.0)
+			+ self.mat.y.z * (self.source_size[1] / 2.0)
+	}
+}
+
+// After Effects' default 50mm camera looking at the centre of the scene, z = 0 stays pixel exact
+fn perspective(viewport_size: [f32; 2]) -> Mat4 {
+	let distance = viewport_size[0] * 50.0 / 36.0;
+	let mut m = Mat4::default();
+	m.z = Vec4 {
+		x: viewport_size[0] / 2.0 / distance,
+		y: viewport_size[1] / 2.0 / distance,
+		z: 1.0,
+		w: 1.0 / distance,
+	};
+	m
+}
+
 impl egui_wgpu::CallbackTrait for WgpuAetVideos {
 	fn prepare(
 		&self,
 		device: &wgpu::Device,
 		queue: &wgpu::Queue,
 		_screen_descriptor: &egui_wgpu::ScreenDescriptor,
-		_egui_encoder: &mut wgpu::CommandEncoder,
+		egui_encoder: &mut wgpu::CommandEncoder,
 		callback_resources: &mut egui_wgpu::CallbackResources,
 	) -> Vec<wgpu::CommandBuffer> {
 		let resources: &mut WgpuRenderResources = callback_resources.get_mut().unwrap();
 
 		let mut spr_infos = Vec::new();
 
+		// Draws the composition onto the screen
 		spr_infos.push(SpriteInfo {
 			matrix: crate::aet::Mat4::default().into(),
 			tex_coords: [[0.0, 0.0], [1.0, 0.0], [0.0, 1.0], [1.0, 1.0]],
-			color: [0.0, 0.0, 0.0, 1.0],
+			color: [1.0, 1.0, 1.0, 1.0],
 			is_ycbcr: 0,
-			_padding_0: 0,
-			_padding_1: 0,
-			_padding_2: 0,
+			brightness: 0.0,
+			contrast: 1.0,
+			gamma: 1.0,
+			is_placeholder: 0,
+			is_hdr: 0,
+			exposure: 0.0,
+			_padding: 0,
 		});
 
 		spr_infos.extend(self.videos.iter().map(|video| {
@@ -2436,7 +5811,7 @@ impl egui_wgpu::CallbackTrait for WgpuAetVideos {
 			m.w =
 				m.x * (video.source_size[0] / 2.0) + m.y * (video.source_size[1] / 2.0) + m.z + m.w;
 
-			let projection = Mat4 {
+			let mut projection = Mat4 {
 				x: Vec4 {
 					x: 2.0 / self.viewport_size[0],
 					y: 0.0,
@@ -2463,6 +5838,10 @@ impl egui_wgpu::CallbackTrait for WgpuAetVideos {
 				},
 			};
 
+			if self.perspective {
+				projection = projection * perspective(self.viewport_size);
+			}
+
 			let mut m = projection * m;
 			m.x = m.x * (video.source_size[0] / 2.0);
 			m.y = m.y * (-video.source_size[1] / 2.0);
@@ -2477,9 +5856,13 @@ impl egui_wgpu::CallbackTrait for WgpuAetVideos {
 				],
 				color: video.color,
 				is_ycbcr: if video.is_ycbcr { 1 } else { 0 },
-				_padding_0: 0,
-				_padding_1: 0,
-				_padding_2: 0,
+				brightness: 0.0,
+				contrast: 1.0,
+				gamma: 1.0,
+				is_placeholder: if video.is_empty { 1 } else { 0 },
+				is_hdr: if video.is_hdr { 1 } else { 0 },
+				exposure: video.exposure,
+				_padding: 0,
 			}
 		}));
 
@@ -2511,6 +5894,96 @@ impl egui_wgpu::CallbackTrait for WgpuAetVideos {
 			);
 		}
 
+		// Release buffers left over from a spike in layer count
+		if resources.uniform_buffers.len() > UNIFORM_BUFFERS_MIN_RETAINED
+			&& resources.uniform_buffers.len() > spr_infos.len() * 2
+		{
+			resources
+				.uniform_buffers
+				.truncate(spr_infos.len().max(UNIFORM_BUFFERS_MIN_RETAINED));
+		}
+
+		UNIFORM_BUFFERS_ALLOCATED.store(resources.uniform_buffers.len(), atomic::Ordering::Relaxed);
+		UNIFORM_BUFFERS_USED.store(spr_infos.len(), atomic::Ordering::Relaxed);
+
+		let size = [self.target_size[0].max(1), self.target_size[1].max(1)];
+		if resources
+			.composite_target
+			.as_ref()
+			.is_none_or(|target| target.size != size || target.nearest != self.nearest)
+		{
+			resources.composite_target =
+				Some(CompositeTarget::new(device, resources, size, self.nearest));
+		}
+
+		let resources: &WgpuRenderResources = callback_resources.get().unwrap();
+		let textures: &WgpuRenderTextures = callback_resources.get().unwrap();
+		let Some(target) = &resources.composite_target else {
+			return Vec::new();
+		};
+
+		let clear = self
+			.background
+			.map_or(wgpu::Color::TRANSPARENT, |[r, g, b]| wgpu::Color {
+				r: r as f64,
+				g: g as f64,
+				b: b as f64,
+				a: 1.0,
+			});
+		let mut render_pass = egui_encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+			label: Some("Composite pass"),
+			color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+				view: &target.view,
+				depth_slice: None,
+				resolve_target: None,
+				ops: wgpu::Operations {
+					load: wgpu::LoadOp::Clear(clear),
+					store: wgpu::StoreOp::Store,
+				},
+			})],
+			depth_stencil_attachment: None,
+			timestamp_writes: None,
+			occlusion_query_set: None,
+		});
+
+		render_pass.set_vertex_buffer(0, resources.vertex_buffer.slice(..));
+
+		// Premultiplied so blending matches the game instead of egui's straight alpha
+		// Drawn in layer order, so the pipeline only changes where the blend mode does
+		let mut bound = None;
+		for (i, video) in self.videos.iter().enumerate() {
+			if bound != Some(video.blend_mode) {
+				bound = Some(video.blend_mode);
+				match video.blend_mode {
+					aet::BlendMode::Screen => {
+						render_pass.set_pipeline(&resources.pipeline_premultiplied_screen)
+					}
+					aet::BlendMode::Add => {
+						render_pass.set_pipeline(&resources.pipeline_premultiplied_add)
+					}
+					aet::BlendMode::Multiply => {
+						render_pass.set_pipeline(&resources.pipeline_premultiplied_multiply)
+					}
+					aet::BlendMode::Overlay => {
+						render_pass.set_pipeline(&resources.pipeline_overlay)
+					}
+					_ => render_pass.set_pipeline(&resources.pipeline_premultiplied_normal),
+				}
+			}
+			// Sprites past the end of the texture list are drawn blank rather than panicking
+			let bind_group = if video.is_empty {
+				&textures.empty_texture
+			} else {
+				textures
+					.fragment_bind_group
+					.get(video.texture_index)
+					.map_or(&textures.empty_texture, |(_, bind_group)| bind_group)
+			};
+			render_pass.set_bind_group(0, bind_group, &[]);
+			render_pass.set_bind_group(1, &resources.uniform_buffers[i + 1].1, &[]);
+			render_pass.draw(0..6, 0..1);
+		}
+
 		Vec::new()
 	}
 
@@ -2521,33 +5994,558 @@ impl egui_wgpu::CallbackTrait for WgpuAetVideos {
 		callback_resources: &egui_wgpu::CallbackResources,
 	) {
 		let resources: &WgpuRenderResources = callback_resources.get().unwrap();
-		let textures: &WgpuRenderTextures = callback_resources.get().unwrap();
+		let Some(target) = &resources.composite_target else {
+			return;
+		};
 
 		render_pass.set_vertex_buffer(0, resources.vertex_buffer.slice(..));
-
-		// Draw black base
-		render_pass.set_pipeline(&resources.pipeline_normal);
-		render_pass.set_bind_group(0, &textures.empty_texture, &[]);
+		render_pass.set_pipeline(&resources.pipeline_composite);
+		render_pass.set_bind_group(0, &target.bind_group, &[]);
 		render_pass.set_bind_group(1, &resources.uniform_buffers[0].1, &[]);
 		render_pass.draw(0..6, 0..1);
+	}
+}
 
-		for (i, video) in self.videos.iter().enumerate() {
-			match video.blend_mode {
-				aet::BlendMode::Screen => render_pass.set_pipeline(&resources.pipeline_screen),
-				aet::BlendMode::Add => render_pass.set_pipeline(&resources.pipeline_add),
-				_ => render_pass.set_pipeline(&resources.pipeline_normal),
-			}
-			if video.is_empty {
-				render_pass.set_bind_group(0, &textures.empty_texture, &[]);
+fn format_bytes(bytes: usize) -> String {
+	if bytes >= 1024 * 1024 {
+		format!("{:.1} MiB", bytes as f32 / (1024.0 * 1024.0))
+	} else {
+		format!("{:.1} KiB", bytes as f32 / 1024.0)
+	}
+}
+
+// Draws the videos into a texture the size of their viewport and reads it back
+fn render_offscreen(
+	render_state: &egui_wgpu::RenderState,
+	videos: &WgpuAetVideos,
+) -> Result<image::RgbaImage, String> {
+	let device = &render_state.device;
+	let queue = &render_state.queue;
+	let width = videos.viewport_size[0] as u32;
+	let height = videos.viewport_size[1] as u32;
+	let size = wgpu::Extent3d {
+		width,
+		height,
+		depth_or_array_layers: 1,
+	};
+
+	let mut renderer = render_state.renderer.write();
+	let resources = &mut renderer.callback_resources;
+	if resources.get::<WgpuRenderTextures>().is_none() {
+		return Err(String::from("Load a sprite set to render the scene"));
+	}
+
+	// The pipelines only draw to the format egui presents with
+	let texture = device.create_texture(&wgpu::TextureDescriptor {
+		label: Some("Offscreen texture"),
+		size,
+		mip_level_count: 1,
+		sample_count: 1,
+		dimension: wgpu::TextureDimension::D2,
+		format: render_state.target_format,
+		usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+		view_formats: &[],
+	});
+	let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+	let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+		label: Some("Offscreen encoder"),
+	});
+	let screen_descriptor = egui_wgpu::ScreenDescriptor {
+		size_in_pixels: [width, height],
+		pixels_per_point: 1.0,
+	};
+	let mut command_buffers =
+		videos.prepare(device, queue, &screen_descriptor, &mut encoder, resources);
+
+	{
+		let mut render_pass = encoder
+			.begin_render_pass(&wgpu::RenderPassDescriptor {
+				label: Some("Offscreen pass"),
+				color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+					view: &view,
+					depth_slice: None,
+					resolve_target: None,
+					ops: wgpu::Operations {
+						load: wgpu::LoadOp::Clear(wgpu::Color::TRANSPARENT),
+						store: wgpu::StoreOp::Store,
+					},
+				})],
+				depth_stencil_attachment: None,
+				timestamp_writes: None,
+				occlusion_query_set: None,
+			})
+			.forget_lifetime();
+		let rect =
+			egui::Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(width as f32, height as f32));
+		videos.paint(
+			egui::PaintCallbackInfo {
+				viewport: rect,
+				clip_rect: rect,
+				pixels_per_point: 1.0,
+				screen_size_px: [width, height],
+			},
+			&mut render_pass,
+			resources,
+		);
+	}
+
+	let bytes_per_row = (width * 4).div_ceil(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT)
+		* wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+	let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+		label: Some("Offscreen buffer"),
+		size: (bytes_per_row * height) as wgpu::BufferAddress,
+		usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+		mapped_at_creation: false,
+	});
+	encoder.copy_texture_to_buffer(
+		texture.as_image_copy(),
+		wgpu::TexelCopyBufferInfo {
+			buffer: &buffer,
+			layout: wgpu::TexelCopyBufferLayout {
+				offset: 0,
+				bytes_per_row: Some(bytes_per_row),
+				rows_per_image: Some(height),
+			},
+		},
+		size,
+	);
+
+	let (tx, rx) = mpsc::channel();
+	encoder.map_buffer_on_submit(&buffer, wgpu::MapMode::Read, .., move |res| {
+		_ = tx.send(res);
+	});
+	command_buffers.push(encoder.finish());
+	queue.submit(command_buffers);
+	_ = device.poll(wgpu::PollType::wait_indefinitely());
+
+	let Ok(Ok(())) = rx.recv() else {
+		return Err(String::from("Could not read the rendered frame"));
+	};
+	let bgra = matches!(
+		render_state.target_format,
+		wgpu::TextureFormat::Bgra8Unorm | wgpu::TextureFormat::Bgra8UnormSrgb
+	);
+	let mut pixels = Vec::with_capacity((width * height * 4) as usize);
+	for row in buffer
+		.get_mapped_range(..)
+		.chunks(bytes_per_row as usize)
+		.take(height as usize)
+	{
+		for pixel in row[..(width * 4) as usize].chunks(4) {
+			let [r, g, b, a] = if bgra {
+				[pixel[2], pixel[1], pixel[0], pixel[3]]
 			} else {
-				render_pass.set_bind_group(
-					0,
-					&textures.fragment_bind_group[video.texture_index].1,
-					&[],
-				);
+				[pixel[0], pixel[1], pixel[2], pixel[3]]
+			};
+			// The composition is premultiplied, images are stored straight
+			let straight = |c: u8| {
+				if a == 0 {
+					c
+				} else {
+					(c as u32 * 255 / a as u32).min(255) as u8
+				}
+			};
+			pixels.extend([straight(r), straight(g), straight(b), a]);
+		}
+	}
+	buffer.unmap();
+
+	image::RgbaImage::from_raw(width, height, pixels)
+		.ok_or_else(|| String::from("Could not read the rendered frame"))
+}
+
+#[derive(Clone)]
+struct StripMetadata {
+	cell_width: u32,
+	cell_height: u32,
+	frame_count: u32,
+	columns: u32,
+	rows: u32,
+	fps: f32,
+}
+
+impl StripMetadata {
+	fn to_json(&self) -> String {
+		format!(
+			"{{\n\t\"cell_width\": {},\n\t\"cell_height\": {},\n\t\"frame_count\": {},\n\t\"columns\": {},\n\t\"rows\": {},\n\t\"fps\": {}\n}}\n",
+			self.cell_width, self.cell_height, self.frame_count, self.columns, self.rows, self.fps
+		)
+	}
+}
+
+// Saves the strip as a PNG and its metadata as a JSON file next to it
+fn export_strip(name: &str, image: image::RgbaImage, metadata: StripMetadata) {
+	let name = name.to_string();
+	std::thread::spawn(move || {
+		tokio::runtime::Builder::new_current_thread()
+			.enable_io()
+			.build()
+			.unwrap()
+			.block_on(async {
+				let Some(file) = rfd::AsyncFileDialog::new()
+					.add_filter("PNG", &["png"])
+					.set_file_name(format!("{name}.png"))
+					.save_file()
+					.await
+				else {
+					return;
+				};
+
+				let mut buf = std::io::Cursor::new(Vec::new());
+				if image::DynamicImage::ImageRgba8(image)
+					.write_to(&mut buf, image::ImageFormat::Png)
+					.is_err()
+				{
+					return;
+				}
+
+				_ = file.write(&buf.into_inner()).await;
+				_ = std::fs::write(file.path().with_extension("json"), metadata.to_json());
+			});
+	});
+}
+
+// Keys past either end land on it, only the one that was nearest is kept so no two share a frame
+fn clamp_curve(curve: &mut aet::FCurve, start: f32, end: f32) {
+	if curve.keys.is_empty() {
+		return;
+	}
+
+	curve.keys.sort_by(|a, b| a.frame.total_cmp(&b.frame));
+	let first = curve
+		.keys
+		.iter()
+		.rposition(|key| key.frame <= start)
+		.unwrap_or(0);
+	let last = curve
+		.keys
+		.iter()
+		.position(|key| key.frame >= end)
+		.map_or(curve.keys.len(), |i| i + 1)
+		.max(first + 1);
+	curve.keys.truncate(last);
+	curve.keys.drain(..first);
+
+	for key in &mut curve.keys {
+		key.frame = key.frame.clamp(start, end);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn curve(keys: &[(f32, f32)]) -> aet::FCurve {
+		aet::FCurve {
+			keys: keys
+				.iter()
+				.map(|&(frame, value)| aet::FCurveKey {
+					frame,
+					value,
+					tangent: 0.0,
+				})
+				.collect(),
+		}
+	}
+
+	fn layer(
+		name: &str,
+		item: aet::Item,
+		parent: Option<Rc<Mutex<aet::Layer>>>,
+	) -> Rc<Mutex<aet::Layer>> {
+		let audio = matches!(item, aet::Item::Audio(_));
+		Rc::new(Mutex::new(aet::Layer {
+			name: String::from(name),
+			start_time: 5.0,
+			end_time: 55.0,
+			offset_time: 2.0,
+			time_scale: 0.5,
+			flags: aet::LayerFlags::new().with_video_active(!audio),
+			quality: aet::LayerQuality::Best,
+			item,
+			markers: Vec::new(),
+			video: (!audio).then(|| aet::LayerVideo {
+				transfer_mode: aet::TransferMode {
+					mode: aet::BlendMode::Normal,
+					flag: 0,
+					matte: 0,
+				},
+				anchor_x: curve(&[(0.0, 16.0)]),
+				anchor_y: curve(&[(0.0, 8.0)]),
+				pos_x: curve(&[(0.0, 100.0), (30.0, 300.0)]),
+				pos_y: curve(&[(0.0, 50.0), (15.0, 60.0), (30.0, 50.0)]),
+				rot_z: curve(&[(10.0, 0.0), (20.0, 90.0)]),
+				scale_x: curve(&[(0.0, 1.0)]),
+				scale_y: curve(&[(0.0, 2.0)]),
+				opacity: curve(&[(0.0, 0.0), (10.0, 1.0)]),
+				_3d: None,
+			}),
+			parent,
+			audio: audio.then(|| aet::LayerAudio {
+				volume_l: curve(&[(0.0, 1.0)]),
+				volume_r: curve(&[(0.0, 0.5)]),
+				pan_l: curve(&[]),
+				pan_r: curve(&[]),
+			}),
+		}))
+	}
+
+	#[test]
+	fn aet_round_trip() {
+		let video = |name: &str| {
+			aet::Item::Video(aet::Video {
+				color: [255, 128, 0],
+				width: 32,
+				height: 16,
+				fpf: 1.0,
+				sources: vec![aet::VideoSource {
+					name: String::from(name),
+					id: 0x1234,
+				}],
+			})
+		};
+
+		let parent = layer("parent", video("SPR_PARENT"), None);
+		let child = layer("child", video("SPR_CHILD"), Some(parent.clone()));
+		let nested = layer(
+			"nested",
+			aet::Item::Composition(aet::Composition {
+				layers: vec![layer("inner", video("SPR_INNER"), None)],
+			}),
+			None,
+		);
+		let sound = layer(
+			"sound",
+			aet::Item::Audio(aet::Audio { sound_index: 3 }),
+			None,
+		);
+
+		let set = aet::Set {
+			modern: false,
+			big_endian: false,
+			is_x: false,
+			scenes: vec![aet::Scene {
+				name: String::from("TEST_SCENE"),
+				start_time: 0.0,
+				end_time: 60.0,
+				fps: 60.0,
+				color: [0, 0, 0],
+				width: 1280,
+				height: 720,
+				camera: None,
+				root: aet::Composition {
+					layers: vec![parent, child, nested, sound],
+				},
+			}],
+		};
+		let data = set.to_buf();
+
+		// Saving an unedited set writes back the same file
+		let node = AetSetNode::read("aet_test.bin", &data);
+		let saved = node.raw_data();
+		assert!(saved == data);
+		assert!(AetSetNode::read("aet_test.bin", &saved) == node);
+
+		let layers = &node.scenes[0].root.layers;
+		assert_eq!(layers.len(), 4);
+		let child = layers[1].try_lock().unwrap();
+		assert!(
+			child
+				.parent
+				.as_ref()
+				.is_some_and(|parent| Rc::ptr_eq(parent, &layers[0]))
+		);
+		let nested = layers[2].try_lock().unwrap();
+		let AetItemNode::Comp(nested) = &nested.item else {
+			panic!("nested composition was not kept");
+		};
+		assert_eq!(nested.layers.len(), 1);
+	}
+
+	// A 2D layer with every curve at its neutral value, changed by edit
+	fn transform(edit: impl FnOnce(&mut aet::LayerVideo), [x, y, z]: [f32; 3]) -> [f32; 3] {
+		let mut video = aet::LayerVideo {
+			transfer_mode: aet::TransferMode {
+				mode: aet::BlendMode::Normal,
+				flag: 0,
+				matte: 0,
+			},
+			anchor_x: curve(&[]),
+			anchor_y: curve(&[]),
+			pos_x: curve(&[]),
+			pos_y: curve(&[]),
+			rot_z: curve(&[]),
+			scale_x: curve(&[(0.0, 1.0)]),
+			scale_y: curve(&[(0.0, 1.0)]),
+			opacity: curve(&[(0.0, 1.0)]),
+			_3d: None,
+		};
+		edit(&mut video);
+
+		let mut mat = Mat4::default();
+		let mut opacity = 1.0;
+		calc_mat(&mut mat, &mut opacity, &video, 0.0);
+		let point = mat * Vec4 { x, y, z, w: 1.0 };
+		[point.x, point.y, point.z]
+	}
+
+	fn assert_near(a: [f32; 3], b: [f32; 3]) {
+		assert!(
+			a.iter().zip(&b).all(|(a, b)| (a - b).abs() < 1e-4),
+			"{a:?} != {b:?}"
+		);
+	}
+
+	#[test]
+	fn negative_rot_z() {
+		let rotate = |video: &mut aet::LayerVideo| video.rot_z = curve(&[(0.0, -90.0)]);
+		assert_near(transform(rotate, [0.0, 0.0, 0.0]), [0.0, 0.0, 0.0]);
+		assert_near(transform(rotate, [32.0, 0.0, 0.0]), [0.0, -32.0, 0.0]);
+		assert_near(transform(rotate, [0.0, 16.0, 0.0]), [16.0, 0.0, 0.0]);
+		assert_near(transform(rotate, [32.0, 16.0, 0.0]), [16.0, -32.0, 0.0]);
+
+		// The opposite way round from a positive rotation
+		let rotate = |video: &mut aet::LayerVideo| video.rot_z = curve(&[(0.0, 90.0)]);
+		assert_near(transform(rotate, [32.0, 16.0, 0.0]), [-16.0, 32.0, 0.0]);
+	}
+
+	fn curves(video: &mut aet::LayerVideo) -> &mut aet::LayerVideo3D {
+		video._3d.insert(aet::LayerVideo3D {
+			anchor_z: curve(&[]),
+			pos_z: curve(&[]),
+			dir_x: curve(&[]),
+			dir_y: curve(&[]),
+			dir_z: curve(&[]),
+			rot_x: curve(&[]),
+			rot_y: curve(&[]),
+			scale_z: curve(&[(0.0, 1.0)]),
+		})
+	}
+
+	// dir and rot on the same axis turn the same way, only their order differs
+	#[test]
+	fn negative_dir_and_rot() {
+		let x = |value: f32, dir: bool| {
+			move |video: &mut aet::LayerVideo| {
+				let _3d = curves(video);
+				let axis = if dir { &mut _3d.dir_x } else { &mut _3d.rot_x };
+				*axis = curve(&[(0.0, value)]);
 			}
-			render_pass.set_bind_group(1, &resources.uniform_buffers[i + 1].1, &[]);
-			render_pass.draw(0..6, 0..1);
+		};
+		let y = |value: f32, dir: bool| {
+			move |video: &mut aet::LayerVideo| {
+				let _3d = curves(video);
+				let axis = if dir { &mut _3d.dir_y } else { &mut _3d.rot_y };
+				*axis = curve(&[(0.0, value)]);
+			}
+		};
+		let z = |value: f32| {
+			move |video: &mut aet::LayerVideo| curves(video).dir_z = curve(&[(0.0, value)])
+		};
+
+		for dir in [true, false] {
+			assert_near(transform(x(-90.0, dir), [0.0, 16.0, 0.0]), [0.0, 0.0, 16.0]);
+			assert_near(transform(x(90.0, dir), [0.0, 16.0, 0.0]), [0.0, 0.0, -16.0]);
+			assert_near(
+				transform(y(-90.0, dir), [32.0, 0.0, 0.0]),
+				[0.0, 0.0, -32.0],
+			);
+			assert_near(transform(y(90.0, dir), [32.0, 0.0, 0.0]), [0.0, 0.0, 32.0]);
+		}
+		assert_near(transform(z(-90.0), [32.0, 16.0, 0.0]), [16.0, -32.0, 0.0]);
+		assert_near(transform(z(90.0), [32.0, 16.0, 0.0]), [-16.0, 32.0, 0.0]);
+	}
+
+	fn keys(curve: &aet::FCurve) -> Vec<(f32, f32, f32)> {
+		curve
+			.keys
+			.iter()
+			.map(|key| (key.frame, key.value, key.tangent))
+			.collect()
+	}
+
+	#[test]
+	fn interp_mode_round_trip() {
+		let mut curve = curve(&[(0.0, 0.0), (10.0, 5.0), (20.0, 5.0)]);
+		let authored = |curve: &aet::FCurve| {
+			keys(curve)
+				.into_iter()
+				.filter(|(frame, _, _)| *frame != 9.0)
+				.map(|(frame, value, _)| (frame, value))
+				.collect::<Vec<_>>()
+		};
+		let original = authored(&curve);
+
+		for (mode, added) in [
+			(InterpMode::Hold, 1),
+			(InterpMode::Linear, -1),
+			(InterpMode::Hermite, 0),
+			(InterpMode::Hold, 1),
+			(InterpMode::Hermite, -1),
+			(InterpMode::Linear, 0),
+		] {
+			assert_eq!(mode.apply(&mut curve.keys, 0), added);
+			assert!(InterpMode::of(&curve.keys, 0) == Some(mode));
+			assert_eq!(authored(&curve), original);
+			// Applying the mode it already has changes nothing
+			assert_eq!(mode.apply(&mut curve.keys, 0), 0);
 		}
+
+		InterpMode::Hold.apply(&mut curve.keys, 0);
+		assert_eq!(
+			keys(&curve)[..3],
+			[(0.0, 0.0, 0.0), (9.0, 0.0, 0.0), (10.0, 5.0, 0.0)]
+		);
+		assert!(InterpMode::of(&curve.keys, 2) == Some(InterpMode::Linear));
+		assert!(InterpMode::of(&curve.keys, 3).is_none());
+	}
+
+	#[test]
+	fn interp_mode_hold_unavailable() {
+		// One frame apart, there is no room for the helper key
+		let mut close = curve(&[(0.0, 0.0), (1.0, 5.0)]);
+		assert!(!InterpMode::Hold.available(&close.keys, 0));
+		let before = keys(&close);
+		assert_eq!(InterpMode::Hold.apply(&mut close.keys, 0), 0);
+		assert_eq!(keys(&close), before);
+
+		// Holding onto the same value is a flat linear segment
+		let mut flat = curve(&[(0.0, 5.0), (10.0, 5.0)]);
+		assert!(!InterpMode::Hold.available(&flat.keys, 0));
+		assert_eq!(InterpMode::Hold.apply(&mut flat.keys, 0), 0);
+		assert!(InterpMode::of(&flat.keys, 0) == Some(InterpMode::Linear));
+	}
+
+	#[test]
+	fn clamp_curve_drops_stacked_keys() {
+		let mut clamped = curve(&[
+			(0.0, 1.0),
+			(2.0, 2.0),
+			(10.0, 3.0),
+			(20.0, 4.0),
+			(30.0, 5.0),
+			(40.0, 6.0),
+		]);
+		clamp_curve(&mut clamped, 5.0, 25.0);
+		assert_eq!(
+			keys(&clamped),
+			[
+				(5.0, 2.0, 0.0),
+				(10.0, 3.0, 0.0),
+				(20.0, 4.0, 0.0),
+				(25.0, 5.0, 0.0)
+			]
+		);
+
+		// A key already on the boundary is the nearest
+		let mut clamped = curve(&[(0.0, 1.0), (5.0, 2.0), (25.0, 3.0), (30.0, 4.0)]);
+		clamp_curve(&mut clamped, 5.0, 25.0);
+		assert_eq!(keys(&clamped), [(5.0, 2.0, 0.0), (25.0, 3.0, 0.0)]);
+
+		// Keys all on one side leave a single key
+		let mut clamped = curve(&[(30.0, 1.0), (40.0, 2.0)]);
+		clamp_curve(&mut clamped, 5.0, 25.0);
+		assert_eq!(keys(&clamped), [(25.0, 1.0, 0.0)]);
 	}
 }
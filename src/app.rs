@@ -1,7 +1,9 @@
+use crate::settings::Action;
 use crate::*;
 use eframe::egui;
 use eframe::egui::NumExt;
 use egui_material_icons::icons::*;
+use notify::Watcher;
 use regex::Regex;
 use std::collections::*;
 use std::path::PathBuf;
@@ -20,6 +22,10 @@ pub trait TreeNode {
 	fn has_context_menu(&self) -> bool {
 		false
 	}
+	// Skipped by the tree while still taking up its index
+	fn is_hidden(&self) -> bool {
+		false
+	}
 	fn display_children(&mut self, _f: &mut dyn FnMut(&mut dyn TreeNode)) {}
 	fn display_tree(
 		&mut self,
@@ -58,6 +64,10 @@ pub struct LayerUndoer {
 	original_layer: aet::AetLayerNode,
 	current_path: Vec<usize>,
 	flux: Option<(f64, aet::AetLayerNode)>,
+	// Bumped whenever a change to the aet is seen
+	generation: u64,
+	// Bumped instead when only the layer at current_path changed
+	layer_generation: u64,
 }
 
 impl LayerUndoer {
@@ -65,30 +75,31 @@ impl LayerUndoer {
 		Self {
 			undos: VecDeque::new(),
 			redos: Vec::new(),
-			original_layer: aet::AetLayerNode {
-				name: String::from("DUMMY"),
-				start_time: 0.0,
-				end_time: 0.0,
-				offset_time: 0.0,
-				time_scale: 1.0,
-				flags: kkdlib::aet::LayerFlags::new(),
-				quality: kkdlib::aet::LayerQuality::None,
-				item: aet::AetItemNode::None,
-				markers: Vec::new(),
-				video: None,
-				parent: None,
-				audio: None,
-				sprites: Rc::new(Mutex::new(Vec::new())),
-				visible: false,
-				selected_key: 0,
-				want_deletion: false,
-				want_duplicate: false,
-			},
+			original_layer: aet::AetLayerNode::root_snapshot(aet::AetCompNode {
+				layers: Vec::new(),
+			}),
 			current_path: Vec::new(),
 			flux: None,
+			generation: 0,
+			layer_generation: 0,
 		}
 	}
 
+	pub fn generation(&self) -> u64 {
+		self.generation
+	}
+
+	pub fn layer_generation(&self) -> (u64, &[usize]) {
+		(self.layer_generation, &self.current_path)
+	}
+
+	// Settling an edit only records the state before it, nothing in the aet changes
+	fn record_undo(&mut self) {
+		let generation = self.generation;
+		self.add_undo(self.original_layer.clone(), self.current_path.clone());
+		self.generation = generation;
+	}
+
 	pub fn has_undo(&self) -> bool {
 		match self.undos.len() {
 			0 => self.flux.is_some(),
@@ -101,6 +112,7 @@ impl LayerUndoer {
 	}
 
 	pub fn undo(&mut self) -> Option<(aet::AetLayerNode, Vec<usize>)> {
+		self.generation += 1;
 		if self.flux.is_some() {
 			self.flux = None;
 			let res = (self.original_layer.clone(), self.current_path.clone());
@@ -113,12 +125,14 @@ impl LayerUndoer {
 	}
 
 	pub fn redo(&mut self) -> Option<(aet::AetLayerNode, Vec<usize>)> {
+		self.generation += 1;
 		self.current_path = Vec::new();
 		self.redos.pop()
 	}
 
 	// Adds a state *before* changes
 	pub fn add_undo(&mut self, layer: aet::AetLayerNode, path: Vec<usize>) {
+		self.generation += 1;
 		self.undos.push_back((layer, path));
 		if self.undos.len() > 100 {
 			self.undos.pop_front();
@@ -127,11 +141,64 @@ impl LayerUndoer {
 		self.flux = None;
 	}
 
+	// Follows scenes to their new index, history of removed scenes is dropped
+	pub fn remap_scenes(&mut self, remap: &[Option<usize>]) {
+		let new_path = |path: &[usize]| -> Option<Vec<usize>> {
+			let mut path = path.to_vec();
+			let scene = path.get_mut(1)?;
+			*scene = (*remap.get(*scene)?)?;
+			Some(path)
+		};
+
+		self.undos = self
+			.undos
+			.drain(..)
+			.filter_map(|(layer, path)| Some((layer, new_path(&path)?)))
+			.collect();
+		self.redos = self
+			.redos
+			.drain(..)
+			.filter_map(|(layer, path)| Some((layer, new_path(&path)?)))
+			.collect();
+		match new_path(&self.current_path) {
+			Some(path) => self.current_path = path,
+			None => {
+				self.current_path = Vec::new();
+				self.flux = None;
+			}
+		}
+		self.generation += 1;
+	}
+
 	pub fn add_redo(&mut self, layer: aet::AetLayerNode, path: Vec<usize>) {
 		self.redos.push((layer, path));
 		self.flux = None;
 	}
 
+	// Whether the layer at path has changes that are still settling
+	pub fn editing(&self, path: &[usize]) -> bool {
+		self.flux.is_some() && self.current_path == path
+	}
+
+	// An edit made outside the selection, before is the layer as it was ahead of it
+	pub fn feed_edit(
+		&mut self,
+		current_time: f64,
+		path: &[usize],
+		before: aet::AetLayerNode,
+		after: &aet::AetLayerNode,
+	) {
+		if path != self.current_path {
+			if self.flux.is_some() {
+				self.record_undo();
+			}
+			self.current_path = path.to_vec();
+			self.original_layer = before;
+		}
+		self.flux = Some((current_time, after.clone()));
+		self.layer_generation += 1;
+	}
+
 	pub fn feed_state(&mut self, current_time: f64, selected: &[usize], set: &aet::AetSetNode) {
 		if selected.len() < 3 || selected[0] != 0 {
 			return;
@@ -157,16 +224,18 @@ impl LayerUndoer {
 				if *last_update != *layer {
 					*time = current_time;
 					*last_update = layer.clone();
+					self.layer_generation += 1;
 				} else if current_time >= *time + 1.0 {
-					self.add_undo(self.original_layer.clone(), self.current_path.clone());
+					self.record_undo();
 					self.original_layer = layer.clone();
 				}
 			} else if self.original_layer != *layer {
 				self.flux = Some((current_time, layer.clone()));
+				self.layer_generation += 1;
 			}
 		} else {
 			if self.flux.is_some() {
-				self.add_undo(self.original_layer.clone(), self.current_path.clone());
+				self.record_undo();
 			}
 			self.current_path = selected.to_vec();
 			self.original_layer = layer.clone();
@@ -183,6 +252,50 @@ pub struct App {
 	spr_db_filepath: Option<PathBuf>,
 	selected: Vec<usize>,
 	file_picker_result: Option<mpsc::Receiver<Option<(std::path::PathBuf, Vec<u8>)>>>,
+	psd_picker_result: Option<mpsc::Receiver<Option<(std::path::PathBuf, Vec<u8>)>>>,
+	atlas_picker_result: Option<mpsc::Receiver<(PathBuf, Vec<(PathBuf, Vec<u8>)>)>>,
+	error: Option<String>,
+	save_warnings: Vec<String>,
+	warnings_heading: &'static str,
+	// Hashes of the documents as last loaded or saved, in tree order
+	saved_hashes: [Option<u64>; 3],
+	check_unsaved: bool,
+	// When to next recheck the documents input could have edited, in tree order
+	unsaved_recheck: Option<(f64, [bool; 3])>,
+	// A folder save in progress, with the hashes of what is being written
+	save_to_result: Option<(mpsc::Receiver<Option<Result<(), String>>>, [Option<u64>; 3])>,
+	// A mod export in progress, with the warnings to show once it is written
+	export_mod_result: Option<(mpsc::Receiver<Option<Result<(), String>>>, Vec<String>)>,
+	title: String,
+	exit_prompt: bool,
+	reload_prompt: bool,
+	watcher: Option<(notify::RecommendedWatcher, Vec<PathBuf>)>,
+	watcher_result: Option<mpsc::Receiver<PathBuf>>,
+	// Our own saves also show up as changes
+	last_save: Option<std::time::Instant>,
+	allow_close: bool,
+	settings: settings::Settings,
+	show_preferences: bool,
+	source_replace: Option<aet::SourceReplace>,
+	last_selected: Vec<usize>,
+	// Path the options panel stays on instead of following the selection
+	pinned: Option<Vec<usize>>,
+	// The aet layer at the pinned path, the pin is dropped once the path leads elsewhere
+	pinned_layer: Option<Rc<Mutex<aet::AetLayerNode>>>,
+	cache_generation: u64,
+	cache_layer_generation: u64,
+	show_render_stats: bool,
+	palette: Option<palette::Palette>,
+	#[cfg(feature = "audio")]
+	audio: audio::AudioPreview,
+	#[cfg(feature = "audio")]
+	show_sounds: bool,
+	// Keys found by the pre-save check, and whether the save picks a folder
+	non_finite_prompt: Option<(Vec<String>, bool)>,
+	// Whether the invalid values prompt was opened by saving before closing
+	close_after_save: bool,
+	// Kept as its own copy so later edits to the original don't carry over
+	layer_clipboard: Option<aet::AetLayerNode>,
 
 	undoer: LayerUndoer,
 }
@@ -213,6 +326,40 @@ impl App {
 			spr_db_filepath: None,
 			selected: Vec::new(),
 			file_picker_result: None,
+			psd_picker_result: None,
+			atlas_picker_result: None,
+			error: None,
+			save_warnings: Vec::new(),
+			warnings_heading: "",
+			saved_hashes: [None; 3],
+			check_unsaved: false,
+			unsaved_recheck: None,
+			save_to_result: None,
+			export_mod_result: None,
+			title: String::new(),
+			exit_prompt: false,
+			reload_prompt: false,
+			watcher: None,
+			watcher_result: None,
+			last_save: None,
+			allow_close: false,
+			settings: settings::Settings::load(cc.storage),
+			show_preferences: false,
+			source_replace: None,
+			last_selected: Vec::new(),
+			pinned: None,
+			pinned_layer: None,
+			cache_generation: 0,
+			cache_layer_generation: 0,
+			show_render_stats: false,
+			palette: None,
+			#[cfg(feature = "audio")]
+			audio: audio::AudioPreview::new(),
+			#[cfg(feature = "audio")]
+			show_sounds: false,
+			non_finite_prompt: None,
+			close_after_save: false,
+			layer_clipboard: None,
 			undoer: LayerUndoer::new(),
 		})
 	}
@@ -259,6 +406,16 @@ pub fn collapsing_selectable_label<R>(
 		let mut state =
 			egui::collapsing_header::CollapsingState::load_with_default_open(ui.ctx(), id, false);
 
+		if selected
+			&& ui.ctx().data(|data| {
+				data.get_temp::<bool>(egui::Id::new(TREE_EXPAND))
+					.is_some_and(|open| open != state.is_open())
+			}) {
+			ui.ctx()
+				.data_mut(|data| data.remove_temp::<bool>(egui::Id::new(TREE_EXPAND)));
+			state.toggle(ui);
+		}
+
 		header_response.widget_info(|| {
 			egui::WidgetInfo::labeled(
 				egui::WidgetType::CollapsingHeader,
@@ -326,6 +483,13 @@ pub fn collapsing_selectable_label<R>(
 	.inner
 }
 
+pub fn unsaved_marker(ui: &mut egui::Ui, unsaved: bool) {
+	if unsaved {
+		ui.label(egui::RichText::new("*").strong())
+			.on_hover_text("Unsaved changes");
+	}
+}
+
 // Based on DragValue
 pub fn num_edit<Num: egui::emath::Numeric + std::str::FromStr + std::fmt::Display>(
 	ui: &mut egui::Ui,
@@ -415,6 +579,22 @@ pub fn num_edit<Num: egui::emath::Numeric + std::str::FromStr + std::fmt::Displa
 	response
 }
 
+// Set in egui's temp data to bring the selected node into view on the next frame
+pub const SCROLL_TO_SELECTION: &str = "ScrollToSelection";
+// Set in egui's temp data when the selection was moved with the keyboard instead of a click
+const TREE_SELECT: &str = "TreeSelect";
+// Whether the selected node should be expanded or collapsed, removed once it was
+const TREE_EXPAND: &str = "TreeExpand";
+// Paths of the nodes shown in the tree this frame, top to bottom
+const TREE_ORDER: &str = "TreeOrder";
+// Set in egui's temp data to add a key to the selected curve at the current time
+pub const ADD_KEY: &str = "AddKey";
+// Set in egui's temp data to copy the selected layer, or paste into the selected scene or composition
+pub const COPY_LAYER: &str = "CopyLayer";
+pub const PASTE_LAYER: &str = "PasteLayer";
+// Whether a layer has been copied, for enabling the paste entries
+pub const LAYER_COPIED: &str = "LayerCopied";
+
 pub fn show_node(
 	ui: &mut egui::Ui,
 	node: &mut dyn TreeNode,
@@ -424,10 +604,32 @@ pub fn show_node(
 	frame: &mut eframe::Frame,
 	undoer: &mut LayerUndoer,
 ) -> egui::Response {
+	if node.is_hidden() {
+		return ui.response();
+	}
+
 	let mut path = path.to_vec();
 	path.push(index);
 
-	if node.has_custom_tree() {
+	let scroll_to = path == *selected
+		&& ui.ctx().data_mut(|data| {
+			data.remove_temp::<bool>(egui::Id::new(SCROLL_TO_SELECTION))
+				.unwrap_or(false)
+		});
+
+	ui.ctx().data_mut(|data| {
+		data.get_temp_mut_or_default::<Vec<Vec<usize>>>(egui::Id::new(TREE_ORDER))
+			.push(path.clone())
+	});
+	if path == *selected
+		&& ui.ctx().data_mut(|data| {
+			data.remove_temp::<bool>(egui::Id::new(TREE_SELECT))
+				.unwrap_or(false)
+		}) {
+		node.selected(frame);
+	}
+
+	let resp = if node.has_custom_tree() {
 		node.display_tree(ui, &path, selected, frame, undoer)
 	} else if node.has_children() {
 		let resp = ui
@@ -488,7 +690,13 @@ pub fn show_node(
 		}
 
 		resp
+	};
+
+	if scroll_to {
+		resp.scroll_to_me(Some(egui::Align::Center));
 	}
+
+	resp
 }
 
 fn show_node_opts(
@@ -520,6 +728,200 @@ fn show_node_opts(
 	});
 }
 
+// Edits a time stored in frames, in seconds when the preferences ask for them
+pub fn time_edit(ui: &mut egui::Ui, frames: &mut f32) -> egui::Response {
+	let display = settings::TimeDisplay::get(ui.ctx());
+	if !display.in_seconds() {
+		return num_edit(ui, frames, 2);
+	}
+
+	// Only written back on change so untouched times don't drift
+	let mut seconds = *frames / display.fps;
+	let response = num_edit(ui, &mut seconds, 3).on_hover_text(format!("Frame {frames:.2}"));
+	if response.changed() {
+		*frames = seconds * display.fps;
+	}
+	response
+}
+
+fn pixel_scale_combo(ui: &mut egui::Ui, pixel_scale: &mut Option<u32>) -> egui::Response {
+	let label = |scale: Option<u32>| scale.map_or(String::from("Fit"), |scale| format!("{scale}×"));
+	egui::ComboBox::from_id_salt("PixelScale")
+		.selected_text(label(*pixel_scale))
+		.width(48.0)
+		.show_ui(ui, |ui| {
+			for scale in [None, Some(1), Some(2), Some(3)] {
+				ui.selectable_value(pixel_scale, scale, label(scale));
+			}
+		})
+		.response
+}
+
+// Playing from the end of the range starts from its beginning, or the other way round in reverse
+fn toggle_playback(
+	playing: &mut bool,
+	time: &mut f32,
+	range: &std::ops::RangeInclusive<f32>,
+	speed: f32,
+) {
+	*playing = !*playing;
+	if *playing && speed >= 0.0 && *time >= *range.end() {
+		*time = *range.start();
+	} else if *playing && speed < 0.0 && *time <= *range.start() {
+		*time = *range.end();
+	}
+}
+
+fn speed_edit(ui: &mut egui::Ui, speed: &mut f32) -> egui::Response {
+	ui.add(
+		egui::DragValue::new(speed)
+			.range(-4.0..=4.0)
+			.speed(0.05)
+			.max_decimals(2)
+			.suffix("×"),
+	)
+}
+
+// Moves time onto the nearest key in range, if there is one
+fn snap_to_key(time: &mut f32, keys: &[f32], range: &std::ops::RangeInclusive<f32>) {
+	if let Some(key) = keys
+		.iter()
+		.filter(|key| range.contains(key))
+		.min_by(|a, b| (*a - *time).abs().total_cmp(&(*b - *time).abs()))
+	{
+		*time = *key;
+	}
+}
+
+// Frame and second ticks over the playback range, clicking or dragging seeks
+fn time_ruler(ui: &mut egui::Ui, time: &mut f32, range: std::ops::RangeInclusive<f32>, fps: f32) {
+	let font = egui::TextStyle::Small.resolve(ui.style());
+	let (rect, response) = ui.allocate_exact_size(
+		egui::vec2(ui.available_width(), font.size * 2.5),
+		egui::Sense::click_and_drag(),
+	);
+	let (start, end) = (*range.start(), *range.end());
+	if end <= start {
+		return;
+	}
+
+	if let Some(pos) = response.interact_pointer_pos() {
+		*time = egui::remap_clamp(pos.x, rect.left()..=rect.right(), range.clone()).round();
+	}
+
+	let to_x = |frame: f32| egui::remap(frame, range.clone(), rect.left()..=rect.right());
+	let frame_width = rect.width() / (end - start);
+
+	// Whole seconds are preferred for labels so both units read cleanly
+	let mut steps = vec![1.0, 2.0, 5.0, 10.0];
+	if fps > 0.0 {
+		steps.extend([0.5, 1.0, 2.0, 5.0, 10.0, 30.0, 60.0].map(|seconds| fps * seconds));
+	}
+	steps.extend([1000.0, 10000.0]);
+	steps.sort_by(f32::total_cmp);
+	let major = steps
+		.into_iter()
+		.find(|step| step * frame_width >= 80.0)
+		.unwrap_or(end - start);
+	let minor = [major / 10.0, major / 5.0, major / 2.0]
+		.into_iter()
+		.find(|step| step.fract() == 0.0 && step * frame_width >= 6.0);
+
+	let painter = ui.painter_at(rect);
+	let visuals = ui.visuals();
+	painter.rect_filled(rect, 0.0, visuals.extreme_bg_color);
+
+	let tick = |frame: f32, length: f32| {
+		let x = to_x(frame);
+		painter.line_segment(
+			[
+				egui::pos2(x, rect.bottom()),
+				egui::pos2(x, rect.bottom() - length),
+			],
+			visuals.widgets.noninteractive.fg_stroke,
+		);
+	};
+
+	if let Some(minor) = minor {
+		let mut frame = (start / minor).ceil() * minor;
+		while frame <= end {
+			tick(frame, rect.height() * 0.25);
+			frame += minor;
+		}
+	}
+
+	let mut frame = (start / major).ceil() * major;
+	while frame <= end {
+		tick(frame, rect.height() * 0.5);
+		let label = if fps > 0.0 {
+			format!("{frame:.0} | {:.2}s", frame / fps)
+		} else {
+			format!("{frame:.0}")
+		};
+		painter.text(
+			egui::pos2(to_x(frame) + 2.0, rect.top()),
+			egui::Align2::LEFT_TOP,
+			label,
+			font.clone(),
+			visuals.weak_text_color(),
+		);
+		frame += major;
+	}
+
+	let x = to_x(time.clamp(start, end));
+	painter.line_segment(
+		[egui::pos2(x, rect.top()), egui::pos2(x, rect.bottom())],
+		egui::Stroke::new(2.0, visuals.selection.bg_fill),
+	);
+}
+
+#[derive(Clone)]
+struct ImageView {
+	path: Vec<usize>,
+	zoom: f32,
+	offset: egui::Vec2,
+}
+
+// Zooms with the scroll wheel and pans by dragging, F or selecting another node fits it again
+fn image_view(ui: &mut egui::Ui, response: &egui::Response, path: &[usize]) -> egui::Rect {
+	let id = egui::Id::new("ImageView");
+	let fit = ImageView {
+		path: path.to_vec(),
+		zoom: 1.0,
+		offset: egui::Vec2::ZERO,
+	};
+	let mut view = ui
+		.data(|data| data.get_temp::<ImageView>(id))
+		.filter(|view| view.path == path)
+		.unwrap_or(fit.clone());
+
+	if let Some(pointer) = response.hover_pos() {
+		let scroll = ui.input(|input| input.smooth_scroll_delta.y);
+		if scroll != 0.0 {
+			let factor = (scroll / 200.0).exp();
+			let new_zoom = (view.zoom * factor).clamp(0.1, 64.0);
+			// Keep the point under the cursor in place
+			let pointer = pointer - response.rect.center();
+			view.offset = pointer + (view.offset - pointer) * (new_zoom / view.zoom);
+			view.zoom = new_zoom;
+		}
+
+		if ui.ctx().memory(|memory| memory.focused().is_none())
+			&& ui.input_mut(|input| input.consume_key(egui::Modifiers::NONE, egui::Key::F))
+		{
+			view = fit;
+		}
+	}
+	view.offset += response.drag_delta();
+
+	let rect = egui::Rect::from_center_size(
+		response.rect.center() + view.offset,
+		response.rect.size() * view.zoom,
+	);
+	ui.data_mut(|data| data.insert_temp(id, view));
+	rect
+}
+
 fn show_node_visual(
 	ui: &mut egui::Ui,
 	node: &mut dyn TreeNode,
@@ -539,9 +941,11 @@ fn show_node_visual(
 	node.display_children(&mut |child| {
 		if index == desired_index {
 			if depth + 1 == desired_path.len() - 1 {
-				let (rect, _) = ui.allocate_exact_size(ui.available_size(), egui::Sense::empty());
-				if let Some(callback) = child.display_visual(ui, rect) {
-					ui.painter().add(callback);
+				let (rect, response) =
+					ui.allocate_exact_size(ui.available_size(), egui::Sense::drag());
+				let view_rect = image_view(ui, &response, desired_path);
+				if let Some(callback) = child.display_visual(ui, view_rect) {
+					ui.painter().with_clip_rect(rect).add(callback);
 				}
 			} else {
 				show_node_visual(ui, child, index, depth + 1, &path, desired_path);
@@ -560,7 +964,381 @@ impl App {
 		node.scenes.get_mut(self.selected[1])
 	}
 
+	fn project(&self) -> project::Project {
+		let file = |path: &Option<PathBuf>, format: Option<(bool, bool, bool)>| {
+			let (path, (modern, big_endian, is_x)) = (path.clone()?, format?);
+			Some(project::ProjectFile {
+				path,
+				modern,
+				big_endian,
+				is_x,
+			})
+		};
+
+		project::Project {
+			aet_set: file(
+				&self.aet_set_filepath,
+				self.aet_set
+					.as_ref()
+					.map(|node| (node.modern, node.big_endian, node.is_x)),
+			),
+			sprite_set: file(
+				&self.sprite_set_filepath,
+				self.sprite_set
+					.as_ref()
+					.map(|node| (node.modern, node.big_endian, node.is_x)),
+			),
+			spr_db: file(
+				&self.spr_db_filepath,
+				self.spr_db
+					.as_ref()
+					.map(|node| (node.modern, node.big_endian, node.is_x)),
+			),
+			notes: self
+				.aet_set
+				.as_ref()
+				.map(|node| node.notes())
+				.unwrap_or_default(),
+		}
+	}
+
+	fn pick_file(&mut self) {
+		let (tx, rx) = mpsc::channel();
+		std::thread::spawn(move || {
+			tokio::runtime::Builder::new_current_thread()
+				.enable_io()
+				.build()
+				.unwrap()
+				.block_on(async {
+					let Some(file) = rfd::AsyncFileDialog::new()
+						.add_filter("DIVA", &["farc", "bin"])
+						.add_filter("ReAET project", &[project::Project::EXTENSION])
+						.pick_file()
+						.await
+					else {
+						tx.send(None).unwrap();
+						return;
+					};
+
+					let path = file.path();
+					let data = file.read().await;
+					tx.send(Some((path.to_path_buf(), data))).unwrap();
+				});
+		});
+
+		self.file_picker_result = Some(rx);
+		self.selected = Vec::new();
+		self.pinned = None;
+	}
+
+	fn pick_psd(&mut self) {
+		let (tx, rx) = mpsc::channel();
+		std::thread::spawn(move || {
+			tokio::runtime::Builder::new_current_thread()
+				.enable_io()
+				.build()
+				.unwrap()
+				.block_on(async {
+					let Some(file) = rfd::AsyncFileDialog::new()
+						.add_filter("Photoshop", &["psd"])
+						.pick_file()
+						.await
+					else {
+						tx.send(None).unwrap();
+						return;
+					};
+
+					let path = file.path();
+					let data = file.read().await;
+					tx.send(Some((path.to_path_buf(), data))).unwrap();
+				});
+		});
+
+		self.psd_picker_result = Some(rx);
+	}
+
+	fn pick_atlas_folder(&mut self) {
+		let (tx, rx) = mpsc::channel();
+		std::thread::spawn(move || {
+			tokio::runtime::Builder::new_current_thread()
+				.enable_io()
+				.build()
+				.unwrap()
+				.block_on(async {
+					let Some(folder) = rfd::AsyncFileDialog::new().pick_folder().await else {
+						return;
+					};
+
+					let files = std::fs::read_dir(folder.path())
+						.into_iter()
+						.flatten()
+						.flatten()
+						.map(|entry| entry.path())
+						.filter(|path| image::ImageFormat::from_path(path).is_ok())
+						.filter_map(|path| Some((path.clone(), std::fs::read(&path).ok()?)))
+						.collect();
+					tx.send((folder.path().to_path_buf(), files)).unwrap();
+				});
+		});
+
+		self.atlas_picker_result = Some(rx);
+	}
+
+	fn close_files(&mut self) {
+		self.aet_set = None;
+		self.aet_set_filepath = None;
+		self.sprite_set = None;
+		self.sprite_set_filepath = None;
+		self.spr_db = None;
+		self.spr_db_filepath = None;
+		self.selected = Vec::new();
+		self.pinned = None;
+		self.layer_clipboard = None;
+	}
+
+	fn copy_layer(&mut self) {
+		let Some(aet_set) = &self.aet_set else {
+			return;
+		};
+		if self.selected.len() < 3 || self.selected[0] != 0 {
+			return;
+		}
+		let Some(layer) = aet_set.scenes[self.selected[1]].selected_layer(&self.selected) else {
+			return;
+		};
+
+		let mut copy = layer.try_lock().unwrap().deep_clone();
+		copy.parent = None;
+		copy.want_deletion = false;
+		copy.want_duplicate = false;
+		copy.precompose = None;
+		copy.duplicate_offset = None;
+		self.layer_clipboard = Some(copy);
+	}
+
+	// Pastes at the end of the selected scene or composition layer
+	fn paste_layer(&mut self) {
+		let Some(copy) = &self.layer_clipboard else {
+			return;
+		};
+		let Some(aet_set) = &mut self.aet_set else {
+			return;
+		};
+		if self.selected.len() < 2 || self.selected[0] != 0 {
+			return;
+		}
+		let scene = &mut aet_set.scenes[self.selected[1]];
+
+		let mut pasted = aet::AetCompNode {
+			layers: vec![Rc::new(Mutex::new(copy.deep_clone()))],
+		};
+		// Sprites are looked up again in case the sprite set was swapped since copying
+		if let Some(spr_db) = &self.spr_db
+			&& let Some(spr_set) = &self.sprite_set
+		{
+			pasted.update_video_textures(spr_db, spr_set);
+		}
+
+		if self.selected.len() == 2 {
+			self.undoer.add_undo(
+				aet::AetLayerNode::root_snapshot(scene.root.clone()),
+				self.selected.clone(),
+			);
+			scene.root.layers.append(&mut pasted.layers);
+		} else if let Some(target) = scene.selected_comp_layer(&self.selected)
+			&& let aet::AetItemNode::Comp(comp) = &mut target.try_lock().unwrap().item
+		{
+			comp.layers.append(&mut pasted.layers);
+		}
+	}
+
+	// Everything the command palette can run
+	fn commands(&self) -> Vec<palette::Command> {
+		let loaded = self.aet_set.is_some() || self.sprite_set.is_some() || self.spr_db.is_some();
+		vec![
+			palette::Command {
+				name: "Open",
+				enabled: true,
+				run: |app, _| app.pick_file(),
+			},
+			palette::Command {
+				name: "Save",
+				enabled: loaded,
+				run: |app, _| app.save_files(),
+			},
+			palette::Command {
+				name: "Save To",
+				enabled: loaded,
+				run: |app, _| app.save_files_to(),
+			},
+			palette::Command {
+				name: "Save project",
+				enabled: loaded,
+				run: |app, _| app.save_project(),
+			},
+			palette::Command {
+				name: "Export mod",
+				enabled: loaded,
+				run: |app, _| app.export_mod(true),
+			},
+			palette::Command {
+				name: "Export mod (unpacked)",
+				enabled: loaded,
+				run: |app, _| app.export_mod(false),
+			},
+			palette::Command {
+				name: "Reload",
+				enabled: loaded,
+				run: |app, frame| {
+					app.update_unsaved();
+					if app.has_unsaved() {
+						app.reload_prompt = true;
+					} else {
+						app.reload_files(frame);
+					}
+				},
+			},
+			palette::Command {
+				name: "Import PSD",
+				enabled: self.aet_set.is_some() && self.sprite_set.is_some(),
+				run: |app, _| app.pick_psd(),
+			},
+			palette::Command {
+				name: "Pack images into texture",
+				enabled: self.sprite_set.is_some(),
+				run: |app, _| app.pick_atlas_folder(),
+			},
+			palette::Command {
+				name: "Close",
+				enabled: loaded,
+				run: |app, _| app.close_files(),
+			},
+			palette::Command {
+				name: "Replace sprite sources",
+				enabled: self.aet_set.is_some(),
+				run: |app, _| app.source_replace = Some(aet::SourceReplace::default()),
+			},
+			palette::Command {
+				name: "Convert to classic",
+				enabled: loaded,
+				run: |app, frame| app.convert_files(false, false, frame),
+			},
+			palette::Command {
+				name: "Convert to modern",
+				enabled: loaded,
+				run: |app, frame| app.convert_files(true, false, frame),
+			},
+			palette::Command {
+				name: "Convert to modern (X)",
+				enabled: loaded,
+				run: |app, frame| app.convert_files(true, true, frame),
+			},
+			palette::Command {
+				name: "Toggle render statistics",
+				enabled: true,
+				run: |app, _| app.show_render_stats = !app.show_render_stats,
+			},
+			palette::Command {
+				name: "Preferences",
+				enabled: true,
+				run: |app, _| app.show_preferences = true,
+			},
+		]
+	}
+
+	fn save_project(&self) {
+		let project = self.project();
+		std::thread::spawn(move || {
+			tokio::runtime::Builder::new_current_thread()
+				.enable_io()
+				.build()
+				.unwrap()
+				.block_on(async {
+					let Some(file) = rfd::AsyncFileDialog::new()
+						.add_filter("ReAET project", &[project::Project::EXTENSION])
+						.set_file_name(format!("project.{}", project::Project::EXTENSION))
+						.save_file()
+						.await
+					else {
+						return;
+					};
+
+					let path = file.path();
+					let Ok(text) = project.write(path.parent().unwrap_or(path)) else {
+						return;
+					};
+					file.write(text.as_bytes()).await.unwrap();
+				});
+		});
+	}
+
+	fn open_project(&mut self, frame: &mut eframe::Frame, path: &PathBuf, data: &[u8]) {
+		let project = match project::Project::read(path.parent().unwrap_or(path), data) {
+			Ok(project) => project,
+			Err(e) => {
+				self.error = Some(format!("Could not open {:?}: {e}", path));
+				return;
+			}
+		};
+
+		// Files the project doesn't list would otherwise be saved over by its documents
+		self.aet_set = None;
+		self.aet_set_filepath = None;
+		self.sprite_set = None;
+		self.sprite_set_filepath = None;
+		self.spr_db = None;
+		self.spr_db_filepath = None;
+
+		// The aet set goes first as loading it replaces the others
+		let mut warnings = Vec::new();
+		for file in [&project.aet_set, &project.spr_db, &project.sprite_set]
+			.into_iter()
+			.flatten()
+		{
+			match std::fs::read(&file.path) {
+				Ok(data) => self.set_file(frame, &file.path, &data),
+				Err(e) => warnings.push(format!("Could not read {:?}: {e}", file.path)),
+			}
+		}
+
+		if let Some(node) = &mut self.aet_set {
+			node.set_notes(&project.notes);
+		}
+
+		let loaded = self.project();
+		for (name, saved, loaded) in [
+			("Aet", &project.aet_set, &loaded.aet_set),
+			("Sprites", &project.sprite_set, &loaded.sprite_set),
+			("Sprite database", &project.spr_db, &loaded.spr_db),
+		] {
+			if let Some(saved) = saved
+				&& let Some(loaded) = loaded
+				&& saved.format() != loaded.format()
+			{
+				warnings.push(format!(
+					"{name} is no longer in the format it had when the project was saved"
+				));
+			}
+		}
+
+		if !warnings.is_empty() {
+			self.save_warnings = warnings;
+			self.warnings_heading = "Opened with warnings";
+		}
+	}
+
 	fn set_file(&mut self, frame: &mut eframe::Frame, path: &PathBuf, data: &[u8]) {
+		if project::Project::is_project(path) {
+			self.open_project(frame, path, data);
+			return;
+		}
+
+		let old_paths = [
+			self.aet_set_filepath.clone(),
+			self.sprite_set_filepath.clone(),
+			self.spr_db_filepath.clone(),
+		];
+
 		let name = path
 			.file_name()
 			.unwrap_or_default()
@@ -573,6 +1351,19 @@ impl App {
 			self.spr_db = None;
 			self.sprite_set = None;
 			self.undoer = LayerUndoer::new();
+
+			if self.settings.validate_keys
+				&& let Some(aet_set) = &self.aet_set
+			{
+				let mut report = aet_set.keys_out_of_bounds();
+				if !report.is_empty() {
+					report.push(String::from(
+						"Nothing was changed, Clamp keys to layers on the set moves them inside",
+					));
+					self.save_warnings = report;
+					self.warnings_heading = "Keys outside their layers";
+				}
+			}
 		} else if SPRSET.is_match(name) {
 			let spr_set = spr::SpriteSetNode::read(&name, data);
 			spr_set.init_wgpu(frame);
@@ -612,6 +1403,7 @@ impl App {
 		}
 
 		self.selected = Vec::new();
+		self.pinned = None;
 
 		if let Some(path) = path.parent()
 			&& let Ok(dir) = path.read_dir()
@@ -695,51 +1487,421 @@ impl App {
 				}
 			}
 		}
+
+		let new_paths = [
+			&self.aet_set_filepath,
+			&self.sprite_set_filepath,
+			&self.spr_db_filepath,
+		];
+		for ((old, new), hash) in old_paths.iter().zip(new_paths).zip(&mut self.saved_hashes) {
+			if old != new {
+				*hash = None;
+			}
+		}
+		self.check_unsaved = true;
 	}
 
-	// Native only
-	fn save_files(&self) {
-		if let Some(aet_set) = &self.aet_set
-			&& let Some(path) = &self.aet_set_filepath
+	fn reload_files(&mut self, frame: &mut eframe::Frame) {
+		let sprite_set_filepath = self.sprite_set_filepath.clone();
+		let spr_db_filepath = self.spr_db_filepath.clone();
+		self.saved_hashes = [None; 3];
+
+		// Loading an aet set also picks up the spr_db and sprite set next to it
+		if let Some(path) = self.aet_set_filepath.clone()
+			&& let Ok(data) = std::fs::read(&path)
 		{
-			let data = aet_set.raw_data();
-			_ = std::fs::write(path, &data);
+			self.set_file(frame, &path, &data);
 		}
 
-		if let Some(sprite_set) = &self.sprite_set
-			&& let Some(path) = &self.sprite_set_filepath
+		if let Some(path) = spr_db_filepath
+			&& self.spr_db_filepath.as_ref() != Some(&path)
+			&& let Ok(data) = std::fs::read(&path)
 		{
-			let data = sprite_set.raw_data();
-			if path.extension() == Some(std::ffi::OsString::from("farc").as_os_str()) {
-				let mut farc = kkdlib::farc::Farc::new();
-				farc.add_file_data(&sprite_set.name, &data);
-				let data = farc.to_buf().unwrap_or_default();
-				_ = std::fs::write(path, &data);
-			} else {
-				_ = std::fs::write(path, &data);
-			}
+			self.set_file(frame, &path, &data);
 		}
 
-		if let Some(spr_db) = &self.spr_db
-			&& let Some(path) = &self.spr_db_filepath
+		if let Some(path) = sprite_set_filepath
+			&& self.sprite_set_filepath.as_ref() != Some(&path)
+			&& let Ok(data) = std::fs::read(&path)
 		{
-			let data = spr_db.raw_data();
-			_ = std::fs::write(path, &data);
+			self.set_file(frame, &path, &data);
 		}
 	}
 
-	// Native only
-	fn save_files_to(&self) {
-		let aet_set = if let Some(aet_set) = &self.aet_set {
-			Some((aet_set.raw_data(), aet_set.name.clone()))
+	fn update_watcher(&mut self, ctx: &egui::Context) {
+		let paths = if self.settings.watch_files {
+			[
+				&self.aet_set_filepath,
+				&self.sprite_set_filepath,
+				&self.spr_db_filepath,
+			]
+			.into_iter()
+			.flatten()
+			.cloned()
+			.collect()
 		} else {
-			None
+			Vec::new()
 		};
 
-		let sprite_set = if let Some(sprite_set) = &self.sprite_set
-			&& let Some(path) = &self.sprite_set_filepath
+		if self
+			.watcher
+			.as_ref()
+			.map_or(Vec::new(), |(_, watched)| watched.clone())
+			== paths
 		{
-			let data = sprite_set.raw_data();
+			return;
+		}
+
+		self.watcher = None;
+		self.watcher_result = None;
+		if paths.is_empty() {
+			return;
+		}
+
+		let (tx, rx) = mpsc::channel();
+		let ctx = ctx.clone();
+		let Ok(mut watcher) =
+			notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+				let Ok(event) = res else {
+					return;
+				};
+				if !event.kind.is_modify() && !event.kind.is_create() {
+					return;
+				}
+				for path in event.paths {
+					_ = tx.send(path);
+				}
+				ctx.request_repaint();
+			})
+		else {
+			return;
+		};
+
+		// Watch the directories as editors often replace files instead of writing to them
+		for dir in paths.iter().filter_map(|path| path.parent()) {
+			_ = watcher.watch(dir, notify::RecursiveMode::NonRecursive);
+		}
+
+		self.watcher = Some((watcher, paths));
+		self.watcher_result = Some(rx);
+	}
+
+	fn check_watcher(&mut self, frame: &mut eframe::Frame) {
+		let Some(rx) = &self.watcher_result else {
+			return;
+		};
+
+		let Some((_, watched)) = &self.watcher else {
+			return;
+		};
+		let changed = rx.try_iter().any(|path| watched.contains(&path));
+		if !changed
+			|| self
+				.last_save
+				.is_some_and(|time| time.elapsed() < std::time::Duration::from_secs(2))
+		{
+			return;
+		}
+
+		self.update_unsaved();
+		if self.has_unsaved() {
+			self.reload_prompt = true;
+		} else {
+			self.reload_files(frame);
+		}
+	}
+
+	fn document_hash(&self, index: usize) -> Option<u64> {
+		fn hash(data: Vec<u8>) -> u64 {
+			let mut hasher = hash_map::DefaultHasher::new();
+			std::hash::Hash::hash(&data, &mut hasher);
+			std::hash::Hasher::finish(&hasher)
+		}
+
+		// Notes aren't part of the aet set's data but are edited alongside it
+		match index {
+			0 => self.aet_set.as_ref().map(|node| {
+				let mut data = node.raw_data();
+				for (path, note) in node.notes() {
+					data.extend(path.bytes().chain(note.bytes()));
+				}
+				hash(data)
+			}),
+			1 => self.sprite_set.as_ref().map(|node| hash(node.raw_data())),
+			_ => self.spr_db.as_ref().map(|node| hash(node.raw_data())),
+		}
+	}
+
+	fn document_hashes(&self) -> [Option<u64>; 3] {
+		std::array::from_fn(|i| self.document_hash(i))
+	}
+
+	fn has_unsaved(&self) -> bool {
+		self.aet_set.as_ref().is_some_and(|node| node.unsaved)
+			|| self.sprite_set.as_ref().is_some_and(|node| node.unsaved)
+			|| self.spr_db.as_ref().is_some_and(|node| node.unsaved)
+	}
+
+	fn mark_saved(&mut self) {
+		self.saved_hashes = self.document_hashes();
+		self.last_save = Some(std::time::Instant::now());
+		self.check_unsaved = true;
+	}
+
+	fn update_unsaved(&mut self) {
+		self.update_unsaved_of([true; 3]);
+	}
+
+	// Documents without a saved hash were just loaded, their current state becomes the baseline
+	fn update_unsaved_of(&mut self, documents: [bool; 3]) {
+		for i in (0..3).filter(|i| documents[*i]) {
+			let hash = self.document_hash(i);
+			let saved = &mut self.saved_hashes[i];
+			if saved.is_none() {
+				*saved = hash;
+			}
+			let unsaved = hash != *saved;
+
+			match i {
+				0 => self.aet_set.as_mut().map(|node| node.unsaved = unsaved),
+				1 => self.sprite_set.as_mut().map(|node| node.unsaved = unsaved),
+				_ => self.spr_db.as_mut().map(|node| node.unsaved = unsaved),
+			};
+		}
+
+		let name = if let Some(node) = &self.aet_set {
+			Some(node.name.as_str())
+		} else if let Some(node) = &self.sprite_set {
+			Some(node.name.as_str())
+		} else if let Some(node) = &self.spr_db {
+			Some(node.filename.as_str())
+		} else {
+			None
+		};
+		self.title = match name {
+			Some(name) if self.has_unsaved() => format!("* {name} - ReAET"),
+			Some(name) => format!("{name} - ReAET"),
+			None => String::from("ReAET"),
+		};
+	}
+
+	// Format flags of each loaded file when they don't all agree
+	fn format_mismatch(&self) -> Option<String> {
+		let mut formats = Vec::new();
+		if let Some(node) = &self.aet_set {
+			formats.push(("Aet", node.modern, node.big_endian, node.is_x));
+		}
+		if let Some(node) = &self.sprite_set {
+			formats.push(("Sprites", node.modern, node.big_endian, node.is_x));
+		}
+		if let Some(node) = &self.spr_db {
+			formats.push(("Sprite database", node.modern, node.big_endian, node.is_x));
+		}
+
+		let (_, modern, big_endian, is_x) = *formats.first()?;
+		if formats
+			.iter()
+			.all(|format| (format.1, format.2, format.3) == (modern, big_endian, is_x))
+		{
+			return None;
+		}
+
+		let lines = formats
+			.iter()
+			.map(|(name, modern, big_endian, is_x)| {
+				let game = match (modern, is_x) {
+					(false, _) => "classic",
+					(true, false) => "modern",
+					(true, true) => "modern (X)",
+				};
+				if *big_endian {
+					format!("{name}: {game}, big endian")
+				} else {
+					format!("{name}: {game}")
+				}
+			})
+			.collect::<Vec<_>>();
+		Some(lines.join("\n"))
+	}
+
+	// Problems that don't prevent saving but will likely misbehave in game
+	fn collect_save_warnings(&self) -> Vec<String> {
+		let mut warnings = Vec::new();
+
+		if let Some(mismatch) = self.format_mismatch() {
+			warnings.push(format!(
+				"Loaded files are for different games: {}",
+				mismatch.replace('\n', ", ")
+			));
+		}
+
+		if let Some(aet_set) = &self.aet_set {
+			match aet_set.companion_names() {
+				None => warnings.push(format!(
+					"Aet set {} is not named aet_*.bin or *.aet, the game will not load it",
+					aet_set.name
+				)),
+				Some([sprite_set_name, _]) => {
+					if let Some(sprite_set) = &self.sprite_set
+						&& sprite_set.name != sprite_set_name
+					{
+						warnings.push(format!(
+							"The game loads sprite set {} along with {}, not {}",
+							sprite_set_name, aet_set.name, sprite_set.name
+						));
+					}
+				}
+			}
+		}
+
+		if let Some(sprite_set) = &self.sprite_set {
+			for sprite in sprite_set.sprites_node.children.try_lock().unwrap().iter() {
+				let sprite = sprite.try_lock().unwrap();
+				if sprite.out_of_bounds() {
+					warnings.push(format!(
+						"Sprite {} exceeds the bounds of its texture",
+						sprite.name
+					));
+				}
+				if sprite.texture_missing {
+					warnings.push(format!(
+						"Sprite {} refers to a removed texture",
+						sprite.name
+					));
+				}
+			}
+
+			if !sprite_set.modern {
+				for texture in &sprite_set.textures_node.children {
+					let texture = texture.try_lock().unwrap();
+					if texture.is_modern_only() {
+						warnings.push(format!(
+							"Texture {} uses a format classic games cannot read",
+							texture.name
+						));
+					}
+				}
+			}
+		}
+
+		warnings
+	}
+
+	// Sets the format flags of every loaded file, re-encoding textures classic games cannot read
+	fn convert_files(&mut self, modern: bool, is_x: bool, frame: &eframe::Frame) {
+		if let Some(aet_set) = &mut self.aet_set {
+			aet_set.modern = modern;
+			aet_set.big_endian = false;
+			aet_set.is_x = is_x;
+		}
+
+		if let Some(sprite_set) = &mut self.sprite_set {
+			sprite_set.convert(modern, is_x, frame);
+		}
+
+		if let Some(spr_db) = &mut self.spr_db {
+			spr_db.modern = modern;
+			spr_db.big_endian = false;
+			spr_db.is_x = is_x;
+		}
+
+		self.save_warnings = self.collect_save_warnings();
+		self.warnings_heading = "Converted with warnings";
+	}
+
+	// Native only
+	// Keys the game cannot load hold the save back behind a prompt
+	fn hold_non_finite(&mut self, to: bool) -> bool {
+		let keys = self
+			.aet_set
+			.as_mut()
+			.map(|aet_set| aet_set.non_finite_keys(false))
+			.unwrap_or_default();
+		if keys.is_empty() {
+			return false;
+		}
+
+		self.non_finite_prompt = Some((keys, to));
+		true
+	}
+
+	// Whether the files were written, false while held back by the prompt or when a write failed
+	fn save_files(&mut self) -> bool {
+		!self.hold_non_finite(false) && self.write_files()
+	}
+
+	fn save_files_to(&mut self) {
+		if !self.hold_non_finite(true) {
+			self.write_files_to();
+		}
+	}
+
+	// Failures are shown in the error modal and leave the documents marked unsaved
+	fn write_files(&mut self) -> bool {
+		self.save_warnings = self.collect_save_warnings();
+		self.warnings_heading = "Saved with warnings";
+
+		let mut errors = Vec::new();
+		let mut write = |path: &PathBuf, data: &[u8]| {
+			if let Err(e) = std::fs::write(path, data) {
+				errors.push(format!("Could not write {:?}: {e}", path));
+			}
+		};
+
+		if let Some(aet_set) = &self.aet_set
+			&& let Some(path) = &self.aet_set_filepath
+		{
+			let data = aet_set.raw_data();
+			write(path, &data);
+		}
+
+		if let Some(sprite_set) = &self.sprite_set
+			&& let Some(path) = &self.sprite_set_filepath
+		{
+			let data = sprite_set.raw_data();
+			if path.extension() == Some(std::ffi::OsString::from("farc").as_os_str()) {
+				let mut farc = kkdlib::farc::Farc::new();
+				farc.add_file_data(&sprite_set.name, &data);
+				let data = farc.to_buf().unwrap_or_default();
+				write(path, &data);
+			} else {
+				write(path, &data);
+			}
+		}
+
+		if let Some(spr_db) = &self.spr_db
+			&& let Some(path) = &self.spr_db_filepath
+		{
+			let data = spr_db.raw_data();
+			write(path, &data);
+		}
+
+		if !errors.is_empty() {
+			self.error = Some(errors.join("\n"));
+			return false;
+		}
+
+		self.mark_saved();
+		true
+	}
+
+	// Native only
+	// Only marked saved once the thread reports every file was written
+	fn write_files_to(&mut self) {
+		self.save_warnings = self.collect_save_warnings();
+		self.warnings_heading = "Saved with warnings";
+		let hashes = self.document_hashes();
+
+		let aet_set = if let Some(aet_set) = &self.aet_set {
+			Some((aet_set.raw_data(), aet_set.name.clone()))
+		} else {
+			None
+		};
+
+		let sprite_set = if let Some(sprite_set) = &self.sprite_set
+			&& let Some(path) = &self.sprite_set_filepath
+		{
+			let data = sprite_set.raw_data();
 			if path.extension() == Some(std::ffi::OsString::from("farc").as_os_str()) {
 				let mut farc = kkdlib::farc::Farc::new();
 				farc.add_file_data(&sprite_set.name, &data);
@@ -761,6 +1923,7 @@ impl App {
 			None
 		};
 
+		let (tx, rx) = mpsc::channel();
 		std::thread::spawn(move || {
 			tokio::runtime::Builder::new_current_thread()
 				.enable_io()
@@ -768,24 +1931,130 @@ impl App {
 				.unwrap()
 				.block_on(async {
 					let Some(folder) = rfd::AsyncFileDialog::new().pick_folder().await else {
+						tx.send(None).unwrap();
 						return;
 					};
 
 					let path = folder.path();
-					if let Some((aet_set, name)) = aet_set {
-						std::fs::write(path.join(name), aet_set).unwrap();
-					}
-					if let Some((sprite_set, name)) = sprite_set {
-						std::fs::write(path.join(name), sprite_set).unwrap();
-					}
-					if let Some((spr_db, name)) = spr_db {
-						std::fs::write(path.join(name), spr_db).unwrap();
+					let errors = [aet_set, sprite_set, spr_db]
+						.into_iter()
+						.flatten()
+						.filter_map(|(data, name)| {
+							let file = path.join(name);
+							std::fs::write(&file, data)
+								.err()
+								.map(|e| format!("Could not write {:?}: {e}", file))
+						})
+						.collect::<Vec<_>>();
+					if errors.is_empty() {
+						tx.send(Some(Ok(()))).unwrap();
+					} else {
+						tx.send(Some(Err(errors.join("\n")))).unwrap();
 					}
 				});
 		});
+
+		self.save_to_result = Some((rx, hashes));
+	}
+
+	// Native only
+	// The sprite set is named after the aet set so the game loads them together
+	// Warnings are shown once the thread reports the files were written
+	fn export_mod(&mut self, pack_farc: bool) {
+		let warnings = self.collect_save_warnings();
+
+		let pack = |name: &str, data: Vec<u8>| {
+			if pack_farc && name.ends_with(".bin") {
+				let mut farc = kkdlib::farc::Farc::new();
+				farc.add_file_data(name, &data);
+				(
+					name.replace(".bin", ".farc"),
+					farc.to_buf().unwrap_or_default(),
+				)
+			} else {
+				(name.to_string(), data)
+			}
+		};
+
+		let mut files = Vec::new();
+		let companion_names = self
+			.aet_set
+			.as_ref()
+			.and_then(|aet_set| aet_set.companion_names());
+		if let Some(aet_set) = &self.aet_set {
+			files.push(pack(&aet_set.name, aet_set.raw_data()));
+		}
+		if let Some(sprite_set) = &self.sprite_set {
+			let name = companion_names
+				.as_ref()
+				.map_or(&sprite_set.name, |[name, _]| name);
+			files.push(pack(name, sprite_set.raw_data()));
+		}
+		if let Some(spr_db) = &self.spr_db {
+			files.push((String::from(MOD_SPR_DB), spr_db.raw_data()));
+		}
+
+		let (tx, rx) = mpsc::channel();
+		std::thread::spawn(move || {
+			tokio::runtime::Builder::new_current_thread()
+				.enable_io()
+				.build()
+				.unwrap()
+				.block_on(async {
+					let Some(folder) = rfd::AsyncFileDialog::new().pick_folder().await else {
+						tx.send(None).unwrap();
+						return;
+					};
+
+					let write = || -> Result<(), String> {
+						let path = folder.path();
+						let dir = path.join("rom").join("2d");
+						std::fs::create_dir_all(&dir)
+							.map_err(|e| format!("Could not create {:?}: {e}", dir))?;
+						for (name, data) in files {
+							let file = dir.join(name);
+							std::fs::write(&file, data)
+								.map_err(|e| format!("Could not write {:?}: {e}", file))?;
+						}
+
+						// An existing config may have been filled in by hand
+						let config = path.join("config.toml");
+						if !config.exists() {
+							let text = toml::to_string(&ModConfig {
+								enabled: true,
+								name: path
+									.file_name()
+									.unwrap_or_default()
+									.to_string_lossy()
+									.to_string(),
+							})
+							.map_err(|e| e.to_string())?;
+							std::fs::write(&config, text)
+								.map_err(|e| format!("Could not write {:?}: {e}", config))?;
+						}
+						Ok(())
+					};
+					tx.send(Some(write())).unwrap();
+				});
+		});
+
+		self.export_mod_result = Some((rx, warnings));
 	}
 }
 
+// The fields of a mod's config.toml that DivaModLoader needs
+#[derive(serde::Serialize)]
+struct ModConfig {
+	enabled: bool,
+	name: String,
+}
+
+// Seconds without input before edited documents are rechecked for unsaved changes
+const UNSAVED_RECHECK_DELAY: f64 = 0.5;
+
+// DivaModLoader merges databases with this name into the game's own
+const MOD_SPR_DB: &str = "mod_spr_db.bin";
+
 fn apply_redo(aet_set: &mut aet::AetSetNode, undoer: &mut LayerUndoer) {
 	let Some((undone, path)) = undoer.redo() else {
 		return;
@@ -802,25 +2071,7 @@ fn apply_redo(aet_set: &mut aet::AetSetNode, undoer: &mut LayerUndoer) {
 		}
 
 		undoer.add_undo(
-			aet::AetLayerNode {
-				name: String::from("DUMMY"),
-				start_time: 0.0,
-				end_time: 0.0,
-				offset_time: 0.0,
-				time_scale: 1.0,
-				flags: kkdlib::aet::LayerFlags::new(),
-				quality: kkdlib::aet::LayerQuality::None,
-				item: aet::AetItemNode::Comp(aet_set.scenes[path[1]].root.clone()),
-				markers: Vec::new(),
-				video: None,
-				parent: None,
-				audio: None,
-				sprites: Rc::new(Mutex::new(Vec::new())),
-				visible: false,
-				selected_key: 0,
-				want_deletion: false,
-				want_duplicate: false,
-			},
+			aet::AetLayerNode::root_snapshot(aet_set.scenes[path[1]].root.clone()),
 			path.clone(),
 		);
 
@@ -868,25 +2119,7 @@ fn apply_undo(aet_set: &mut aet::AetSetNode, undoer: &mut LayerUndoer) {
 		}
 
 		undoer.add_redo(
-			aet::AetLayerNode {
-				name: String::from("DUMMY"),
-				start_time: 0.0,
-				end_time: 0.0,
-				offset_time: 0.0,
-				time_scale: 1.0,
-				flags: kkdlib::aet::LayerFlags::new(),
-				quality: kkdlib::aet::LayerQuality::None,
-				item: aet::AetItemNode::Comp(aet_set.scenes[path[1]].root.clone()),
-				markers: Vec::new(),
-				video: None,
-				parent: None,
-				audio: None,
-				sprites: Rc::new(Mutex::new(Vec::new())),
-				visible: false,
-				selected_key: 0,
-				want_deletion: false,
-				want_duplicate: false,
-			},
+			aet::AetLayerNode::root_snapshot(aet_set.scenes[path[1]].root.clone()),
 			path.clone(),
 		);
 
@@ -918,38 +2151,20 @@ fn apply_undo(aet_set: &mut aet::AetSetNode, undoer: &mut LayerUndoer) {
 	}
 }
 
-const OPEN_SHORTCUT: egui::KeyboardShortcut = egui::KeyboardShortcut {
-	modifiers: egui::Modifiers::COMMAND,
-	logical_key: egui::Key::O,
-};
-
-const SAVE_SHORTCUT: egui::KeyboardShortcut = egui::KeyboardShortcut {
-	modifiers: egui::Modifiers::COMMAND,
-	logical_key: egui::Key::S,
-};
-
-const SAVE_TO_SHORTCUT: egui::KeyboardShortcut = egui::KeyboardShortcut {
-	modifiers: egui::Modifiers::COMMAND.plus(egui::Modifiers::SHIFT),
-	logical_key: egui::Key::S,
-};
-
-const CLOSE_SHORTCUT: egui::KeyboardShortcut = egui::KeyboardShortcut {
-	modifiers: egui::Modifiers::COMMAND,
-	logical_key: egui::Key::W,
-};
-
-const UNDO_SHORTCUT: egui::KeyboardShortcut = egui::KeyboardShortcut {
-	modifiers: egui::Modifiers::COMMAND,
-	logical_key: egui::Key::Z,
-};
-
-const REDO_SHORTCUT: egui::KeyboardShortcut = egui::KeyboardShortcut {
-	modifiers: egui::Modifiers::COMMAND,
-	logical_key: egui::Key::Y,
-};
-
 impl eframe::App for App {
+	fn save(&mut self, storage: &mut dyn eframe::Storage) {
+		eframe::set_value(storage, eframe::APP_KEY, &self.settings);
+	}
+
 	fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+		self.settings.defaults.publish(ctx);
+		let seconds = self.settings.time_in_seconds;
+		settings::TimeDisplay {
+			seconds,
+			fps: self.get_active_scene().map_or(0.0, |scene| scene.fps),
+		}
+		.publish(ctx);
+
 		ctx.input_mut(|input| {
 			for file in &input.raw.dropped_files {
 				if let Some(path) = &file.path
@@ -960,53 +2175,73 @@ impl eframe::App for App {
 				}
 			}
 
-			if input.consume_shortcut(&OPEN_SHORTCUT) {
-				let (tx, rx) = mpsc::channel();
-				std::thread::spawn(move || {
-					tokio::runtime::Builder::new_current_thread()
-						.enable_io()
-						.build()
-						.unwrap()
-						.block_on(async {
-							let Some(file) = rfd::AsyncFileDialog::new()
-								.add_filter("DIVA", &["farc", "bin"])
-								.pick_file()
-								.await
-							else {
-								tx.send(None).unwrap();
-								return;
-							};
-
-							let path = file.path();
-							let data = file.read().await;
-							tx.send(Some((path.to_path_buf(), data))).unwrap();
-						});
-				});
+			// Let the preferences window see the keys being bound
+			if self.settings.recording.is_some() {
+				return;
+			}
+
+			if input.consume_shortcut(&self.settings.shortcut(Action::Open)) {
+				self.pick_file();
+			}
 
-				self.file_picker_result = Some(rx);
-				self.selected = Vec::new();
+			if input.consume_shortcut(&self.settings.shortcut(Action::CommandPalette)) {
+				self.palette = Some(palette::Palette::default());
 			}
 
-			if input.consume_shortcut(&SAVE_TO_SHORTCUT) {
+			if input.consume_shortcut(&self.settings.shortcut(Action::SaveTo)) {
 				self.save_files_to();
 			}
 
-			if input.consume_shortcut(&SAVE_SHORTCUT) {
+			if input.consume_shortcut(&self.settings.shortcut(Action::Save)) {
 				self.save_files();
 			}
 
-			if input.consume_shortcut(&CLOSE_SHORTCUT) {
-				self.aet_set = None;
-				self.aet_set_filepath = None;
-				self.sprite_set = None;
-				self.sprite_set_filepath = None;
-				self.spr_db = None;
-				self.spr_db_filepath = None;
-				self.selected = Vec::new();
+			if input.consume_shortcut(&self.settings.shortcut(Action::Close)) {
+				self.close_files();
+			}
+
+			// Sprite sets keep their own history, used while something in them is selected
+			if self.selected.first() == Some(&1)
+				&& let Some(spr_set) = &mut self.sprite_set
+			{
+				if spr_set.has_undo()
+					&& input.consume_shortcut(&self.settings.shortcut(Action::Undo))
+					&& spr_set.undo()
+				{
+					self.selected = vec![1];
+					self.pinned = None;
+				}
+
+				if spr_set.has_redo()
+					&& input.consume_shortcut(&self.settings.shortcut(Action::Redo))
+					&& spr_set.redo()
+				{
+					self.selected = vec![1];
+					self.pinned = None;
+				}
+			}
+
+			// So does the spr_db, its entries stay where they are
+			if self.selected.first() == Some(&2)
+				&& let Some(spr_db) = &mut self.spr_db
+			{
+				if spr_db.has_undo()
+					&& input.consume_shortcut(&self.settings.shortcut(Action::Undo))
+				{
+					spr_db.undo();
+				}
+
+				if spr_db.has_redo()
+					&& input.consume_shortcut(&self.settings.shortcut(Action::Redo))
+				{
+					spr_db.redo();
+				}
 			}
 
 			if let Some(aet_set) = &mut self.aet_set {
-				if self.undoer.has_undo() && input.consume_shortcut(&UNDO_SHORTCUT) {
+				if self.undoer.has_undo()
+					&& input.consume_shortcut(&self.settings.shortcut(Action::Undo))
+				{
 					apply_undo(aet_set, &mut self.undoer);
 
 					if let Some(spr_db) = &self.spr_db
@@ -1018,7 +2253,9 @@ impl eframe::App for App {
 					}
 				}
 
-				if self.undoer.has_redo() && input.consume_shortcut(&REDO_SHORTCUT) {
+				if self.undoer.has_redo()
+					&& input.consume_shortcut(&self.settings.shortcut(Action::Redo))
+				{
 					apply_redo(aet_set, &mut self.undoer);
 
 					if let Some(spr_db) = &self.spr_db
@@ -1028,58 +2265,322 @@ impl eframe::App for App {
 							scene.root.update_video_textures(spr_db, spr_set);
 						}
 					}
-				}
-			}
-		});
+				}
+			}
+		});
+
+		if ctx
+			.data_mut(|data| data.remove_temp::<bool>(egui::Id::new(COPY_LAYER)))
+			.is_some()
+		{
+			self.copy_layer();
+		}
+		if ctx
+			.data_mut(|data| data.remove_temp::<bool>(egui::Id::new(PASTE_LAYER)))
+			.is_some()
+		{
+			self.paste_layer();
+		}
+		let copied = self.layer_clipboard.is_some();
+		ctx.data_mut(|data| data.insert_temp(egui::Id::new(LAYER_COPIED), copied));
+
+		// Layer history and the selection are kept by scene index
+		if let Some(aet_set) = &mut self.aet_set
+			&& let Some(remap) = aet_set.scene_remap.take()
+		{
+			self.undoer.remap_scenes(&remap);
+			let remap_path = |path: &[usize]| -> Option<Vec<usize>> {
+				let [0, scene, ..] = *path else {
+					return None;
+				};
+				let mut path = path.to_vec();
+				path[1] = (*remap.get(scene)?)?;
+				Some(path)
+			};
+			if self.selected.first() == Some(&0) && self.selected.len() >= 2 {
+				self.selected = remap_path(&self.selected).unwrap_or(vec![0]);
+			}
+			if let Some(pinned) = &self.pinned
+				&& pinned.first() == Some(&0)
+				&& pinned.len() >= 2
+			{
+				self.pinned = remap_path(pinned);
+			}
+		}
+
+		// Layers added, removed or moved before the pinned one leave its path on another layer
+		if let Some(aet_set) = &self.aet_set
+			&& let Some(pinned @ [0, scene, _, ..]) = self.pinned.as_deref()
+		{
+			let layer = aet_set
+				.scenes
+				.get(*scene)
+				.and_then(|scene| scene.selected_layer(pinned));
+			if !matches!((&layer, &self.pinned_layer), (Some(a), Some(b)) if Rc::ptr_eq(a, b)) {
+				self.pinned = None;
+			}
+		}
+
+		// The pinned layer is followed while its edits settle, the options panel reports them
+		if let Some(aet_set) = &self.aet_set {
+			let path = match &self.pinned {
+				Some(pinned) if self.undoer.editing(pinned) => pinned,
+				_ => &self.selected,
+			};
+			self.undoer
+				.feed_state(ctx.input(|input| input.time), path, aet_set);
+		}
+
+		if let Some(rx) = &mut self.file_picker_result
+			&& let Ok(res) = rx.try_recv()
+		{
+			if let Some((path, data)) = res {
+				self.set_file(frame, &path, &data);
+			}
+			self.file_picker_result = None;
+		}
+
+		if let Some((rx, warnings)) = &mut self.export_mod_result
+			&& let Ok(res) = rx.try_recv()
+		{
+			match res {
+				Some(Ok(())) => {
+					self.save_warnings = std::mem::take(warnings);
+					self.warnings_heading = "Exported with warnings";
+				}
+				Some(Err(e)) => self.error = Some(e),
+				None => {}
+			}
+			self.export_mod_result = None;
+		}
+
+		if let Some((rx, hashes)) = &self.save_to_result
+			&& let Ok(res) = rx.try_recv()
+		{
+			let hashes = *hashes;
+			match res {
+				Some(Ok(())) => {
+					self.saved_hashes = hashes;
+					self.last_save = Some(std::time::Instant::now());
+					self.check_unsaved = true;
+				}
+				Some(Err(e)) => self.error = Some(e),
+				None => {}
+			}
+			self.save_to_result = None;
+		}
+
+		if let Some(rx) = &mut self.psd_picker_result
+			&& let Ok(res) = rx.try_recv()
+		{
+			if let Some((path, data)) = res
+				&& let Some(aet_set) = &mut self.aet_set
+				&& let Some(spr_set) = &mut self.sprite_set
+			{
+				let name = path.file_stem().unwrap_or_default().to_string_lossy();
+				match crate::import::psd(&name, &data, aet_set, spr_set) {
+					Ok(unsupported) => {
+						self.save_warnings = unsupported;
+						self.warnings_heading = "Imported with warnings";
+					}
+					Err(e) => self.error = Some(e),
+				}
+			}
+			self.psd_picker_result = None;
+			self.check_unsaved = true;
+		}
+
+		if let Some(rx) = &mut self.atlas_picker_result
+			&& let Ok((folder, files)) = rx.try_recv()
+		{
+			if let Some(spr_set) = &mut self.sprite_set {
+				let name = folder.file_name().unwrap_or_default().to_string_lossy();
+				let padding = self.settings.defaults.atlas_padding;
+				match crate::import::atlas(&name, files, padding, spr_set) {
+					Ok(unreadable) => {
+						self.save_warnings = unreadable;
+						self.warnings_heading = "Some images could not be read";
+					}
+					Err(e) => self.error = Some(e),
+				}
+			}
+			self.atlas_picker_result = None;
+			self.check_unsaved = true;
+		}
+
+		if ctx.input(|input| input.viewport().close_requested()) && !self.allow_close {
+			self.update_unsaved();
+			if self.has_unsaved() {
+				ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+				self.exit_prompt = true;
+			}
+		}
+
+		if self.palette.is_some() {
+			let commands = self.commands();
+			let (chosen, close) = self
+				.palette
+				.as_mut()
+				.map_or((None, true), |palette| palette.show(ctx, &commands));
+			if close {
+				self.palette = None;
+			}
+			if let Some(i) = chosen {
+				(commands[i].run)(self, frame);
+			}
+		}
+
+		if let Some((keys, to)) = &self.non_finite_prompt {
+			let to = *to;
+			let mut write = None;
+			let modal = egui::Modal::new(egui::Id::new("NonFinitePrompt")).show(ctx, |ui| {
+				ui.heading("Invalid curve values");
+				ui.label(
+					"These keys have NaN or infinite numbers, the game will fail to load them",
+				);
+				egui::ScrollArea::vertical()
+					.max_height(300.0)
+					.show(ui, |ui| {
+						for key in keys {
+							ui.label(key);
+						}
+					});
+				ui.horizontal(|ui| {
+					if ui.button("Set to 0 and save").clicked() {
+						write = Some(true);
+						ui.close();
+					}
+					if ui.button("Save anyway").clicked() {
+						write = Some(false);
+						ui.close();
+					}
+					if ui.button("Cancel").clicked() {
+						ui.close();
+					}
+				});
+			});
+
+			if modal.should_close() {
+				self.non_finite_prompt = None;
+			}
+			if let Some(zero) = write {
+				if zero && let Some(aet_set) = &mut self.aet_set {
+					aet_set.non_finite_keys(true);
+				}
+				if to {
+					self.write_files_to();
+				} else if self.write_files() && self.close_after_save {
+					self.allow_close = true;
+					ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+				}
+			}
+			if self.non_finite_prompt.is_none() {
+				self.close_after_save = false;
+			}
+		}
+
+		if self.exit_prompt {
+			let modal = egui::Modal::new(egui::Id::new("ExitPrompt")).show(ctx, |ui| {
+				ui.heading("Unsaved changes");
+				ui.label("Save changes before closing?");
+				ui.horizontal(|ui| {
+					// A failed write is shown in the error modal and keeps the window open
+					if ui.button("Save").clicked() {
+						if self.save_files() {
+							self.allow_close = true;
+							ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+						} else {
+							self.close_after_save = self.non_finite_prompt.is_some();
+						}
+						ui.close();
+					}
+					if ui.button("Discard").clicked() {
+						self.allow_close = true;
+						ctx.send_viewport_cmd(egui::ViewportCommand::Close);
+						ui.close();
+					}
+					if ui.button("Cancel").clicked() {
+						ui.close();
+					}
+				});
+			});
+
+			if modal.should_close() {
+				self.exit_prompt = false;
+			}
+		}
+
+		self.update_watcher(ctx);
+		self.check_watcher(frame);
+
+		if self.reload_prompt {
+			let modal = egui::Modal::new(egui::Id::new("ReloadPrompt")).show(ctx, |ui| {
+				ui.heading("Unsaved changes");
+				ui.label("Reloading will discard unsaved changes");
+				ui.horizontal(|ui| {
+					if ui.button("Reload").clicked() {
+						self.reload_files(frame);
+						ui.close();
+					}
+					if ui.button("Cancel").clicked() {
+						ui.close();
+					}
+				});
+			});
+
+			if modal.should_close() {
+				self.reload_prompt = false;
+			}
+		}
+
+		if let Some(error) = &self.error {
+			let modal = egui::Modal::new(egui::Id::new("AppError")).show(ctx, |ui| {
+				ui.heading("An error has occured");
+				ui.vertical_centered(|ui| {
+					ui.label(error);
+					if ui.button("Ok").clicked() {
+						ui.close();
+					}
+				});
+			});
 
-		if let Some(aet_set) = &self.aet_set {
-			self.undoer
-				.feed_state(ctx.input(|input| input.time), &self.selected, aet_set);
+			if modal.should_close() {
+				self.error = None;
+			}
 		}
 
-		if let Some(rx) = &mut self.file_picker_result
-			&& let Ok(res) = rx.try_recv()
-		{
-			if let Some((path, data)) = res {
-				self.set_file(frame, &path, &data);
+		if !self.save_warnings.is_empty() {
+			let modal = egui::Modal::new(egui::Id::new("SaveWarnings")).show(ctx, |ui| {
+				ui.heading(self.warnings_heading);
+				egui::ScrollArea::vertical()
+					.max_height(300.0)
+					.show(ui, |ui| {
+						for warning in &self.save_warnings {
+							ui.label(warning);
+						}
+					});
+				ui.vertical_centered(|ui| {
+					if ui.button("Ok").clicked() {
+						ui.close();
+					}
+				});
+			});
+
+			if modal.should_close() {
+				self.save_warnings.clear();
 			}
-			self.file_picker_result = None;
 		}
 
 		egui::TopBottomPanel::top("MenuBar").show(ctx, |ui| {
 			egui::MenuBar::new().ui(ui, |ui| {
 				ui.menu_button("File", |ui| {
 					if ui
-						.add(
-							egui::Button::new("Open")
-								.shortcut_text(ctx.format_shortcut(&OPEN_SHORTCUT)),
-						)
+						.add(egui::Button::new("Open").shortcut_text(
+							ctx.format_shortcut(&self.settings.shortcut(Action::Open)),
+						))
 						.clicked()
 					{
-						let (tx, rx) = mpsc::channel();
-						std::thread::spawn(move || {
-							tokio::runtime::Builder::new_current_thread()
-								.enable_io()
-								.build()
-								.unwrap()
-								.block_on(async {
-									let Some(file) = rfd::AsyncFileDialog::new()
-										.add_filter("DIVA", &["farc", "bin"])
-										.pick_file()
-										.await
-									else {
-										tx.send(None).unwrap();
-										return;
-									};
-
-									let path = file.path();
-									let data = file.read().await;
-									tx.send(Some((path.to_path_buf(), data))).unwrap();
-								});
-						});
-
-						self.file_picker_result = Some(rx);
-						self.selected = Vec::new();
+						self.pick_file();
 						ui.close();
 					}
 
@@ -1088,8 +2589,9 @@ impl eframe::App for App {
 							self.aet_set.is_some()
 								|| self.sprite_set.is_some()
 								|| self.spr_db.is_some(),
-							egui::Button::new("Save")
-								.shortcut_text(ctx.format_shortcut(&SAVE_SHORTCUT)),
+							egui::Button::new("Save").shortcut_text(
+								ctx.format_shortcut(&self.settings.shortcut(Action::Save)),
+							),
 						)
 						.clicked()
 					{
@@ -1101,31 +2603,146 @@ impl eframe::App for App {
 							self.aet_set.is_some()
 								|| self.sprite_set.is_some()
 								|| self.spr_db.is_some(),
-							egui::Button::new("Save To")
-								.shortcut_text(ctx.format_shortcut(&SAVE_TO_SHORTCUT)),
+							egui::Button::new("Save To").shortcut_text(
+								ctx.format_shortcut(&self.settings.shortcut(Action::SaveTo)),
+							),
 						)
 						.clicked()
 					{
 						self.save_files_to();
 					}
 
+					ui.add_enabled_ui(
+						self.aet_set.is_some()
+							|| self.sprite_set.is_some()
+							|| self.spr_db.is_some(),
+						|ui| {
+							ui.menu_button("Export mod", |ui| {
+								let id = egui::Id::new("ModFarc");
+								let mut pack_farc =
+									ui.data(|data| data.get_temp::<bool>(id)).unwrap_or(true);
+								ui.checkbox(&mut pack_farc, "Pack in farc archives");
+								ui.data_mut(|data| data.insert_temp(id, pack_farc));
+
+								if ui
+									.button("Export")
+									.on_hover_text(
+										"Write the files into a mod folder laid out for DivaModLoader",
+									)
+									.clicked()
+								{
+									self.export_mod(pack_farc);
+									ui.close();
+								}
+							});
+						},
+					);
+
+					if ui
+						.add_enabled(
+							self.aet_set.is_some()
+								|| self.sprite_set.is_some()
+								|| self.spr_db.is_some(),
+							egui::Button::new("Reload"),
+						)
+						.clicked()
+					{
+						self.update_unsaved();
+						if self.has_unsaved() {
+							self.reload_prompt = true;
+						} else {
+							self.reload_files(frame);
+						}
+						ui.close();
+					}
+
+					if ui
+						.add_enabled(
+							self.aet_set.is_some()
+								|| self.sprite_set.is_some()
+								|| self.spr_db.is_some(),
+							egui::Button::new("Save project"),
+						)
+						.on_hover_text(
+							"Save which files are open together, opening it loads them all",
+						)
+						.clicked()
+					{
+						self.save_project();
+						ui.close();
+					}
+
+					if ui
+						.add_enabled(
+							self.aet_set.is_some() && self.sprite_set.is_some(),
+							egui::Button::new("Import PSD"),
+						)
+						.on_hover_text("Create a scene with a layer per PSD layer")
+						.clicked()
+					{
+						self.pick_psd();
+						ui.close();
+					}
+
+					if ui
+						.add_enabled(
+							self.sprite_set.is_some(),
+							egui::Button::new("Pack images into texture"),
+						)
+						.on_hover_text(
+							"Pack every image in a folder into a new texture with a sprite for each",
+						)
+						.clicked()
+					{
+						self.pick_atlas_folder();
+						ui.close();
+					}
+
+					ui.menu_button("Loaded files", |ui| {
+						let paths = [
+							("Aet", self.aet_set_filepath.clone()),
+							("Sprites", self.sprite_set_filepath.clone()),
+							("Sprite database", self.spr_db_filepath.clone()),
+						];
+						if paths.iter().all(|(_, path)| path.is_none()) {
+							ui.label("No files loaded");
+						}
+
+						egui::Grid::new("LoadedFilesGrid").show(ui, |ui| {
+							for (label, path) in paths {
+								let Some(path) = path else {
+									continue;
+								};
+
+								ui.label(label);
+								ui.label(path.display().to_string());
+								if ui
+									.button(ICON_FOLDER_OPEN)
+									.on_hover_text("Show in file browser")
+									.clicked()
+								{
+									if let Err(e) = opener::reveal(&path) {
+										self.error = Some(e.to_string());
+									}
+									ui.close();
+								}
+								ui.end_row();
+							}
+						});
+					});
+
 					if ui
 						.add_enabled(
 							self.aet_set.is_some()
 								|| self.sprite_set.is_some()
 								|| self.spr_db.is_some(),
-							egui::Button::new("Close")
-								.shortcut_text(ctx.format_shortcut(&CLOSE_SHORTCUT)),
+							egui::Button::new("Close").shortcut_text(
+								ctx.format_shortcut(&self.settings.shortcut(Action::Close)),
+							),
 						)
 						.clicked()
 					{
-						self.aet_set = None;
-						self.aet_set_filepath = None;
-						self.sprite_set = None;
-						self.sprite_set_filepath = None;
-						self.spr_db = None;
-						self.spr_db_filepath = None;
-						self.selected = Vec::new();
+						self.close_files();
 					}
 				});
 
@@ -1134,8 +2751,9 @@ impl eframe::App for App {
 						if ui
 							.add_enabled(
 								self.undoer.has_undo(),
-								egui::Button::new("Undo")
-									.shortcut_text(ctx.format_shortcut(&UNDO_SHORTCUT)),
+								egui::Button::new("Undo").shortcut_text(
+									ctx.format_shortcut(&self.settings.shortcut(Action::Undo)),
+								),
 							)
 							.clicked()
 						{
@@ -1153,8 +2771,9 @@ impl eframe::App for App {
 						if ui
 							.add_enabled(
 								self.undoer.has_redo(),
-								egui::Button::new("Redo")
-									.shortcut_text(ctx.format_shortcut(&REDO_SHORTCUT)),
+								egui::Button::new("Redo").shortcut_text(
+									ctx.format_shortcut(&self.settings.shortcut(Action::Redo)),
+								),
 							)
 							.clicked()
 						{
@@ -1171,40 +2790,263 @@ impl eframe::App for App {
 					} else {
 						ui.add_enabled(
 							false,
-							egui::Button::new("Undo")
-								.shortcut_text(ctx.format_shortcut(&UNDO_SHORTCUT)),
+							egui::Button::new("Undo").shortcut_text(
+								ctx.format_shortcut(&self.settings.shortcut(Action::Undo)),
+							),
 						);
 						ui.add_enabled(
 							false,
-							egui::Button::new("Redo")
-								.shortcut_text(ctx.format_shortcut(&REDO_SHORTCUT)),
+							egui::Button::new("Redo").shortcut_text(
+								ctx.format_shortcut(&self.settings.shortcut(Action::Redo)),
+							),
 						);
 					}
+
+					ui.separator();
+					if ui
+						.add_enabled(
+							self.aet_set.is_some(),
+							egui::Button::new("Replace sprite sources"),
+						)
+						.clicked()
+					{
+						self.source_replace = Some(aet::SourceReplace::default());
+						ui.close();
+					}
+
+					ui.separator();
+					if ui.button("Preferences").clicked() {
+						self.show_preferences = true;
+						ui.close();
+					}
+				});
+
+				ui.menu_button("Convert", |ui| {
+					let enabled = self.aet_set.is_some()
+						|| self.sprite_set.is_some()
+						|| self.spr_db.is_some();
+
+					if ui
+						.add_enabled(enabled, egui::Button::new("To classic"))
+						.clicked()
+					{
+						self.convert_files(false, false, frame);
+						ui.close();
+					}
+
+					if ui
+						.add_enabled(enabled, egui::Button::new("To modern"))
+						.clicked()
+					{
+						self.convert_files(true, false, frame);
+						ui.close();
+					}
+
+					if ui
+						.add_enabled(enabled, egui::Button::new("To modern (X)"))
+						.clicked()
+					{
+						self.convert_files(true, true, frame);
+						ui.close();
+					}
 				});
+
+				ui.menu_button("View", |ui| {
+					ui.checkbox(&mut self.show_render_stats, "Render statistics");
+					#[cfg(feature = "audio")]
+					ui.checkbox(&mut self.show_sounds, "Sounds");
+				});
+
+				if let Some(mismatch) = self.format_mismatch() {
+					ui.label(egui::RichText::new(ICON_WARNING).color(ui.visuals().warn_fg_color))
+						.on_hover_text(format!(
+							"Loaded files are for different games, one may be the wrong file\n{mismatch}"
+						));
+				}
 			});
 		});
 
+		egui::Window::new("Preferences")
+			.open(&mut self.show_preferences)
+			.resizable(false)
+			.show(ctx, |ui| {
+				self.settings.display(ui);
+			});
+		if !self.show_preferences {
+			self.settings.recording = None;
+		}
+
+		let mut open = self.source_replace.is_some();
+		let mut apply = false;
+		if let Some(replace) = &mut self.source_replace
+			&& let Some(aet_set) = &self.aet_set
+		{
+			egui::Window::new("Replace sprite sources")
+				.open(&mut open)
+				.show(ctx, |ui| {
+					egui::Grid::new("SourceReplaceGrid").show(ui, |ui| {
+						ui.label("Find in name");
+						ui.text_edit_singleline(&mut replace.find);
+						ui.end_row();
+
+						ui.label("Replace with");
+						ui.text_edit_singleline(&mut replace.replace);
+						ui.end_row();
+
+						ui.label("IDs from");
+						ui.horizontal(|ui| {
+							num_edit(ui, &mut replace.id_range[0], 0);
+							ui.label("to");
+							num_edit(ui, &mut replace.id_range[1], 0);
+						});
+						ui.end_row();
+
+						ui.label("Add to ID");
+						num_edit(ui, &mut replace.id_offset, 0);
+						ui.end_row();
+					});
+
+					ui.separator();
+
+					let changes = replace.preview(aet_set);
+					ui.label(format!("{} sources will change", changes.len()));
+					egui::ScrollArea::vertical()
+						.max_height(300.0)
+						.show(ui, |ui| {
+							egui::Grid::new("SourceReplacePreview")
+								.striped(true)
+								.show(ui, |ui| {
+									for change in &changes {
+										ui.label(&change.layer);
+										ui.label(format!("{} ({})", change.old.0, change.old.1));
+										ui.label(ICON_ARROW_RIGHT);
+										ui.label(format!("{} ({})", change.new.0, change.new.1));
+										ui.end_row();
+									}
+								});
+						});
+
+					if ui
+						.add_enabled(!changes.is_empty(), egui::Button::new("Apply"))
+						.clicked()
+					{
+						apply = true;
+					}
+				});
+		}
+
+		if apply
+			&& let Some(replace) = &self.source_replace
+			&& let Some(aet_set) = &mut self.aet_set
+		{
+			replace.apply(aet_set);
+			if let Some(spr_db) = &self.spr_db
+				&& let Some(spr_set) = &self.sprite_set
+			{
+				for scene in &mut aet_set.scenes {
+					scene.root.update_video_textures(spr_db, spr_set);
+				}
+			}
+		}
+		if !open || self.aet_set.is_none() {
+			self.source_replace = None;
+		}
+
+		egui::Window::new("Render statistics")
+			.open(&mut self.show_render_stats)
+			.resizable(false)
+			.show(ctx, |ui| {
+				let allocated = txp::UNIFORM_BUFFERS_ALLOCATED.load(atomic::Ordering::Relaxed);
+				let used = txp::UNIFORM_BUFFERS_USED.load(atomic::Ordering::Relaxed);
+				let size = std::mem::size_of::<txp::SpriteInfo>();
+
+				egui::Grid::new("RenderStatsGrid").show(ui, |ui| {
+					ui.label("Uniform buffers in use");
+					ui.label(used.to_string());
+					ui.end_row();
+
+					ui.label("Uniform buffers allocated");
+					ui.label(allocated.to_string());
+					ui.end_row();
+
+					ui.label("Allocated size");
+					ui.label(format!("{:.1} KiB", (allocated * size) as f32 / 1024.0));
+					ui.end_row();
+				});
+			});
+
+		#[cfg(feature = "audio")]
+		{
+			let used = self
+				.aet_set
+				.as_ref()
+				.map(|aet_set| aet_set.sound_indices())
+				.unwrap_or_default();
+			egui::Window::new("Sounds")
+				.open(&mut self.show_sounds)
+				.resizable(false)
+				.show(ctx, |ui| self.audio.display(ui, &used));
+		}
+
 		egui::SidePanel::right("RightSidePanel")
 			.resizable(true)
 			.show(ctx, |ui| {
-				if !self.selected.is_empty() {
+				let target = self.pinned.clone().unwrap_or(self.selected.clone());
+				if !target.is_empty() {
 					egui::TopBottomPanel::bottom("NodeOptions")
 						.resizable(true)
 						.show_inside(ui, |ui| {
+							// Edits to a pinned layer other than the selected one are recorded here,
+							// wherever the pointer or focus is
+							let pinned_before = self
+								.pinned_layer
+								.as_ref()
+								.filter(|_| self.pinned.is_some() && target != self.selected)
+								.map(|layer| (layer.clone(), layer.try_lock().unwrap().clone()));
+
+							if ui
+								.selectable_label(self.pinned.is_some(), ICON_PUSH_PIN)
+								.on_hover_text(
+									"Keep these options shown while selecting other nodes",
+								)
+								.clicked()
+							{
+								self.pinned = match self.pinned {
+									Some(_) => None,
+									None => Some(self.selected.clone()),
+								};
+								self.pinned_layer = self.aet_set.as_ref().and_then(|aet_set| {
+									aet_set
+										.scenes
+										.get(*self.selected.get(1)?)?
+										.selected_layer(&self.selected)
+								});
+							}
+
 							if let Some(node) = &mut self.aet_set
-								&& self.selected[0] == 0
+								&& target[0] == 0
 							{
-								show_node_opts(ui, node, 0, 0, &[], &self.selected, frame);
+								show_node_opts(ui, node, 0, 0, &[], &target, frame);
 							}
 							if let Some(node) = &mut self.sprite_set
-								&& self.selected[0] == 1
+								&& target[0] == 1
 							{
-								show_node_opts(ui, node, 1, 0, &[], &self.selected, frame);
+								show_node_opts(ui, node, 1, 0, &[], &target, frame);
 							}
 							if let Some(node) = &mut self.spr_db
-								&& self.selected[0] == 2
+								&& target[0] == 2
 							{
-								show_node_opts(ui, node, 2, 0, &[], &self.selected, frame);
+								show_node_opts(ui, node, 2, 0, &[], &target, frame);
+							}
+
+							if let Some((layer, before)) = pinned_before
+								&& self.pinned.as_ref() == Some(&target)
+							{
+								let after = layer.try_lock().unwrap();
+								if *after != before {
+									let time = ui.input(|input| input.time);
+									self.undoer.feed_edit(time, &target, before, &after);
+								}
 							}
 
 							ui.take_available_space();
@@ -1212,6 +3054,34 @@ impl eframe::App for App {
 				}
 
 				egui::ScrollArea::vertical().show(ui, |ui| {
+					// Arrow keys step frames unless the tree is hovered
+					let key = if ui.rect_contains_pointer(ui.clip_rect())
+						&& ui.ctx().memory(|memory| memory.focused().is_none())
+					{
+						ui.input_mut(|input| {
+							[
+								egui::Key::ArrowUp,
+								egui::Key::ArrowDown,
+								egui::Key::ArrowLeft,
+								egui::Key::ArrowRight,
+							]
+							.into_iter()
+							.find(|key| input.consume_key(egui::Modifiers::NONE, *key))
+						})
+					} else {
+						None
+					};
+
+					ui.ctx().data_mut(|data| {
+						data.insert_temp(egui::Id::new(TREE_ORDER), Vec::<Vec<usize>>::new());
+						if let Some(key @ (egui::Key::ArrowLeft | egui::Key::ArrowRight)) = key {
+							data.insert_temp(
+								egui::Id::new(TREE_EXPAND),
+								key == egui::Key::ArrowRight,
+							);
+						}
+					});
+
 					if let Some(node) = &mut self.aet_set {
 						show_node(
 							ui,
@@ -1246,6 +3116,39 @@ impl eframe::App for App {
 						);
 					}
 
+					let (order, expand) = ui.ctx().data_mut(|data| {
+						(
+							data.remove_temp::<Vec<Vec<usize>>>(egui::Id::new(TREE_ORDER))
+								.unwrap_or_default(),
+							data.remove_temp::<bool>(egui::Id::new(TREE_EXPAND)),
+						)
+					});
+					let position = order.iter().position(|path| *path == self.selected);
+					let next = match (key, position) {
+						(Some(egui::Key::ArrowUp), Some(i)) => order.get(i.saturating_sub(1)),
+						(Some(egui::Key::ArrowUp), None) => order.last(),
+						(Some(egui::Key::ArrowDown), Some(i)) => order.get(i + 1),
+						(Some(egui::Key::ArrowDown), None) => order.first(),
+						// Collapsing an already collapsed node moves up to its parent instead
+						(Some(egui::Key::ArrowLeft), Some(_))
+							if expand == Some(false) && self.selected.len() > 1 =>
+						{
+							order
+								.iter()
+								.find(|path| **path == self.selected[..self.selected.len() - 1])
+						}
+						_ => None,
+					};
+					if let Some(next) = next
+						&& *next != self.selected
+					{
+						self.selected = next.clone();
+						ui.ctx().data_mut(|data| {
+							data.insert_temp(egui::Id::new(TREE_SELECT), true);
+							data.insert_temp(egui::Id::new(SCROLL_TO_SELECTION), true);
+						});
+					}
+
 					ui.take_available_space();
 				});
 
@@ -1255,25 +3158,52 @@ impl eframe::App for App {
 		egui::TopBottomPanel::bottom("CurveEditor")
 			.resizable(true)
 			.show(ctx, |ui| {
+				let selected = self.selected.clone();
+				let add_key = self.settings.shortcut(Action::AddKey);
 				if let Some(scene) = self.get_active_scene() {
 					ui.horizontal(|ui| {
+						let has_comp = scene.selected_comp_layer(&selected).is_some();
+						let isolated_duration = scene.isolated_duration(&selected);
+						let loop_range = scene.loop_range(&selected);
+						let key_times = if scene.snap_to_keys {
+							scene.key_times(&selected)
+						} else {
+							Vec::new()
+						};
+						let (time, range) = if let Some(duration) = isolated_duration {
+							(&mut scene.isolated_time, 0.0..=duration)
+						} else {
+							(&mut scene.current_time, scene.start_time..=scene.end_time)
+						};
+
 						if ui.ctx().memory(|memory| memory.focused().is_none()) {
 							if ui.input_mut(|input| {
 								input.consume_key(egui::Modifiers::NONE, egui::Key::Space)
 							}) {
-								scene.playing = !scene.playing;
+								toggle_playback(
+									&mut scene.playing,
+									time,
+									&range,
+									scene.playback_speed,
+								);
 							}
 
 							if ui.input_mut(|input| {
 								input.consume_key(egui::Modifiers::NONE, egui::Key::ArrowLeft)
 							}) {
-								scene.current_time -= 1.0;
+								*time -= 1.0;
 							}
 
 							if ui.input_mut(|input| {
 								input.consume_key(egui::Modifiers::NONE, egui::Key::ArrowRight)
 							}) {
-								scene.current_time += 1.0;
+								*time += 1.0;
+							}
+
+							if scene.selected_curve.is_some()
+								&& ui.input_mut(|input| input.consume_shortcut(&add_key))
+							{
+								ui.data_mut(|data| data.insert_temp(egui::Id::new(ADD_KEY), true));
 							}
 						}
 
@@ -1285,18 +3215,26 @@ impl eframe::App for App {
 									let start = ui.available_width();
 
 									_ = ui.selectable_label(false, ICON_PLAY_ARROW);
+									_ = ui.button(ICON_KEY);
 									ui.checkbox(
 										&mut scene.display_placeholders,
 										"Display placeholders",
 									);
 									ui.checkbox(&mut scene.centered, "Centered");
+									ui.checkbox(&mut scene.perspective, "Perspective");
+									ui.checkbox(&mut scene.depth_sort, "Depth sort");
+									ui.checkbox(&mut scene.show_hierarchy, "Show hierarchy");
+									ui.checkbox(&mut scene.scrub_cache, "Cache frames");
+									ui.checkbox(&mut scene.snap_to_keys, "Snap to keys");
+									pixel_scale_combo(ui, &mut scene.pixel_scale);
+									ui.checkbox(&mut scene.blend_backdrop, "Backdrop only");
+									ui.checkbox(&mut scene.looping, "Loop");
+									speed_edit(ui, &mut scene.playback_speed);
+									ui.checkbox(&mut scene.isolate_comp, "Isolate composition");
 									ui.add(
-										egui::Slider::new(
-											&mut scene.current_time,
-											scene.start_time..=scene.end_time,
-										)
-										.clamping(egui::SliderClamping::Edits)
-										.max_decimals(0),
+										egui::Slider::new(&mut *time, range.clone())
+											.clamping(egui::SliderClamping::Edits)
+											.max_decimals(0),
 									);
 
 									start - ui.available_width()
@@ -1317,28 +3255,120 @@ impl eframe::App for App {
 							ICON_PLAY_ARROW
 						};
 						if ui.selectable_label(false, playback_icon).clicked() {
-							scene.playing = !scene.playing;
+							toggle_playback(&mut scene.playing, time, &range, scene.playback_speed);
+						}
+
+						if ui
+							.add_enabled(
+								scene.selected_curve.is_some(),
+								egui::Button::new(ICON_KEY),
+							)
+							.on_hover_text(format!(
+								"Add a key to the selected curve ({})",
+								ui.ctx().format_shortcut(&add_key)
+							))
+							.clicked()
+						{
+							ui.data_mut(|data| data.insert_temp(egui::Id::new(ADD_KEY), true));
 						}
 
 						ui.checkbox(&mut scene.display_placeholders, "Display placeholders");
 						ui.checkbox(&mut scene.centered, "Centered");
-						ui.add(
-							egui::Slider::new(
-								&mut scene.current_time,
-								scene.start_time..=scene.end_time,
-							)
-							.clamping(egui::SliderClamping::Edits)
-							.max_decimals(0),
+						ui.checkbox(&mut scene.perspective, "Perspective")
+							.on_hover_text("Preview 3D layers through a perspective camera");
+						ui.checkbox(&mut scene.depth_sort, "Depth sort")
+							.on_hover_text("Draw 3D layers back to front instead of in list order");
+						ui.checkbox(&mut scene.show_hierarchy, "Show hierarchy")
+							.on_hover_text(
+								"Outline every composition the selected layer inherits transforms from",
+							);
+						ui.checkbox(&mut scene.looping, "Loop").on_hover_text(
+							"Repeat playback between the loop markers, or over the whole range",
+						);
+						speed_edit(ui, &mut scene.playback_speed).on_hover_text(
+							"Playback speed, negative plays backwards. The scene's fps is not changed",
+						);
+						ui.checkbox(&mut scene.scrub_cache, "Cache frames")
+							.on_hover_text("Keep computed frames so scrubbing over them is faster");
+						ui.add_enabled(
+							scene.backdrop.is_some(),
+							egui::Checkbox::new(&mut scene.blend_backdrop, "Backdrop only"),
+						)
+						.on_hover_text(
+							"Preview the selected layer over only the backdrop layer, set from a layer's context menu",
 						);
+						pixel_scale_combo(ui, &mut scene.pixel_scale).on_hover_text(
+							"Show the scene at a whole multiple of its size, fitting it when the panel is too small",
+						);
+						ui.checkbox(&mut scene.snap_to_keys, "Snap to keys")
+							.on_hover_text(
+								"Scrub between the keys of the selected layer, or of every layer when none is",
+							);
+						ui.add_enabled(
+							has_comp,
+							egui::Checkbox::new(&mut scene.isolate_comp, "Isolate composition"),
+						)
+						.on_hover_text("Preview the selected composition on its own timeline");
+						let display = settings::TimeDisplay::get(ui.ctx());
+						let mut slider = egui::Slider::new(&mut *time, range.clone())
+							.clamping(egui::SliderClamping::Edits)
+							.max_decimals(0);
+						if display.in_seconds() {
+							let fps = display.fps as f64;
+							slider = slider
+								.custom_formatter(move |frames, _| format!("{:.2}s", frames / fps))
+								.custom_parser(move |text| {
+									text.trim_end_matches('s')
+										.trim()
+										.parse::<f64>()
+										.ok()
+										.map(|seconds| seconds * fps)
+								});
+						}
+						if ui.add(slider).changed() {
+							snap_to_key(time, &key_times, &range);
+						}
 
-						if scene.playing && scene.current_time < scene.end_time {
+						if scene.playing {
+							let speed = scene.playback_speed;
 							ctx.input(|input| {
-								scene.current_time += input.stable_dt * scene.fps;
+								*time += input.stable_dt * scene.fps * speed;
 							});
+							if scene.looping {
+								let (start, end) =
+									loop_range.unwrap_or((*range.start(), *range.end()));
+								if *time >= end || *time < start {
+									*time = if speed < 0.0 { end } else { start };
+								}
+							} else if *time >= *range.end() && speed >= 0.0 {
+								// Stops on the last frame, playing again starts over
+								*time = *range.end();
+								scene.playing = false;
+							} else if *time <= *range.start() && speed < 0.0 {
+								*time = *range.start();
+								scene.playing = false;
+							}
 							ctx.request_repaint();
 						}
 					});
 
+					let fps = scene.fps;
+					let key_times = if scene.snap_to_keys {
+						scene.key_times(&selected)
+					} else {
+						Vec::new()
+					};
+					let (time, range) = if let Some(duration) = scene.isolated_duration(&selected) {
+						(&mut scene.isolated_time, 0.0..=duration)
+					} else {
+						(&mut scene.current_time, scene.start_time..=scene.end_time)
+					};
+					let before = *time;
+					time_ruler(ui, time, range.clone(), fps);
+					if *time != before {
+						snap_to_key(time, &key_times, &range);
+					}
+
 					ui.separator();
 				}
 
@@ -1357,11 +3387,41 @@ impl eframe::App for App {
 						&self.selected,
 					);
 				}
+				// Not taken when the selection has no curve editor
+				ui.data_mut(|data| data.remove_temp::<bool>(egui::Id::new(ADD_KEY)));
 
 				ui.take_available_space();
 			});
 
+		// Isolated compositions play on their own timeline, which has no sounds placed on it
+		// Sounds can't be played backwards
+		#[cfg(feature = "audio")]
+		{
+			let selected = self.selected.clone();
+			let (sounds, fps, speed, playing) =
+				self.get_active_scene()
+					.map_or((Vec::new(), 60.0, 1.0, false), |scene| {
+						(
+							scene.sounds_at(),
+							scene.fps,
+							scene.playback_speed,
+							scene.playing
+								&& scene.playback_speed > 0.0
+								&& scene.isolated_duration(&selected).is_none(),
+						)
+					});
+			if let Err(e) = self.audio.update(&sounds, fps, speed, playing) {
+				self.error = Some(e);
+			}
+		}
+
+		if let Some(spr_db) = &mut self.spr_db {
+			spr_db.feed_state(ctx.input(|input| input.time), &self.selected);
+		}
+
 		if let Some(spr_set) = &mut self.sprite_set {
+			spr_set.feed_state(ctx.input(|input| input.time), &self.selected);
+
 			if spr_set.textures_node.children_changed
 				|| spr_set
 					.textures_node
@@ -1377,6 +3437,7 @@ impl eframe::App for App {
 				}
 			}
 
+			let mut new_entries = Vec::new();
 			if let Some(set) = &mut spr_set.db_set {
 				let mut set = set.try_lock().unwrap();
 				for (i, spr) in spr_set
@@ -1397,6 +3458,7 @@ impl eframe::App for App {
 					}));
 
 					spr.db_entry = Some(entry.clone());
+					new_entries.push(entry.clone());
 					set.entries.push(entry);
 				}
 
@@ -1416,13 +3478,57 @@ impl eframe::App for App {
 					}));
 
 					tex.db_entry = Some(entry.clone());
+					new_entries.push(entry.clone());
 					set.entries.push(entry);
 				}
 			}
 
 			spr_set.update_db_entries();
+
+			// Entries are only named by update_db_entries, so hash after it
+			if self.settings.hash_new_db_ids {
+				for entry in new_entries {
+					let mut entry = entry.try_lock().unwrap();
+					entry.id = kkdlib::hash::murmurhash(entry.name.bytes().collect::<Vec<_>>());
+				}
+			}
+		}
+
+		// Scrubbing only moves the pointer or steps frames, anything else may edit the aet
+		let edited = ctx.input(|input| {
+			input.events.iter().any(|event| match event {
+				egui::Event::PointerMoved(_) | egui::Event::MouseMoved(_) => false,
+				egui::Event::Key { key, .. } => {
+					!matches!(key, egui::Key::ArrowLeft | egui::Key::ArrowRight)
+				}
+				_ => true,
+			})
+		});
+		if let Some(aet_set) = &mut self.aet_set {
+			for scene in &mut aet_set.scenes {
+				scene.continue_strip(ctx, frame);
+			}
 		}
 
+		let generation = self.undoer.generation();
+		let (layer_generation, layer_path) = self.undoer.layer_generation();
+		if edited || generation != self.cache_generation {
+			if let Some(aet_set) = &mut self.aet_set {
+				for scene in &mut aet_set.scenes {
+					scene.frame_cache.clear();
+				}
+			}
+		} else if layer_generation != self.cache_layer_generation
+			&& let Some(aet_set) = &mut self.aet_set
+			&& let [0, scene, layer, ..] = *layer_path
+			&& let Some(scene) = aet_set.scenes.get_mut(scene)
+		{
+			// Curve and gizmo drags only touch the selected layer
+			scene.invalidate_layer(layer);
+		}
+		self.cache_generation = generation;
+		self.cache_layer_generation = layer_generation;
+
 		egui::CentralPanel::default().show(ctx, |ui| {
 			let selected = self.selected.clone();
 			if let Some(scene) = self.get_active_scene() {
@@ -1437,5 +3543,60 @@ impl eframe::App for App {
 				show_node_visual(ui, node, 1, 0, &[], &self.selected);
 			}
 		});
+
+		if self.selected != self.last_selected {
+			self.last_selected = self.selected.clone();
+
+			if self.settings.auto_select_curve {
+				let selected = self.selected.clone();
+				if let Some(scene) = self.get_active_scene()
+					&& let Some(layer) = scene.selected_layer(&selected)
+					&& let Some(curve) = layer.try_lock().unwrap().default_curve()
+				{
+					scene.selected_curve = Some(curve);
+				}
+
+				ctx.data_mut(|data| data.insert_temp(egui::Id::new(SCROLL_TO_SELECTION), true));
+			}
+		}
+
+		// Serialising is too slow with large textures to do on every input, so only the documents
+		// input could have edited are rechecked, once it has settled
+		let (edited, now) = ctx.input(|input| {
+			let edited = input.events.iter().any(|event| {
+				!matches!(
+					event,
+					egui::Event::PointerMoved(_) | egui::Event::MouseMoved(_)
+				)
+			});
+			(edited, input.time)
+		});
+		if edited {
+			let mut documents = self
+				.unsaved_recheck
+				.map_or([false; 3], |(_, documents)| documents);
+			documents[0] = true;
+			documents[1] |= self.selected.first() == Some(&1);
+			documents[2] = true;
+			self.unsaved_recheck = Some((now + UNSAVED_RECHECK_DELAY, documents));
+			ctx.request_repaint_after(std::time::Duration::from_secs_f64(UNSAVED_RECHECK_DELAY));
+		}
+
+		let documents = if self.check_unsaved {
+			Some([true; 3])
+		} else {
+			self.unsaved_recheck
+				.filter(|(time, _)| now >= *time)
+				.map(|(_, documents)| documents)
+		};
+		if let Some(documents) = documents {
+			self.check_unsaved = false;
+			self.unsaved_recheck = None;
+			let title = self.title.clone();
+			self.update_unsaved_of(documents);
+			if self.title != title {
+				ctx.send_viewport_cmd(egui::ViewportCommand::Title(self.title.clone()));
+			}
+		}
 	}
 }
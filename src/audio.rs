@@ -0,0 +1,202 @@
+use crate::aet::SoundAt;
+use eframe::egui;
+use egui_material_icons::icons::*;
+use rodio::Source;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+use std::sync::mpsc;
+
+// A sound started for one audio layer
+struct Voice {
+	key: usize,
+	// Where into the sound the layer was last seen
+	frame: f32,
+	sink: rodio::Sink,
+}
+
+// Plays files standing in for the game's sounds along with a scene's playback
+pub struct AudioPreview {
+	pub files: BTreeMap<u32, PathBuf>,
+	stream: Option<(rodio::OutputStream, rodio::OutputStreamHandle)>,
+	voices: Vec<Voice>,
+	file_picker_result: Option<mpsc::Receiver<Option<(u32, PathBuf)>>>,
+	// Sounds whose file could not be played, skipped until another file is picked
+	failed: BTreeSet<u32>,
+	// Whether no audio device could be opened, tried again the next time playback starts
+	device_failed: bool,
+}
+
+impl AudioPreview {
+	pub fn new() -> Self {
+		Self {
+			files: BTreeMap::new(),
+			stream: None,
+			voices: Vec::new(),
+			file_picker_result: None,
+			failed: BTreeSet::new(),
+			device_failed: false,
+		}
+	}
+
+	// Called every frame, voices stop when playback does and start over when the time jumps back
+	// Sinks have a single volume, so the left and right volumes are averaged
+	// Errors are only returned once, the sound or device that failed is skipped afterwards
+	pub fn update(
+		&mut self,
+		sounds: &[SoundAt],
+		fps: f32,
+		speed: f32,
+		playing: bool,
+	) -> Result<(), String> {
+		if !playing {
+			self.voices.clear();
+			self.device_failed = false;
+			return Ok(());
+		}
+		if self.device_failed {
+			return Ok(());
+		}
+
+		if self.stream.is_none() {
+			match rodio::OutputStream::try_default() {
+				Ok(stream) => self.stream = Some(stream),
+				Err(e) => {
+					self.device_failed = true;
+					return Err(format!("Could not open an audio device: {e}"));
+				}
+			}
+		}
+		let Some((_, handle)) = &self.stream else {
+			return Ok(());
+		};
+
+		self.voices.retain(|voice| {
+			sounds.iter().any(|sound| {
+				sound.key == voice.key
+					&& sound.frame >= voice.frame
+					&& sound.frame - voice.frame < fps / 2.0
+			})
+		});
+
+		for sound in sounds {
+			let volume = (sound.volume[0] + sound.volume[1]) / 2.0;
+			if let Some(voice) = self.voices.iter_mut().find(|voice| voice.key == sound.key) {
+				voice.frame = sound.frame;
+				voice.sink.set_volume(volume);
+				voice.sink.set_speed(speed);
+				continue;
+			}
+
+			let Some(path) = self.files.get(&sound.sound_index) else {
+				continue;
+			};
+			if self.failed.contains(&sound.sound_index) {
+				continue;
+			}
+			let source = std::fs::File::open(path)
+				.map_err(|e| e.to_string())
+				.and_then(|file| {
+					rodio::Decoder::new(std::io::BufReader::new(file)).map_err(|e| e.to_string())
+				});
+			let source = match source {
+				Ok(source) => source,
+				Err(e) => {
+					self.failed.insert(sound.sound_index);
+					return Err(format!("Could not play {}: {e}", path.display()));
+				}
+			};
+			let sink = match rodio::Sink::try_new(handle) {
+				Ok(sink) => sink,
+				Err(e) => {
+					self.failed.insert(sound.sound_index);
+					return Err(format!("Could not play {}: {e}", path.display()));
+				}
+			};
+
+			sink.set_volume(volume);
+			sink.set_speed(speed);
+			sink.append(source.skip_duration(std::time::Duration::from_secs_f32(
+				(sound.frame / fps).max(0.0),
+			)));
+			self.voices.push(Voice {
+				key: sound.key,
+				frame: sound.frame,
+				sink,
+			});
+		}
+
+		Ok(())
+	}
+
+	// A row per sound index the aet uses or that has a file
+	pub fn display(&mut self, ui: &mut egui::Ui, used: &[u32]) {
+		if let Some(rx) = &mut self.file_picker_result
+			&& let Ok(res) = rx.try_recv()
+		{
+			if let Some((index, path)) = res {
+				self.files.insert(index, path);
+				self.failed.remove(&index);
+				self.voices.clear();
+			}
+			self.file_picker_result = None;
+		}
+
+		let mut indices = used.to_vec();
+		indices.extend(self.files.keys());
+		indices.sort();
+		indices.dedup();
+
+		if indices.is_empty() {
+			ui.label("No audio layers");
+			return;
+		}
+
+		egui::Grid::new("SoundsGrid").striped(true).show(ui, |ui| {
+			for index in indices {
+				ui.label(format!("Sound {index}"));
+				match self.files.get(&index) {
+					Some(path) => ui.label(path.file_name().unwrap_or_default().to_string_lossy()),
+					None => ui.weak("No file"),
+				};
+
+				ui.horizontal(|ui| {
+					if ui
+						.button(ICON_FOLDER_OPEN)
+						.on_hover_text("Pick a WAV file to play for this sound")
+						.clicked()
+					{
+						let (tx, rx) = mpsc::channel();
+						std::thread::spawn(move || {
+							tokio::runtime::Builder::new_current_thread()
+								.enable_io()
+								.build()
+								.unwrap()
+								.block_on(async {
+									let file = rfd::AsyncFileDialog::new()
+										.add_filter("WAV", &["wav"])
+										.pick_file()
+										.await;
+									tx.send(file.map(|file| (index, file.path().to_path_buf())))
+										.unwrap();
+								});
+						});
+
+						self.file_picker_result = Some(rx);
+					}
+
+					if self.files.contains_key(&index)
+						&& ui
+							.button(ICON_DELETE)
+							.on_hover_text("Forget the file")
+							.clicked()
+					{
+						self.files.remove(&index);
+						self.failed.remove(&index);
+						self.voices.clear();
+					}
+				});
+				ui.end_row();
+			}
+		});
+	}
+}
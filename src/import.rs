@@ -0,0 +1,422 @@
+use crate::aet::*;
+use crate::spr::*;
+use crate::txp::*;
+use kkdlib::*;
+use std::rc::Rc;
+use std::sync::*;
+
+const ATLAS_PADDING: u32 = 2;
+const ATLAS_MAX_WIDTH: u32 = 4096;
+// Larger textures can't be created on most GPUs
+const ATLAS_MAX_SIZE: u32 = 16384;
+
+struct PsdLayer {
+	name: String,
+	image: image::RgbaImage,
+	left: i32,
+	top: i32,
+	opacity: f32,
+	visible: bool,
+	blend_mode: aet::BlendMode,
+}
+
+// Builds a scene with one video layer per PSD layer, their pixels packed into a new texture
+// Returns the PSD features that could not be carried over
+pub fn psd(
+	name: &str,
+	data: &[u8],
+	aet_set: &mut AetSetNode,
+	spr_set: &mut SpriteSetNode,
+) -> Result<Vec<String>, String> {
+	let psd = psd::Psd::from_bytes(data).map_err(|e| e.to_string())?;
+	let mut unsupported = Vec::new();
+
+	let mut layers = Vec::new();
+	for layer in psd.layers() {
+		let left = layer.layer_left().max(0);
+		let top = layer.layer_top().max(0);
+		let right = layer.layer_right().min(psd.width() as i32);
+		let bottom = layer.layer_bottom().min(psd.height() as i32);
+		if right <= left || bottom <= top {
+			continue;
+		}
+
+		let Some(canvas) = image::RgbaImage::from_raw(psd.width(), psd.height(), layer.rgba())
+		else {
+			return Err(format!("Could not read pixels of {}", layer.name()));
+		};
+		let image = image::imageops::crop_imm(
+			&canvas,
+			left as u32,
+			top as u32,
+			(right - left) as u32,
+			(bottom - top) as u32,
+		)
+		.to_image();
+
+		let blend_mode = match layer.blend_mode() {
+			psd::BlendMode::Normal => aet::BlendMode::Normal,
+			psd::BlendMode::Screen => aet::BlendMode::Screen,
+			psd::BlendMode::LinearDodge => aet::BlendMode::Add,
+			psd::BlendMode::Multiply => aet::BlendMode::Multiply,
+			psd::BlendMode::Overlay => aet::BlendMode::Overlay,
+			mode => {
+				unsupported.push(format!(
+					"{}: {:?} blending, using Normal",
+					layer.name(),
+					mode
+				));
+				aet::BlendMode::Normal
+			}
+		};
+
+		layers.push(PsdLayer {
+			name: layer.name().to_string(),
+			image,
+			left,
+			top,
+			opacity: layer.opacity() as f32 / 255.0,
+			visible: layer.visible(),
+			blend_mode,
+		});
+	}
+
+	if layers.is_empty() {
+		return Err(String::from("PSD has no layers with pixels"));
+	}
+
+	let (atlas, positions) = pack(
+		&layers.iter().map(|layer| &layer.image).collect::<Vec<_>>(),
+		ATLAS_PADDING,
+	)?;
+	let (texture, texture_index) = add_texture(name, atlas, spr_set);
+
+	let sprites = spr_set.sprites_node.children.clone();
+	let mut root = AetCompNode { layers: Vec::new() };
+	for (layer, (x, y)) in layers.iter().zip(positions) {
+		let sprite = Rc::new(Mutex::new(sprite_node(
+			format!("{}_{}", name, layer.name),
+			&layer.image,
+			(x, y),
+			texture.clone(),
+			texture_index,
+			spr_set,
+		)));
+		sprites.try_lock().unwrap().push(sprite.clone());
+
+		// The first layer of a composition is drawn on top, PSDs list the bottom layer first
+		root.layers.insert(
+			0,
+			Rc::new(Mutex::new(video_layer(layer, sprite, sprites.clone()))),
+		);
+	}
+
+	aet_set.scenes.push(AetSceneNode {
+		name: name.to_string(),
+		start_time: 0.0,
+		end_time: 60.0,
+		fps: 60.0,
+		color: [0, 0, 0],
+		width: psd.width(),
+		height: psd.height(),
+		camera: None,
+		root,
+		note: String::new(),
+
+		current_time: 0.0,
+		playing: false,
+		display_placeholders: false,
+		centered: false,
+		perspective: false,
+		depth_sort: false,
+		show_hierarchy: false,
+		isolate_comp: false,
+		isolated_time: 0.0,
+		looping: false,
+		playback_speed: 1.0,
+		scrub_cache: false,
+		snap_to_keys: false,
+		pixel_scale: None,
+		backdrop: None,
+		blend_backdrop: false,
+		want_deletion: false,
+		want_duplicate: false,
+		frame_cache: std::collections::HashMap::new(),
+		want_strip_export: None,
+		strip_render: None,
+		confirm_removal: false,
+		error: None,
+
+		selected_curve: None,
+		gizmo: transform_gizmo_egui::Gizmo::default(),
+	});
+
+	Ok(unsupported)
+}
+
+// Creates a sprite per image of a folder, all packed into one new texture
+// Returns the files that could not be read
+pub fn atlas(
+	name: &str,
+	mut files: Vec<(std::path::PathBuf, Vec<u8>)>,
+	padding: u32,
+	spr_set: &mut SpriteSetNode,
+) -> Result<Vec<String>, String> {
+	files.sort_by(|(a, _), (b, _)| a.cmp(b));
+	let mut unreadable = Vec::new();
+	let mut images = Vec::new();
+	for (path, data) in &files {
+		let file = path.file_name().unwrap_or_default().to_string_lossy();
+		let Ok(format) = image::ImageFormat::from_path(path) else {
+			continue;
+		};
+		match image::load(std::io::Cursor::new(data), format) {
+			Ok(image) => images.push((
+				path.file_stem()
+					.unwrap_or_default()
+					.to_string_lossy()
+					.to_string(),
+				image.to_rgba8(),
+			)),
+			Err(e) => unreadable.push(format!("{file}: {e}")),
+		}
+	}
+
+	if images.is_empty() {
+		return Err(String::from("Folder has no images that could be read"));
+	}
+
+	let (atlas, positions) = pack(
+		&images.iter().map(|(_, image)| image).collect::<Vec<_>>(),
+		padding,
+	)?;
+	let (texture, texture_index) = add_texture(name, atlas, spr_set);
+
+	for ((name, image), position) in images.iter().zip(positions) {
+		let sprite = sprite_node(
+			name.clone(),
+			image,
+			position,
+			texture.clone(),
+			texture_index,
+			spr_set,
+		);
+		spr_set
+			.sprites_node
+			.children
+			.try_lock()
+			.unwrap()
+			.push(Rc::new(Mutex::new(sprite)));
+	}
+
+	Ok(unreadable)
+}
+
+// Appends the atlas to the set's textures, flipped like the ones already there
+fn add_texture(
+	name: &str,
+	atlas: image::RgbaImage,
+	spr_set: &mut SpriteSetNode,
+) -> (Rc<Mutex<TextureNode>>, u32) {
+	let mut mip = txp::Mipmap::new();
+	mip.set_width(atlas.width() as _);
+	mip.set_height(atlas.height() as _);
+	mip.set_format(txp::Format::RGBA8);
+	mip.set_data(
+		image::DynamicImage::ImageRgba8(atlas)
+			.flipv()
+			.to_rgba8()
+			.as_raw(),
+	);
+
+	let mut texture = txp::Texture::new();
+	texture.set_array_size(1);
+	texture.set_mipmaps_count(1);
+	texture.set_has_cube_map(false);
+	texture.add_mipmap(&mip);
+
+	let textures = &mut spr_set.textures_node;
+	let texture_index = textures.children.len() as u32;
+	let texture = Rc::new(Mutex::new(TextureNode {
+		name: name.to_string(),
+		texture,
+		flip: textures
+			.children
+			.first()
+			.map_or(true, |tex| tex.try_lock().unwrap().flip),
+		index: texture_index,
+		texture_updated: true,
+		db_entry: None,
+		file_picker_result: None,
+		mip_folder_result: None,
+		error: None,
+		adjustment: ColorAdjustment::default(),
+		exposure: 0.0,
+		want_sprite_filter: false,
+		want_deletion: false,
+	}));
+	textures.children.push(texture.clone());
+	textures.children_changed = true;
+
+	(texture, texture_index)
+}
+
+fn sprite_node(
+	name: String,
+	image: &image::RgbaImage,
+	(x, y): (u32, u32),
+	texture: Rc<Mutex<TextureNode>>,
+	texture_index: u32,
+	spr_set: &SpriteSetNode,
+) -> SpriteInfoNode {
+	let mut info = spr::Info::new();
+	info.set_texid(texture_index);
+	info.set_px(x as f32);
+	info.set_py(y as f32);
+	info.set_width(image.width() as f32);
+	info.set_height(image.height() as f32);
+	info.set_resolution_mode(spr::ResolutionMode::FHD);
+
+	SpriteInfoNode {
+		name,
+		info,
+		texture,
+		texture_names: spr_set.texture_names.clone(),
+		want_new_texture: None,
+		texture_missing: false,
+		hidden: false,
+		db_entry: None,
+		file_picker_result: None,
+		error: None,
+		want_deletion: false,
+	}
+}
+
+fn video_layer(
+	layer: &PsdLayer,
+	sprite: Rc<Mutex<SpriteInfoNode>>,
+	sprites: Rc<Mutex<Vec<Rc<Mutex<SpriteInfoNode>>>>>,
+) -> AetLayerNode {
+	let constant = |value: f32| aet::FCurve {
+		keys: vec![aet::FCurveKey {
+			frame: 0.0,
+			value,
+			tangent: 0.0,
+		}],
+	};
+	// Without a sprite database the source is saved as is, so it has to name the new sprite
+	let source_name = sprite.try_lock().unwrap().name.clone();
+	let source_id = kkdlib::hash::murmurhash(source_name.bytes().collect::<Vec<_>>());
+
+	AetLayerNode {
+		name: layer.name.clone(),
+		start_time: 0.0,
+		end_time: 60.0,
+		offset_time: 0.0,
+		time_scale: 1.0,
+		flags: aet::LayerFlags::new().with_video_active(layer.visible),
+		quality: aet::LayerQuality::Best,
+		item: AetItemNode::Video(AetVideoNode {
+			color: [255, 255, 255],
+			width: layer.image.width() as u16,
+			height: layer.image.height() as u16,
+			fpf: 1.0,
+			sources: vec![AetVideoSourceNode {
+				name: source_name,
+				id: source_id,
+				sprite: Some(sprite),
+			}],
+		}),
+		markers: Vec::new(),
+		video: Some(aet::LayerVideo {
+			transfer_mode: aet::TransferMode {
+				mode: layer.blend_mode,
+				flag: 0,
+				matte: 0,
+			},
+			anchor_x: aet::FCurve { keys: Vec::new() },
+			anchor_y: aet::FCurve { keys: Vec::new() },
+			pos_x: constant(layer.left as f32),
+			pos_y: constant(layer.top as f32),
+			rot_z: aet::FCurve { keys: Vec::new() },
+			scale_x: constant(1.0),
+			scale_y: constant(1.0),
+			opacity: constant(layer.opacity),
+			_3d: None,
+		}),
+		parent: None,
+		audio: None,
+		note: String::new(),
+		sprites,
+		visible: true,
+		selected_key: 0,
+		selected_keys: Vec::new(),
+		want_deletion: false,
+		want_duplicate: false,
+		precompose: None,
+		duplicate_offset: None,
+		dragging_tangent: false,
+		dragging_key: false,
+		error: None,
+		curve_file_picker_result: None,
+	}
+}
+
+// Shelf packs the layers tallest first into a power of two sized atlas
+// Layers are never rotated, spr::Info has no flag to rotate them back
+fn pack(
+	images: &[&image::RgbaImage],
+	padding: u32,
+) -> Result<(image::RgbaImage, Vec<(u32, u32)>), String> {
+	let widest = images
+		.iter()
+		.map(|image| image.width() + padding)
+		.max()
+		.unwrap_or(1);
+	let area = images
+		.iter()
+		.map(|image| (image.width() + padding) as u64 * (image.height() + padding) as u64)
+		.sum::<u64>();
+	let mut width = widest
+		.max((area as f64).sqrt() as u32)
+		.next_power_of_two()
+		.min(ATLAS_MAX_WIDTH.max(widest.next_power_of_two()));
+
+	let mut order = (0..images.len()).collect::<Vec<_>>();
+	order.sort_by_key(|&i| std::cmp::Reverse(images[i].height()));
+
+	// Too tall atlases are widened, up to the largest size a texture can have
+	let (positions, height) = loop {
+		let mut positions = vec![(0, 0); images.len()];
+		let (mut x, mut y, mut shelf_height) = (0, 0, 0);
+		for &i in &order {
+			let image = images[i];
+			if x + image.width() > width {
+				x = 0;
+				y += shelf_height;
+				shelf_height = 0;
+			}
+			positions[i] = (x, y);
+			x += image.width() + padding;
+			shelf_height = shelf_height.max(image.height() + padding);
+		}
+		let height = (y + shelf_height).next_power_of_two();
+
+		if width <= ATLAS_MAX_SIZE && height <= ATLAS_MAX_SIZE {
+			break (positions, height);
+		}
+		if width >= ATLAS_MAX_SIZE {
+			return Err(format!(
+				"Images need a {width}x{height} texture, the largest possible is {ATLAS_MAX_SIZE}x{ATLAS_MAX_SIZE}"
+			));
+		}
+		width *= 2;
+	};
+
+	let mut atlas = image::RgbaImage::new(width, height);
+	for (image, (x, y)) in images.iter().zip(&positions) {
+		image::imageops::replace(&mut atlas, *image, *x as i64, *y as i64);
+	}
+
+	Ok((atlas, positions))
+}
@@ -1,5 +1,11 @@
 pub mod aet;
 pub mod app;
+#[cfg(feature = "audio")]
+pub mod audio;
+pub mod import;
+pub mod palette;
+pub mod project;
+pub mod settings;
 pub mod spr;
 pub mod spr_db;
 pub mod txp;
@@ -0,0 +1,106 @@
+use crate::app::App;
+use eframe::egui;
+
+// A named action the palette can run
+pub struct Command {
+	pub name: &'static str,
+	pub enabled: bool,
+	pub run: fn(&mut App, &mut eframe::Frame),
+}
+
+#[derive(Default)]
+pub struct Palette {
+	pub filter: String,
+	// Row of the highlighted match
+	pub index: usize,
+}
+
+// Higher for query characters found in a row or at the start of words, None when any is missing
+pub fn fuzzy_score(query: &str, name: &str) -> Option<i32> {
+	let name = name.to_lowercase().chars().collect::<Vec<_>>();
+	let mut score = 0;
+	let mut next = 0;
+	let mut last = None;
+	for c in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+		let i = next + name[next..].iter().position(|n| *n == c)?;
+		if last.is_some_and(|last| last + 1 == i) {
+			score += 4;
+		}
+		if i == 0 || name[i - 1] == ' ' || name[i - 1] == '(' {
+			score += 2;
+		}
+		score -= (i - next) as i32;
+		last = Some(i);
+		next = i + 1;
+	}
+
+	Some(score)
+}
+
+impl Palette {
+	// The command chosen this frame, and whether the palette should close
+	pub fn show(&mut self, ctx: &egui::Context, commands: &[Command]) -> (Option<usize>, bool) {
+		let mut matches = commands
+			.iter()
+			.enumerate()
+			.filter(|(_, command)| command.enabled)
+			.filter_map(|(i, command)| Some((fuzzy_score(&self.filter, command.name)?, i)))
+			.collect::<Vec<_>>();
+		// Stable, so equal scores keep the registry order
+		matches.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+		self.index = self.index.min(matches.len().saturating_sub(1));
+
+		let mut chosen = None;
+		let modal = egui::Modal::new(egui::Id::new("CommandPalette")).show(ctx, |ui| {
+			ui.set_width(400.0);
+
+			let (up, down, enter) = ui.input_mut(|input| {
+				(
+					input.consume_key(egui::Modifiers::NONE, egui::Key::ArrowUp),
+					input.consume_key(egui::Modifiers::NONE, egui::Key::ArrowDown),
+					input.consume_key(egui::Modifiers::NONE, egui::Key::Enter),
+				)
+			});
+			if up {
+				self.index = self.index.saturating_sub(1);
+			}
+			if down && self.index + 1 < matches.len() {
+				self.index += 1;
+			}
+			if enter {
+				chosen = matches.get(self.index).map(|(_, i)| *i);
+			}
+
+			let resp = ui.add(
+				egui::TextEdit::singleline(&mut self.filter)
+					.hint_text("Type a command")
+					.desired_width(f32::INFINITY),
+			);
+			resp.request_focus();
+			if resp.changed() {
+				self.index = 0;
+			}
+
+			ui.separator();
+
+			if matches.is_empty() {
+				ui.weak("No matching commands");
+			}
+			egui::ScrollArea::vertical()
+				.max_height(300.0)
+				.show(ui, |ui| {
+					for (row, (_, i)) in matches.iter().enumerate() {
+						let resp = ui.selectable_label(row == self.index, commands[*i].name);
+						if row == self.index && (up || down) {
+							resp.scroll_to_me(None);
+						}
+						if resp.clicked() {
+							chosen = Some(*i);
+						}
+					}
+				});
+		});
+
+		(chosen, chosen.is_some() || modal.should_close())
+	}
+}
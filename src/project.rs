@@ -0,0 +1,68 @@
+use std::path::{Path, PathBuf};
+
+// A file of a project and the format it had when the project was saved
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+pub struct ProjectFile {
+	pub path: PathBuf,
+	pub modern: bool,
+	pub big_endian: bool,
+	pub is_x: bool,
+}
+
+impl ProjectFile {
+	pub fn format(&self) -> (bool, bool, bool) {
+		(self.modern, self.big_endian, self.is_x)
+	}
+}
+
+// Which aet set, sprite set and sprite database are edited together, saved as TOML
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Project {
+	pub aet_set: Option<ProjectFile>,
+	pub sprite_set: Option<ProjectFile>,
+	pub spr_db: Option<ProjectFile>,
+	// Notes on aet scenes and layers, keyed by their indices joined with /
+	pub notes: std::collections::BTreeMap<String, String>,
+}
+
+impl Project {
+	pub const EXTENSION: &str = "reaet";
+
+	pub fn is_project(path: &Path) -> bool {
+		path.extension()
+			.is_some_and(|extension| extension == Self::EXTENSION)
+	}
+
+	// Relative paths are resolved against the directory of the project file
+	pub fn read(dir: &Path, data: &[u8]) -> Result<Self, String> {
+		let text = std::str::from_utf8(data).map_err(|e| e.to_string())?;
+		let mut project: Self = toml::from_str(text).map_err(|e| e.to_string())?;
+		for file in project.files_mut() {
+			file.path = dir.join(&file.path);
+		}
+		Ok(project)
+	}
+
+	// Files next to or below the project file are stored relative so the folder can be moved
+	pub fn write(&self, dir: &Path) -> Result<String, String> {
+		let mut project = Self {
+			aet_set: self.aet_set.clone(),
+			sprite_set: self.sprite_set.clone(),
+			spr_db: self.spr_db.clone(),
+			notes: self.notes.clone(),
+		};
+		for file in project.files_mut() {
+			if let Ok(relative) = file.path.strip_prefix(dir) {
+				file.path = relative.to_path_buf();
+			}
+		}
+		toml::to_string_pretty(&project).map_err(|e| e.to_string())
+	}
+
+	fn files_mut(&mut self) -> impl Iterator<Item = &mut ProjectFile> {
+		[&mut self.aet_set, &mut self.sprite_set, &mut self.spr_db]
+			.into_iter()
+			.flatten()
+	}
+}
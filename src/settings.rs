@@ -0,0 +1,268 @@
+use eframe::egui;
+use std::collections::HashMap;
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+pub enum Action {
+	Open,
+	Save,
+	SaveTo,
+	Close,
+	Undo,
+	Redo,
+	AddKey,
+	CommandPalette,
+}
+
+impl Action {
+	pub const ALL: [Action; 8] = [
+		Action::Open,
+		Action::Save,
+		Action::SaveTo,
+		Action::Close,
+		Action::Undo,
+		Action::Redo,
+		Action::AddKey,
+		Action::CommandPalette,
+	];
+
+	pub fn name(&self) -> &'static str {
+		match self {
+			Action::Open => "Open",
+			Action::Save => "Save",
+			Action::SaveTo => "Save To",
+			Action::Close => "Close",
+			Action::Undo => "Undo",
+			Action::Redo => "Redo",
+			Action::AddKey => "Add key",
+			Action::CommandPalette => "Command palette",
+		}
+	}
+
+	pub fn default_shortcut(&self) -> egui::KeyboardShortcut {
+		match self {
+			Action::Open => egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::O),
+			Action::Save => egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::S),
+			Action::SaveTo => egui::KeyboardShortcut::new(
+				egui::Modifiers::COMMAND.plus(egui::Modifiers::SHIFT),
+				egui::Key::S,
+			),
+			Action::Close => egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::W),
+			Action::Undo => egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Z),
+			Action::Redo => egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::Y),
+			Action::AddKey => egui::KeyboardShortcut::new(egui::Modifiers::NONE, egui::Key::K),
+			Action::CommandPalette => {
+				egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::P)
+			}
+		}
+	}
+}
+
+// Properties of layers, sprites and textures created from the context menus
+#[derive(Clone, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Defaults {
+	pub layer_name: String,
+	// 0 uses the length of the parent layer
+	pub layer_duration: f32,
+	pub sprite_size: [f32; 2],
+	pub texture_size: [u32; 2],
+	// Gap left between images packed into one texture
+	pub atlas_padding: u32,
+}
+
+impl Default for Defaults {
+	fn default() -> Self {
+		Self {
+			layer_name: String::from("DUMMY"),
+			layer_duration: 0.0,
+			sprite_size: [2.0, 2.0],
+			texture_size: [16, 16],
+			atlas_padding: 2,
+		}
+	}
+}
+
+impl Defaults {
+	const ID: &str = "Defaults";
+	// Larger textures can't be created on most GPUs
+	pub const MAX_TEXTURE_SIZE: u32 = 16384;
+
+	// Published by the app every frame so context menus can read them
+	pub fn get(ctx: &egui::Context) -> Self {
+		ctx.data(|data| data.get_temp(egui::Id::new(Self::ID)))
+			.unwrap_or_default()
+	}
+
+	pub fn publish(&self, ctx: &egui::Context) {
+		ctx.data_mut(|data| data.insert_temp(egui::Id::new(Self::ID), self.clone()));
+	}
+}
+
+// Units time fields are shown in, times are always stored in frames
+#[derive(Clone, Copy)]
+pub struct TimeDisplay {
+	pub seconds: bool,
+	// Of the active scene, seconds fall back to frames without one
+	pub fps: f32,
+}
+
+impl TimeDisplay {
+	const ID: &str = "TimeDisplay";
+
+	pub fn get(ctx: &egui::Context) -> Self {
+		ctx.data(|data| data.get_temp(egui::Id::new(Self::ID)))
+			.unwrap_or(Self {
+				seconds: false,
+				fps: 0.0,
+			})
+	}
+
+	pub fn publish(&self, ctx: &egui::Context) {
+		ctx.data_mut(|data| data.insert_temp(egui::Id::new(Self::ID), *self));
+	}
+
+	pub fn in_seconds(&self) -> bool {
+		self.seconds && self.fps > 0.0
+	}
+}
+
+#[derive(Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct Settings {
+	// Only rebound actions are stored so new defaults still apply
+	pub shortcuts: HashMap<Action, egui::KeyboardShortcut>,
+	pub auto_select_curve: bool,
+	pub validate_keys: bool,
+	pub hash_new_db_ids: bool,
+	pub watch_files: bool,
+	pub time_in_seconds: bool,
+	pub defaults: Defaults,
+
+	#[serde(skip)]
+	pub recording: Option<Action>,
+}
+
+impl Settings {
+	pub fn load(storage: Option<&dyn eframe::Storage>) -> Self {
+		storage
+			.and_then(|storage| eframe::get_value(storage, eframe::APP_KEY))
+			.unwrap_or_default()
+	}
+
+	pub fn shortcut(&self, action: Action) -> egui::KeyboardShortcut {
+		self.shortcuts
+			.get(&action)
+			.copied()
+			.unwrap_or(action.default_shortcut())
+	}
+
+	pub fn display(&mut self, ui: &mut egui::Ui) {
+		if let Some(action) = self.recording {
+			let pressed = ui.input(|input| {
+				input.events.iter().find_map(|event| match event {
+					egui::Event::Key {
+						key,
+						pressed: true,
+						modifiers,
+						..
+					} => Some(egui::KeyboardShortcut::new(*modifiers, *key)),
+					_ => None,
+				})
+			});
+
+			if let Some(shortcut) = pressed {
+				if shortcut.logical_key != egui::Key::Escape {
+					self.shortcuts.insert(action, shortcut);
+				}
+				self.recording = None;
+			}
+		}
+
+		ui.heading("General");
+		ui.checkbox(
+			&mut self.auto_select_curve,
+			"Select a curve and reveal layers when selecting them",
+		);
+		ui.checkbox(
+			&mut self.validate_keys,
+			"Report keys outside their layer when opening an aet set",
+		);
+		ui.checkbox(
+			&mut self.hash_new_db_ids,
+			"Give new database entries an ID hashed from their name",
+		)
+		.on_hover_text("Otherwise new entries start at ID 0 until Murmur is pressed");
+		ui.checkbox(
+			&mut self.watch_files,
+			"Reload files when they change on disk",
+		);
+		ui.checkbox(&mut self.time_in_seconds, "Show times in seconds")
+			.on_hover_text("Times are still stored in frames, converted with the scene's FPS");
+
+		ui.heading("Defaults");
+		egui::Grid::new("DefaultsGrid").show(ui, |ui| {
+			ui.label("Layer name");
+			ui.text_edit_singleline(&mut self.defaults.layer_name);
+			ui.end_row();
+
+			ui.label("Layer duration")
+				.on_hover_text("0 uses the length of the parent layer");
+			crate::app::num_edit(ui, &mut self.defaults.layer_duration, 2);
+			ui.end_row();
+
+			ui.label("Sprite size");
+			ui.horizontal(|ui| {
+				crate::app::num_edit(ui, &mut self.defaults.sprite_size[0], 0);
+				crate::app::num_edit(ui, &mut self.defaults.sprite_size[1], 0);
+			});
+			ui.end_row();
+
+			ui.label("Texture size");
+			ui.horizontal(|ui| {
+				for size in &mut self.defaults.texture_size {
+					crate::app::num_edit(ui, size, 0);
+					*size = (*size).clamp(1, Defaults::MAX_TEXTURE_SIZE);
+				}
+			});
+			ui.end_row();
+
+			ui.label("Atlas padding")
+				.on_hover_text("Gap between images packed into one texture");
+			crate::app::num_edit(ui, &mut self.defaults.atlas_padding, 0);
+			ui.end_row();
+		});
+
+		ui.heading("Shortcuts");
+		egui::Grid::new("ShortcutsGrid")
+			.striped(true)
+			.show(ui, |ui| {
+				for action in Action::ALL {
+					ui.label(action.name());
+
+					let text = if self.recording == Some(action) {
+						String::from("Press a key...")
+					} else {
+						ui.ctx().format_shortcut(&self.shortcut(action))
+					};
+					if ui
+						.button(text)
+						.on_hover_text("Click then press the new shortcut, Escape cancels")
+						.clicked()
+					{
+						self.recording = Some(action);
+					}
+
+					if ui
+						.add_enabled(
+							self.shortcuts.contains_key(&action),
+							egui::Button::new("Reset"),
+						)
+						.clicked()
+					{
+						self.shortcuts.remove(&action);
+					}
+					ui.end_row();
+				}
+			});
+	}
+}
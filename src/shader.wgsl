@@ -24,6 +24,12 @@ struct SpriteInfo {
 	tex_coords_br: vec2<f32>,
 	color: vec4<f32>,
 	is_ycbcr: u32,
+	brightness: f32,
+	contrast: f32,
+	gamma: f32,
+	is_placeholder: u32,
+	is_hdr: u32,
+	exposure: f32,
 };
 
 @group(1) @binding(0)
@@ -54,15 +60,51 @@ var Texture: texture_2d<f32>;
 @group(0) @binding(1)
 var Sampler: sampler;
 
-@fragment
-fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-	if spr.is_ycbcr == 1 {
+// Must match ColorAdjustment::apply
+fn adjust(rgb: vec3<f32>) -> vec3<f32> {
+	if spr.brightness == 0.0 && spr.contrast == 1.0 && spr.gamma == 1.0 {
+		return rgb;
+	}
+	var adjusted = (rgb - 0.5) * spr.contrast + 0.5 + spr.brightness;
+	return min(pow(max(adjusted, vec3(0.0)), vec3(1.0 / spr.gamma)), vec3(1.0));
+}
+
+// Reinhard, then encoded for display as BC6H stores linear values
+fn tone_map(rgb: vec3<f32>) -> vec3<f32> {
+	var exposed = max(rgb, vec3(0.0)) * exp2(spr.exposure);
+	return pow(exposed / (exposed + 1.0), vec3(1.0 / 2.2));
+}
+
+const STRIPE_WIDTH = 8.0;
+const STRIPE_SHADE = 0.6;
+
+fn shade(in: VertexOutput) -> vec4<f32> {
+	if spr.is_placeholder == 1 {
+		// Diagonal stripes in screen space so placeholders never pass for a solid sprite
+		var stripe = fract((in.position.x + in.position.y) / (STRIPE_WIDTH * 2.0)) < 0.5;
+		var shade = select(STRIPE_SHADE, 1.0, stripe);
+		return vec4(spr.color.rgb * shade, spr.color.a);
+	} else if spr.is_ycbcr == 1 {
 		var ya = textureSampleLevel(Texture, Sampler, in.tex_coords, 0.0).xy;
 		var cbcr = textureSampleLevel(Texture, Sampler, in.tex_coords, 1.0).xy * CBCR_MULT - CBCR_SUB;
 		var rgb = vec3(ya.x, cbcr) * YCbCrRgbMatrix;
-		return vec4(rgb, ya.y) * spr.color;
+		return vec4(adjust(rgb), ya.y) * spr.color;
 	} else {
 		var rgba = textureSample(Texture, Sampler, in.tex_coords);
-		return rgba * spr.color;
+		if spr.is_hdr == 1 {
+			rgba = vec4(tone_map(rgba.rgb), rgba.a);
+		}
+		return vec4(adjust(rgba.rgb), rgba.a) * spr.color;
 	}
 }
+
+@fragment
+fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
+	return shade(in);
+}
+
+@fragment
+fn fs_premultiplied(in: VertexOutput) -> @location(0) vec4<f32> {
+	var color = shade(in);
+	return vec4(color.rgb * color.a, color.a);
+}
@@ -5,8 +5,9 @@ use eframe::egui;
 use eframe::egui::Widget;
 use eframe::egui_wgpu;
 use eframe::egui_wgpu::wgpu;
+use egui_material_icons::icons::*;
 use image::{EncodableLayout, GenericImage};
-use kkdlib::spr;
+use kkdlib::{spr, txp};
 use regex::Regex;
 use std::rc::Rc;
 use std::sync::*;
@@ -21,28 +22,202 @@ pub struct SpriteSetNode {
 	pub textures_node: TextureSetNode,
 	pub texture_names: Rc<Mutex<Vec<String>>>,
 	pub db_set: Option<Rc<Mutex<SprDbSetNode>>>,
+	pub unsaved: bool,
+	pub undos: Vec<SprUndo>,
+	pub redos: Vec<SprUndo>,
+	// The selected sprite or texture before the edits in progress, and the last change while they settle
+	pub edit: Option<(SprUndo, Option<(f64, SprUndo)>)>,
+	// Pixels of the selected node's texture, kept so replacing them can be undone
+	pub pixels: Option<(Rc<Mutex<TextureNode>>, txp::Texture)>,
 }
 
+// Additions, removals and reordering of textures and sprites, or edits to a single one
+#[derive(Clone)]
+pub enum SprUndo {
+	Lists(ListSnapshot),
+	Sprite(Rc<Mutex<SpriteInfoNode>>, SpriteState),
+	Texture(Rc<Mutex<TextureNode>>, TextureState),
+}
+
+#[derive(Clone)]
+pub struct SpriteState {
+	name: String,
+	info: spr::Info,
+	texture: Rc<Mutex<TextureNode>>,
+	db_entry: Option<(u32, String)>,
+}
+
+#[derive(Clone)]
+pub struct TextureState {
+	name: String,
+	flip: bool,
+	db_entry: Option<(u32, String)>,
+	// Only kept when the pixels were replaced, too large to keep for every edit
+	pixels: Option<txp::Texture>,
+}
+
+fn db_entry_state(entry: &Option<Rc<Mutex<SprDbEntryNode>>>) -> Option<(u32, String)> {
+	let entry = entry.as_ref()?.try_lock().unwrap();
+	Some((entry.id, entry.name.clone()))
+}
+
+fn restore_db_entry(entry: &Option<Rc<Mutex<SprDbEntryNode>>>, state: Option<(u32, String)>) {
+	if let Some(entry) = entry
+		&& let Some((id, name)) = state
+	{
+		let mut entry = entry.try_lock().unwrap();
+		entry.id = id;
+		entry.name = name;
+	}
+}
+
+impl SprUndo {
+	fn sprite(sprite: &Rc<Mutex<SpriteInfoNode>>) -> Self {
+		let lock = sprite.try_lock().unwrap();
+		SprUndo::Sprite(
+			sprite.clone(),
+			SpriteState {
+				name: lock.name.clone(),
+				info: lock.info.clone(),
+				texture: lock.texture.clone(),
+				db_entry: db_entry_state(&lock.db_entry),
+			},
+		)
+	}
+
+	fn texture(texture: &Rc<Mutex<TextureNode>>, pixels: bool) -> Self {
+		let lock = texture.try_lock().unwrap();
+		SprUndo::Texture(
+			texture.clone(),
+			TextureState {
+				name: lock.name.clone(),
+				flip: lock.flip,
+				db_entry: db_entry_state(&lock.db_entry),
+				pixels: pixels.then(|| lock.texture.clone()),
+			},
+		)
+	}
+
+	fn same_node(&self, other: &Self) -> bool {
+		match (self, other) {
+			(SprUndo::Sprite(a, _), SprUndo::Sprite(b, _)) => Rc::ptr_eq(a, b),
+			(SprUndo::Texture(a, _), SprUndo::Texture(b, _)) => Rc::ptr_eq(a, b),
+			_ => false,
+		}
+	}
+
+	// Pixels aren't compared, their replacement is recorded on its own
+	fn same_state(&self, other: &Self) -> bool {
+		match (self, other) {
+			(SprUndo::Sprite(_, a), SprUndo::Sprite(_, b)) => {
+				a.name == b.name
+					&& Rc::ptr_eq(&a.texture, &b.texture)
+					&& a.db_entry == b.db_entry
+					&& a.info.px() == b.info.px()
+					&& a.info.py() == b.info.py()
+					&& a.info.width() == b.info.width()
+					&& a.info.height() == b.info.height()
+					&& a.info.resolution_mode() as u32 == b.info.resolution_mode() as u32
+			}
+			(SprUndo::Texture(_, a), SprUndo::Texture(_, b)) => {
+				a.name == b.name && a.flip == b.flip && a.db_entry == b.db_entry
+			}
+			_ => false,
+		}
+	}
+}
+
+// The texture and sprite lists at one point, the nodes themselves are shared with the live lists
+#[derive(Clone)]
+pub struct ListSnapshot {
+	textures: Vec<Rc<Mutex<TextureNode>>>,
+	sprites: Vec<Rc<Mutex<SpriteInfoNode>>>,
+}
+
+impl ListSnapshot {
+	fn same_as(&self, other: &Self) -> bool {
+		self.textures.len() == other.textures.len()
+			&& self.sprites.len() == other.sprites.len()
+			&& self
+				.textures
+				.iter()
+				.zip(&other.textures)
+				.all(|(a, b)| Rc::ptr_eq(a, b))
+			&& self
+				.sprites
+				.iter()
+				.zip(&other.sprites)
+				.all(|(a, b)| Rc::ptr_eq(a, b))
+	}
+}
+
+const UNDO_LIMIT: usize = 100;
+
 impl TreeNode for SpriteSetNode {
 	fn label(&self) -> &str {
 		&self.name
 	}
 
+	fn label_sameline(&mut self, ui: &mut egui::Ui) {
+		crate::app::unsaved_marker(ui, self.unsaved);
+		if self.textures_node.children.is_empty() {
+			ui.label(egui::RichText::new(ICON_WARNING).color(ui.visuals().warn_fg_color))
+				.on_hover_text("Set has no textures, none of its sprites can be shown");
+		}
+	}
+
 	fn has_children(&self) -> bool {
 		true
 	}
 
 	fn display_children(&mut self, f: &mut dyn FnMut(&mut dyn TreeNode)) {
+		let before = self.list_snapshot();
+
 		f(&mut self.sprites_node);
 		for sprite in self.sprites_node.children.try_lock().unwrap().iter_mut() {
 			let mut sprite = sprite.try_lock().unwrap();
 			if let Some(texid) = sprite.want_new_texture {
-				sprite.texture = self.textures_node.children[texid as usize].clone();
+				match self.textures_node.children.get(texid as usize) {
+					Some(texture) => {
+						sprite.texture = texture.clone();
+						sprite.texture_missing = false;
+					}
+					None => sprite.texture_missing = true,
+				}
 			}
 			sprite.want_new_texture = None;
 		}
 		f(&mut self.textures_node);
 
+		for texture in &self.textures_node.children {
+			let mut lock = texture.try_lock().unwrap();
+			if lock.want_sprite_filter {
+				lock.want_sprite_filter = false;
+				self.sprites_node.texture_filter = Some(texture.clone());
+			}
+		}
+		if let Some(texture) = &self.sprites_node.texture_filter
+			&& !self
+				.textures_node
+				.children
+				.iter()
+				.any(|child| Rc::ptr_eq(child, texture))
+		{
+			self.sprites_node.texture_filter = None;
+		}
+
+		// Other sprites follow their texture's new index, these would silently point at its neighbour
+		if self.textures_node.children_changed {
+			for sprite in self.sprites_node.children.try_lock().unwrap().iter() {
+				let mut sprite = sprite.try_lock().unwrap();
+				sprite.texture_missing = !self
+					.textures_node
+					.children
+					.iter()
+					.any(|texture| Rc::ptr_eq(texture, &sprite.texture));
+			}
+		}
+
 		self.texture_names.try_lock().unwrap().clone_from(
 			&self
 				.textures_node
@@ -51,6 +226,10 @@ impl TreeNode for SpriteSetNode {
 				.map(|child| child.try_lock().unwrap().name.clone())
 				.collect(),
 		);
+
+		if !before.same_as(&self.list_snapshot()) {
+			self.push_undo(SprUndo::Lists(before));
+		}
 	}
 
 	fn raw_data(&self) -> Vec<u8> {
@@ -153,6 +332,215 @@ impl TreeNode for SpriteSetNode {
 }
 
 impl SpriteSetNode {
+	fn list_snapshot(&self) -> ListSnapshot {
+		ListSnapshot {
+			textures: self.textures_node.children.clone(),
+			sprites: self.sprites_node.children.try_lock().unwrap().clone(),
+		}
+	}
+
+	// Nodes removed since the snapshot still have their deletion flag set
+	fn restore_lists(&mut self, snapshot: ListSnapshot) {
+		for (i, texture) in snapshot.textures.iter().enumerate() {
+			let mut texture = texture.try_lock().unwrap();
+			texture.want_deletion = false;
+			texture.index = i as u32;
+		}
+		for sprite in &snapshot.sprites {
+			sprite.try_lock().unwrap().want_deletion = false;
+		}
+
+		self.textures_node.children = snapshot.textures;
+		*self.sprites_node.children.try_lock().unwrap() = snapshot.sprites;
+		self.textures_node.children_changed = true;
+	}
+
+	fn push_undo(&mut self, undo: SprUndo) {
+		self.undos.push(undo);
+		if self.undos.len() > UNDO_LIMIT {
+			self.undos.remove(0);
+		}
+		self.redos.clear();
+	}
+
+	fn settle_edit(&mut self) {
+		if let Some((original, Some(_))) = self.edit.take() {
+			self.push_undo(original);
+		}
+	}
+
+	// The same as the given state but as things are now
+	fn current_state(&self, undo: &SprUndo) -> SprUndo {
+		match undo {
+			SprUndo::Lists(_) => SprUndo::Lists(self.list_snapshot()),
+			SprUndo::Sprite(sprite, _) => SprUndo::sprite(sprite),
+			SprUndo::Texture(texture, state) => SprUndo::texture(texture, state.pixels.is_some()),
+		}
+	}
+
+	fn restore(&mut self, undo: SprUndo) {
+		match undo {
+			SprUndo::Lists(snapshot) => self.restore_lists(snapshot),
+			SprUndo::Sprite(sprite, state) => {
+				let mut sprite = sprite.try_lock().unwrap();
+				sprite.name = state.name;
+				sprite.info = state.info;
+				sprite.texture_missing = !self
+					.textures_node
+					.children
+					.iter()
+					.any(|texture| Rc::ptr_eq(texture, &state.texture));
+				sprite.texture = state.texture;
+				restore_db_entry(&sprite.db_entry, state.db_entry);
+			}
+			SprUndo::Texture(texture, state) => {
+				let mut texture = texture.try_lock().unwrap();
+				texture.name = state.name;
+				texture.flip = state.flip;
+				restore_db_entry(&texture.db_entry, state.db_entry);
+				if let Some(pixels) = state.pixels {
+					texture.texture = pixels;
+					texture.texture_updated = true;
+				}
+			}
+		}
+	}
+
+	pub fn has_undo(&self) -> bool {
+		!self.undos.is_empty() || matches!(self.edit, Some((_, Some(_))))
+	}
+
+	pub fn has_redo(&self) -> bool {
+		!self.redos.is_empty() && !matches!(self.edit, Some((_, Some(_))))
+	}
+
+	// Whether the lists changed, paths into them may point elsewhere afterwards
+	pub fn undo(&mut self) -> bool {
+		self.settle_edit();
+		let Some(undo) = self.undos.pop() else {
+			return false;
+		};
+		let lists = matches!(undo, SprUndo::Lists(_));
+		self.redos.push(self.current_state(&undo));
+		self.restore(undo);
+		self.pixels = None;
+		lists
+	}
+
+	pub fn redo(&mut self) -> bool {
+		let Some(redo) = self.redos.pop() else {
+			return false;
+		};
+		let lists = matches!(redo, SprUndo::Lists(_));
+		self.undos.push(self.current_state(&redo));
+		self.restore(redo);
+		self.edit = None;
+		self.pixels = None;
+		lists
+	}
+
+	// Called every frame before textures are uploaded, edits count as one once nothing changed for a second
+	pub fn feed_state(&mut self, current_time: f64, selected: &[usize]) {
+		let target = match selected {
+			[1, 0, i] => self
+				.sprites_node
+				.children
+				.try_lock()
+				.unwrap()
+				.get(*i)
+				.map(SprUndo::sprite),
+			[1, 1, i] => self
+				.textures_node
+				.children
+				.get(*i)
+				.map(|texture| SprUndo::texture(texture, false)),
+			_ => None,
+		};
+
+		// A sprite's pixels are replaced on its texture
+		let texture = match &target {
+			Some(SprUndo::Sprite(_, state)) => Some(state.texture.clone()),
+			Some(SprUndo::Texture(texture, _)) => Some(texture.clone()),
+			_ => None,
+		};
+		if let Some(texture) = texture {
+			let updated = texture.try_lock().unwrap().texture_updated;
+			match self
+				.pixels
+				.take()
+				.filter(|(kept, _)| Rc::ptr_eq(kept, &texture))
+			{
+				Some((_, pixels)) if updated => {
+					self.settle_edit();
+					let mut undo = SprUndo::texture(&texture, false);
+					if let SprUndo::Texture(_, state) = &mut undo {
+						state.pixels = Some(pixels);
+					}
+					self.push_undo(undo);
+					let pixels = texture.try_lock().unwrap().texture.clone();
+					self.pixels = Some((texture, pixels));
+				}
+				Some(kept) => self.pixels = Some(kept),
+				None => {
+					let pixels = texture.try_lock().unwrap().texture.clone();
+					self.pixels = Some((texture, pixels));
+				}
+			}
+		} else {
+			self.pixels = None;
+		}
+
+		let Some(target) = target else {
+			self.settle_edit();
+			return;
+		};
+		let settled = match &mut self.edit {
+			Some((original, flux)) if original.same_node(&target) => match flux {
+				Some((time, last)) if !last.same_state(&target) => {
+					*time = current_time;
+					*last = target.clone();
+					false
+				}
+				Some((time, _)) => current_time >= *time + 1.0,
+				None => {
+					if !original.same_state(&target) {
+						*flux = Some((current_time, target.clone()));
+					}
+					false
+				}
+			},
+			_ => true,
+		};
+		if settled {
+			self.settle_edit();
+			self.edit = Some((target, None));
+		}
+	}
+
+	pub fn convert(&mut self, modern: bool, is_x: bool, frame: &eframe::Frame) {
+		self.modern = modern;
+		self.big_endian = false;
+		self.is_x = is_x;
+		self.textures_node.modern = modern;
+		self.textures_node.big_endian = false;
+
+		if modern {
+			return;
+		}
+
+		for texture in &self.textures_node.children {
+			let mut texture = texture.try_lock().unwrap();
+			if !texture.is_modern_only() {
+				continue;
+			}
+
+			if let Some(tex) = convert_texture(&texture.texture, txp::Format::BC3 as u32, frame) {
+				texture.texture = tex;
+				texture.texture_updated = true;
+			}
+		}
+	}
+
 	pub fn name_pattern() -> Regex {
 		Regex::new(r"(^spr_.*\.bin)|(\.spr)$").unwrap()
 	}
@@ -177,6 +565,11 @@ impl SpriteSetNode {
 			textures_node,
 			texture_names,
 			db_set: None,
+			unsaved: false,
+			undos: Vec::new(),
+			redos: Vec::new(),
+			edit: None,
+			pixels: None,
 		}
 	}
 
@@ -520,6 +913,12 @@ impl SpriteSetNode {
 pub struct SpriteInfosNode {
 	pub children: Rc<Mutex<Vec<Rc<Mutex<SpriteInfoNode>>>>>,
 	pub texture_names: Rc<Mutex<Vec<String>>>,
+	// Only sprites on this texture are listed
+	pub texture_filter: Option<Rc<Mutex<TextureNode>>>,
+	pub folder_result: Option<mpsc::Receiver<Vec<(std::path::PathBuf, Vec<u8>)>>>,
+	pub export_result: Option<mpsc::Receiver<Vec<String>>>,
+	// What happened to each file of the last folder replaced or exported, shown until dismissed
+	pub report: Vec<String>,
 }
 
 impl TreeNode for SpriteInfosNode {
@@ -535,24 +934,84 @@ impl TreeNode for SpriteInfosNode {
 		true
 	}
 
+	fn label_sameline(&mut self, ui: &mut egui::Ui) {
+		let Some(texture) = &self.texture_filter else {
+			return;
+		};
+		let name = texture.try_lock().unwrap().name.clone();
+		if ui
+			.button(ICON_FILTER_ALT_OFF)
+			.on_hover_text(format!(
+				"Only sprites on {name} are listed, click to list all"
+			))
+			.clicked()
+		{
+			self.texture_filter = None;
+		}
+	}
+
 	fn display_children(&mut self, f: &mut dyn FnMut(&mut dyn TreeNode)) {
 		self.children.try_lock().unwrap().retain_mut(|spr| {
 			let mut spr = spr.try_lock().unwrap();
 			let texid = spr.texture.try_lock().unwrap().index;
 			spr.info.set_texid(texid);
+			spr.hidden = self
+				.texture_filter
+				.as_ref()
+				.is_some_and(|texture| !Rc::ptr_eq(texture, &spr.texture));
 			f(&mut *spr);
 			!spr.want_deletion
 		});
 	}
 
+	fn display_opts(&mut self, ui: &mut egui::Ui, frame: &mut eframe::Frame) {
+		if let Some(rx) = &mut self.folder_result
+			&& let Ok(files) = rx.try_recv()
+		{
+			self.report = self.replace_from_folder(files, frame);
+			self.folder_result = None;
+		}
+
+		if let Some(rx) = &mut self.export_result
+			&& let Ok(report) = rx.try_recv()
+		{
+			self.report = report;
+			self.export_result = None;
+		}
+
+		if !self.report.is_empty() {
+			let modal =
+				egui::Modal::new(egui::Id::new("SpriteReplaceReport")).show(ui.ctx(), |ui| {
+					ui.heading(&self.report[0]);
+					egui::ScrollArea::vertical()
+						.max_height(300.0)
+						.show(ui, |ui| {
+							for line in &self.report[1..] {
+								ui.label(line);
+							}
+						});
+					ui.vertical_centered(|ui| {
+						if ui.button("Ok").clicked() {
+							ui.close();
+						}
+					});
+				});
+
+			if modal.should_close() {
+				self.report.clear();
+			}
+		}
+	}
+
 	fn display_ctx_menu(&mut self, ui: &mut egui::Ui) {
 		if ui.button("Add").clicked() {
+			let defaults = crate::settings::Defaults::get(ui.ctx());
 			let mut info = spr::Info::new();
 			info.set_texid(0);
 			info.set_px(2.0);
 			info.set_py(2.0);
-			info.set_width(2.0);
-			info.set_height(2.0);
+			info.set_width(defaults.sprite_size[0]);
+			info.set_height(defaults.sprite_size[1]);
 			info.set_resolution_mode(spr::ResolutionMode::FHD);
 			let len = self.children.try_lock().unwrap().len();
 
@@ -570,21 +1029,287 @@ impl TreeNode for SpriteInfosNode {
 						texture_updated: false,
 						db_entry: None,
 						file_picker_result: None,
+						mip_folder_result: None,
 						error: None,
+						adjustment: ColorAdjustment::default(),
+						exposure: 0.0,
+						want_sprite_filter: false,
 						want_deletion: false,
 					})),
 					texture_names: self.texture_names.clone(),
 					want_new_texture: Some(0),
+					texture_missing: false,
+					hidden: false,
 					db_entry: None,
 					file_picker_result: None,
 					error: None,
 					want_deletion: false,
 				})));
 		}
+
+		if ui
+			.button("Replace all from folder")
+			.on_hover_text("Replace each sprite with the image in a folder named after it")
+			.clicked()
+		{
+			let (tx, rx) = mpsc::channel();
+			std::thread::spawn(move || {
+				tokio::runtime::Builder::new_current_thread()
+					.enable_io()
+					.build()
+					.unwrap()
+					.block_on(async {
+						let Some(folder) = rfd::AsyncFileDialog::new().pick_folder().await else {
+							return;
+						};
+
+						let files = std::fs::read_dir(folder.path())
+							.into_iter()
+							.flatten()
+							.flatten()
+							.map(|entry| entry.path())
+							.filter(|path| image::ImageFormat::from_path(path).is_ok())
+							.filter_map(|path| Some((path.clone(), std::fs::read(&path).ok()?)))
+							.collect();
+						tx.send(files).unwrap();
+					});
+			});
+
+			self.folder_result = Some(rx);
+		}
+
+		if ui
+			.button("Export all")
+			.on_hover_text("Save every sprite to a folder as a PNG named after it")
+			.clicked()
+		{
+			self.export_all();
+		}
+
+		ui.menu_button("Set resolution mode for all", |ui| {
+			for i in 0..=0x20 {
+				let mode: spr::ResolutionMode = unsafe { std::mem::transmute(i) };
+				if ui.button(format!("{:?}", mode)).clicked() {
+					for sprite in self.children.try_lock().unwrap().iter() {
+						sprite.try_lock().unwrap().info.set_resolution_mode(mode);
+					}
+					ui.close();
+				}
+			}
+		});
+
+		ui.menu_button("Export contact sheet", |ui| {
+			let id = egui::Id::new("ContactSheet");
+			let (mut columns, mut size) = ui
+				.data(|data| data.get_temp::<(u32, u32)>(id))
+				.unwrap_or((8, 128));
+
+			egui::Grid::new("ContactSheetGrid").show(ui, |ui| {
+				ui.label("Columns");
+				crate::app::num_edit(ui, &mut columns, 0);
+				ui.end_row();
+
+				ui.label("Thumbnail size");
+				crate::app::num_edit(ui, &mut size, 0);
+				ui.end_row();
+			});
+
+			ui.data_mut(|data| data.insert_temp(id, (columns.max(1), size.max(16))));
+
+			if ui.button("Export").clicked() {
+				let sprites = self.decoded_sprites();
+				if let Err(e) = contact_sheet_size(sprites.len(), columns.max(1), size.max(16)) {
+					self.report = vec![String::from("Contact sheet not exported"), e];
+					ui.close();
+					return;
+				}
+				std::thread::spawn(move || {
+					tokio::runtime::Builder::new_current_thread()
+						.enable_io()
+						.build()
+						.unwrap()
+						.block_on(async {
+							let Some(file) = rfd::AsyncFileDialog::new()
+								.add_filter("PNG", &["png"])
+								.set_file_name("contact_sheet.png")
+								.save_file()
+								.await
+							else {
+								return;
+							};
+
+							let image = contact_sheet(sprites, columns.max(1), size.max(16));
+							let mut buf = std::io::Cursor::new(Vec::new());
+							if let Err(_) = image::DynamicImage::ImageRgba8(image)
+								.write_to(&mut buf, image::ImageFormat::Png)
+							{
+								return;
+							};
+
+							file.write(&buf.into_inner()).await.unwrap();
+						});
+				});
+				ui.close();
+			}
+		});
 	}
 }
 
 impl SpriteInfosNode {
+	// File names each sprite is exported under, without the extension
+	// Names are sanitized, and ones Windows would treat as the same get a number appended
+	fn export_file_stems(&self) -> Vec<String> {
+		let mut used = std::collections::HashSet::new();
+		self.children
+			.try_lock()
+			.unwrap()
+			.iter()
+			.map(|sprite| {
+				let stem = sanitize_file_name(&sprite.try_lock().unwrap().name);
+				let mut file = stem.clone();
+				let mut n = 2;
+				while !used.insert(file.to_lowercase()) {
+					file = format!("{stem} ({n})");
+					n += 1;
+				}
+				file
+			})
+			.collect()
+	}
+
+	// Files are matched to sprites by the names export_all gives them, so an exported folder reads back
+	fn replace_from_folder(
+		&self,
+		mut files: Vec<(std::path::PathBuf, Vec<u8>)>,
+		frame: &mut eframe::Frame,
+	) -> Vec<String> {
+		files.sort_by(|(a, _), (b, _)| a.cmp(b));
+		let stems = self
+			.export_file_stems()
+			.into_iter()
+			.enumerate()
+			.map(|(i, stem)| (stem.to_lowercase(), i))
+			.collect::<std::collections::HashMap<_, _>>();
+		let children = self.children.try_lock().unwrap();
+		let mut replaced = 0;
+		let mut report = Vec::new();
+		let mut matched = std::collections::HashSet::new();
+		for (path, data) in &files {
+			let file = path.file_name().unwrap_or_default().to_string_lossy();
+			let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+			let Some(&i) = stems.get(&stem.to_lowercase()) else {
+				report.push(format!("{file}: no sprite named {stem}"));
+				continue;
+			};
+
+			let mut sprite = children[i].try_lock().unwrap();
+			// The same name with another extension would silently undo the first replacement
+			if !matched.insert(i) {
+				report.push(format!(
+					"{file}: {} was already matched by another file",
+					sprite.name
+				));
+				continue;
+			}
+
+			sprite.pick_file(path, data, frame);
+			match sprite.error.take() {
+				Some(e) => report.push(format!("{file}: {e}")),
+				None => {
+					replaced += 1;
+					report.push(format!("{file}: replaced {}", sprite.name));
+				}
+			}
+		}
+
+		let unmatched = children.len() - matched.len();
+		if unmatched > 0 {
+			report.push(format!("{unmatched} sprites had no file and were kept"));
+		}
+
+		report.insert(
+			0,
+			format!("Replaced {replaced} sprites from {} files", files.len()),
+		);
+		report
+	}
+
+	// Every sprite with its texture decoded, each texture only once however many sprites it has
+	// Decoding needs the texture so it happens here, cropping the sprites out can be left to a thread
+	fn decoded_sprites(&self) -> Vec<(String, Result<(Arc<image::RgbaImage>, [u32; 4]), String>)> {
+		let mut textures = std::collections::HashMap::new();
+		self.children
+			.try_lock()
+			.unwrap()
+			.iter()
+			.map(|sprite| {
+				let sprite = sprite.try_lock().unwrap();
+				let texture = textures
+					.entry(Rc::as_ptr(&sprite.texture))
+					.or_insert_with(|| {
+						texture_image(&sprite.texture.try_lock().unwrap()).map(Arc::new)
+					})
+					.clone();
+				(
+					sprite.name.clone(),
+					texture.map(|texture| (texture, sprite.rect())),
+				)
+			})
+			.collect()
+	}
+
+	// Textures are decoded here, cropping, encoding and writing is left to the thread once a folder is picked
+	fn export_all(&mut self) {
+		let stems = self.export_file_stems();
+		let sprites = self.decoded_sprites();
+		let total = sprites.len();
+
+		let (tx, rx) = mpsc::channel();
+		std::thread::spawn(move || {
+			tokio::runtime::Builder::new_current_thread()
+				.enable_io()
+				.build()
+				.unwrap()
+				.block_on(async {
+					let Some(folder) = rfd::AsyncFileDialog::new().pick_folder().await else {
+						tx.send(Vec::new()).unwrap();
+						return;
+					};
+
+					let mut failed = Vec::new();
+					let mut exported = 0;
+					for ((name, sprite), stem) in sprites.into_iter().zip(stems) {
+						let (texture, rect) = match sprite {
+							Ok(sprite) => sprite,
+							Err(e) => {
+								failed.push(format!("{name}: {e}"));
+								continue;
+							}
+						};
+						let image = crop_sprite(&texture, rect);
+						let file = format!("{stem}.png");
+
+						let mut buf = std::io::Cursor::new(Vec::new());
+						if let Err(e) = image::DynamicImage::ImageRgba8(image)
+							.write_to(&mut buf, image::ImageFormat::Png)
+						{
+							failed.push(format!("{name}: {e}"));
+							continue;
+						}
+						match std::fs::write(folder.path().join(&file), buf.into_inner()) {
+							Ok(()) => exported += 1,
+							Err(e) => failed.push(format!("{name}: could not write {file}: {e}")),
+						}
+					}
+
+					failed.insert(0, format!("Exported {exported} of {total} sprites"));
+					tx.send(failed).unwrap();
+				});
+		});
+
+		self.export_result = Some(rx);
+	}
+
 	fn new(
 		set: &spr::Set,
 		textures_node: &TextureSetNode,
@@ -601,6 +1326,8 @@ impl SpriteInfosNode {
 							texture: textures_node.children[info.texid() as usize].clone(),
 							texture_names: texture_names.clone(),
 							want_new_texture: None,
+							texture_missing: false,
+							hidden: false,
 							db_entry: None,
 							file_picker_result: None,
 							error: None,
@@ -610,16 +1337,166 @@ impl SpriteInfosNode {
 					.collect(),
 			)),
 			texture_names,
+			texture_filter: None,
+			folder_result: None,
+			export_result: None,
+			report: Vec::new(),
 		}
 	}
 }
 
+const CONTACT_SHEET_PADDING: u32 = 8;
+const CONTACT_SHEET_LABEL_SIZE: f32 = 14.0;
+// Larger images can't be created on most GPUs or opened by most viewers
+const CONTACT_SHEET_MAX_SIZE: u64 = 16384;
+// 256 MiB of pixels, built in memory before it is encoded
+const CONTACT_SHEET_MAX_PIXELS: u64 = 8192 * 8192;
+
+// Width and height of the contact sheet of count sprites, if it isn't too large to build
+fn contact_sheet_size(count: usize, columns: u32, size: u32) -> Result<(u32, u32), String> {
+	let rows = (count as u64).div_ceil(columns as u64).max(1);
+	let padding = CONTACT_SHEET_PADDING as u64;
+	let width = columns as u64 * (size as u64 + padding) + padding;
+	let height = rows * (size as u64 + CONTACT_SHEET_LABEL_SIZE as u64 + padding * 2) + padding;
+	if width > CONTACT_SHEET_MAX_SIZE || height > CONTACT_SHEET_MAX_SIZE {
+		return Err(format!(
+			"The contact sheet would be {width}x{height}, the largest possible is {CONTACT_SHEET_MAX_SIZE}x{CONTACT_SHEET_MAX_SIZE}. Use a smaller thumbnail size or another column count"
+		));
+	}
+	if width * height > CONTACT_SHEET_MAX_PIXELS {
+		return Err(format!(
+			"The contact sheet would be {width}x{height}, more than {CONTACT_SHEET_MAX_PIXELS} pixels. Use a smaller thumbnail size"
+		));
+	}
+	Ok((width as u32, height as u32))
+}
+
+// Grid of every sprite scaled to fit `size` with its name underneath
+// Takes the sprites from decoded_sprites so it can run off the UI thread,
+// their count and the layout must have passed contact_sheet_size
+fn contact_sheet(
+	sprites: Vec<(String, Result<(Arc<image::RgbaImage>, [u32; 4]), String>)>,
+	columns: u32,
+	size: u32,
+) -> image::RgbaImage {
+	let fonts = egui::FontDefinitions::default();
+	let font = fonts.families[&egui::FontFamily::Proportional]
+		.first()
+		.and_then(|name| fonts.font_data.get(name))
+		.and_then(|data| ab_glyph::FontRef::try_from_slice(&data.font).ok());
+
+	let (width, height) = contact_sheet_size(sprites.len(), columns, size).unwrap_or((1, 1));
+	let cell_w = size + CONTACT_SHEET_PADDING;
+	let cell_h = size + CONTACT_SHEET_LABEL_SIZE as u32 + CONTACT_SHEET_PADDING * 2;
+	let mut sheet = image::RgbaImage::from_pixel(width, height, image::Rgba([32, 32, 32, 255]));
+
+	for (i, (name, sprite)) in sprites.iter().enumerate() {
+		let x = CONTACT_SHEET_PADDING + (i as u32 % columns) * cell_w;
+		let y = CONTACT_SHEET_PADDING + (i as u32 / columns) * cell_h;
+
+		if let Ok((texture, rect)) = sprite {
+			let image = crop_sprite(texture, *rect);
+			if image.width() > 0 && image.height() > 0 {
+				let scale = (size as f32 / image.width().max(image.height()) as f32).min(1.0);
+				let width = ((image.width() as f32 * scale) as u32).max(1);
+				let height = ((image.height() as f32 * scale) as u32).max(1);
+				let thumb = image::imageops::resize(
+					&image,
+					width,
+					height,
+					image::imageops::FilterType::Triangle,
+				);
+				image::imageops::overlay(
+					&mut sheet,
+					&thumb,
+					(x + (size - width) / 2) as i64,
+					(y + (size - height) / 2) as i64,
+				);
+			}
+		}
+
+		if let Some(font) = &font {
+			draw_text(
+				&mut sheet,
+				font,
+				name,
+				x,
+				y + size + CONTACT_SHEET_PADDING / 2,
+				CONTACT_SHEET_LABEL_SIZE,
+				size,
+			);
+		}
+	}
+
+	sheet
+}
+
+// The texture's first mip flipped upright, which sprite rectangles are measured on
+fn texture_image(texture: &TextureNode) -> Result<image::RgbaImage, String> {
+	let Some(mip) = texture.texture.get_mipmap(0, 0) else {
+		return Err(String::from("Texture has no data"));
+	};
+
+	let rgba = if texture.texture.is_ycbcr() {
+		texture.texture.decode_ycbcr()
+	} else {
+		mip.rgba()
+	};
+
+	let Some(rgba) = rgba else {
+		return Err(String::from("Could not convert texture to RGBA"));
+	};
+
+	let Some(image) = image::RgbaImage::from_raw(mip.width() as u32, mip.height() as u32, rgba)
+	else {
+		return Err(String::from("Could not load image"));
+	};
+
+	Ok(image::imageops::flip_vertical(&image))
+}
+
+fn crop_sprite(texture: &image::RgbaImage, [x, y, width, height]: [u32; 4]) -> image::RgbaImage {
+	image::imageops::crop_imm(texture, x, y, width, height).to_image()
+}
+
+// Characters Windows doesn't allow in file names are replaced, as are trailing dots and spaces
+fn sanitize_file_name(name: &str) -> String {
+	let name = name
+		.chars()
+		.map(|c| match c {
+			'<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+			c if c.is_control() => '_',
+			c => c,
+		})
+		.collect::<String>();
+	let name = name.trim_end_matches(['.', ' ']);
+	if name.is_empty() {
+		String::from("sprite")
+	} else {
+		name.to_string()
+	}
+}
+
+// Copied from a sprite's context menu, kept in egui's temp data so any sprite can paste it
+const SPRITE_COORDINATES: &str = "SpriteCoordinates";
+
+#[derive(Clone, Copy)]
+struct SpriteCoordinates {
+	px: f32,
+	py: f32,
+	width: f32,
+	height: f32,
+	resolution_mode: spr::ResolutionMode,
+}
+
 pub struct SpriteInfoNode {
 	pub name: String,
 	pub info: spr::Info,
 	pub texture: Rc<Mutex<TextureNode>>,
 	pub texture_names: Rc<Mutex<Vec<String>>>,
 	pub want_new_texture: Option<u32>,
+	pub texture_missing: bool,
+	pub hidden: bool,
 	pub db_entry: Option<Rc<Mutex<SprDbEntryNode>>>,
 	pub file_picker_result: Option<mpsc::Receiver<Option<(std::path::PathBuf, Vec<u8>)>>>,
 	pub error: Option<String>,
@@ -627,6 +1504,74 @@ pub struct SpriteInfoNode {
 }
 
 impl SpriteInfoNode {
+	// Whether the sprite rectangle reaches outside of its texture
+	pub fn out_of_bounds(&self) -> bool {
+		let texture = self.texture.try_lock().unwrap();
+		let Some(mip) = texture.texture.get_mipmap(0, 0) else {
+			return false;
+		};
+
+		self.info.px() < 0.0
+			|| self.info.py() < 0.0
+			|| self.info.px() + self.info.width() > mip.width() as f32
+			|| self.info.py() + self.info.height() > mip.height() as f32
+	}
+
+	// The sprite cropped out of its texture
+	pub fn image(&self) -> Result<image::RgbaImage, String> {
+		let texture = texture_image(&self.texture.try_lock().unwrap())?;
+		Ok(crop_sprite(&texture, self.rect()))
+	}
+
+	// Position and size on the texture as decoded by texture_image
+	fn rect(&self) -> [u32; 4] {
+		[
+			self.info.px() as u32,
+			self.info.py() as u32,
+			self.info.width() as u32,
+			self.info.height() as u32,
+		]
+	}
+
+	fn export(&self, image: image::RgbaImage) {
+		let name = self.name.clone();
+		std::thread::spawn(move || {
+			tokio::runtime::Builder::new_current_thread()
+				.enable_io()
+				.build()
+				.unwrap()
+				.block_on(async {
+					let Some(file) = rfd::AsyncFileDialog::new()
+						.add_filter(
+							"Images (.avif, .bmp, .jpg, .png, .webp)",
+							&["avif", "bmp", "jpg", "png", "webp"],
+						)
+						.set_file_name(format!("{name}.png"))
+						.save_file()
+						.await
+					else {
+						return;
+					};
+
+					let path = std::path::PathBuf::from(file.file_name());
+					let extension = path.extension().unwrap_or_default();
+					let Some(format) = image::ImageFormat::from_extension(extension) else {
+						return;
+					};
+
+					let mut buf = std::io::Cursor::new(Vec::new());
+
+					if let Err(_) =
+						image::DynamicImage::ImageRgba8(image).write_to(&mut buf, format)
+					{
+						return;
+					};
+
+					file.write(&buf.into_inner()).await.unwrap();
+				});
+		});
+	}
+
 	fn pick_file(&mut self, path: &std::path::PathBuf, data: &[u8], frame: &mut eframe::Frame) {
 		let extension = path.extension().unwrap_or_default();
 		let Some(format) = image::ImageFormat::from_extension(extension) else {
@@ -635,7 +1580,10 @@ impl SpriteInfoNode {
 		};
 
 		let mut texture = self.texture.try_lock().unwrap();
-		let mip = texture.texture.get_mipmap(0, 0).unwrap();
+		let Some(mip) = texture.texture.get_mipmap(0, 0) else {
+			self.error = Some(String::from("Texture has no data"));
+			return;
+		};
 
 		let Ok(new_image) = image::load(std::io::Cursor::new(data), format) else {
 			self.error = Some(format!("Failed to parse {:?} as image", path));
@@ -645,8 +1593,12 @@ impl SpriteInfoNode {
 		if new_image.width() != self.info.width() as u32
 			|| new_image.height() != self.info.height() as u32
 		{
-			self.error = Some(String::from(
-				"New image did match dimensions of current sprite",
+			self.error = Some(format!(
+				"New image is {}x{}, the sprite is {}x{}",
+				new_image.width(),
+				new_image.height(),
+				self.info.width(),
+				self.info.height()
 			));
 			return;
 		}
@@ -767,76 +1719,99 @@ impl TreeNode for SpriteInfoNode {
 		&self.name
 	}
 
+	fn is_hidden(&self) -> bool {
+		self.hidden
+	}
+
+	fn label_sameline(&mut self, ui: &mut egui::Ui) {
+		if self.out_of_bounds() {
+			ui.label(egui::RichText::new(ICON_WARNING).color(ui.visuals().warn_fg_color))
+				.on_hover_text("Sprite exceeds the bounds of its texture");
+		}
+		if self.texture_missing {
+			ui.label(egui::RichText::new(ICON_WARNING).color(ui.visuals().error_fg_color))
+				.on_hover_text("Texture of this sprite was removed, pick a new one");
+		}
+	}
+
 	fn has_context_menu(&self) -> bool {
 		true
 	}
 
 	fn display_ctx_menu(&mut self, ui: &mut egui::Ui) {
 		if ui.button("Export").clicked() {
-			let texture = self.texture.try_lock().unwrap();
-			let mip = texture.texture.get_mipmap(0, 0).unwrap();
-
-			let rgba = if texture.texture.is_ycbcr() {
-				texture.texture.decode_ycbcr()
-			} else {
-				mip.rgba()
-			};
-
-			let Some(rgba) = rgba else {
-				self.error = Some(String::from("Could not convert texture to RGBA"));
+			match self.image() {
+				Ok(image) => self.export(image),
+				Err(e) => self.error = Some(e),
+			}
+		}
+		ui.menu_button("Export at resolution", |ui| {
+			let Some(mode_height) = resolution_height(self.info.resolution_mode()) else {
+				ui.label("Unknown resolution mode");
 				return;
 			};
 
-			let Some(image) =
-				image::RgbaImage::from_raw(mip.width() as u32, mip.height() as u32, rgba)
-			else {
-				self.error = Some(String::from("Could not load image"));
-				return;
+			for (name, height) in EXPORT_RESOLUTIONS {
+				let scale = height as f32 / mode_height as f32;
+				if ui
+					.button(name)
+					.on_hover_text(format!("Scaled by {scale:.2}"))
+					.clicked()
+				{
+					match self.image() {
+						Ok(image) => {
+							let width = (image.width() as f32 * scale).round().max(1.0) as u32;
+							let height = (image.height() as f32 * scale).round().max(1.0) as u32;
+							self.export(image::imageops::resize(
+								&image,
+								width,
+								height,
+								image::imageops::FilterType::Lanczos3,
+							));
+						}
+						Err(e) => self.error = Some(e),
+					}
+					ui.close();
+				}
+			}
+		});
+		ui.separator();
+		if ui.button("Copy coordinates").clicked() {
+			let coordinates = SpriteCoordinates {
+				px: self.info.px(),
+				py: self.info.py(),
+				width: self.info.width(),
+				height: self.info.height(),
+				resolution_mode: self.info.resolution_mode(),
 			};
-
-			let name = self.name.clone();
-			let crop_x = self.info.px() as u32;
-			let crop_y = self.info.py() as u32;
-			let crop_w = self.info.width() as u32;
-			let crop_h = self.info.height() as u32;
-			std::thread::spawn(move || {
-				tokio::runtime::Builder::new_current_thread()
-					.enable_io()
-					.build()
-					.unwrap()
-					.block_on(async {
-						let Some(file) = rfd::AsyncFileDialog::new()
-							.add_filter(
-								"Images (.avif, .bmp, .jpg, .png, .webp)",
-								&["avif", "bmp", "jpg", "png", "webp"],
-							)
-							.set_file_name(format!("{name}.png"))
-							.save_file()
-							.await
-						else {
-							return;
-						};
-
-						let path = std::path::PathBuf::from(file.file_name());
-						let extension = path.extension().unwrap_or_default();
-						let Some(format) = image::ImageFormat::from_extension(extension) else {
-							return;
-						};
-
-						let mut buf = std::io::Cursor::new(Vec::new());
-
-						if let Err(_) = image::DynamicImage::ImageRgba8(image)
-							.flipv()
-							.crop(crop_x, crop_y, crop_w, crop_h)
-							.write_to(&mut buf, format)
-						{
-							return;
-						};
-
-						file.write(&buf.into_inner()).await.unwrap();
-					});
-			});
+			ui.ctx()
+				.data_mut(|data| data.insert_temp(egui::Id::new(SPRITE_COORDINATES), coordinates));
+		}
+		let coordinates = ui
+			.ctx()
+			.data(|data| data.get_temp::<SpriteCoordinates>(egui::Id::new(SPRITE_COORDINATES)));
+		let paste = ui.add_enabled(
+			coordinates.is_some(),
+			egui::Button::new("Paste coordinates"),
+		);
+		if let Some(coordinates) = coordinates {
+			let paste = paste.on_hover_text(format!(
+				"X {} Y {}, {}x{}, {:?}",
+				coordinates.px,
+				coordinates.py,
+				coordinates.width,
+				coordinates.height,
+				coordinates.resolution_mode
+			));
+			if paste.clicked() {
+				self.info.set_px(coordinates.px);
+				self.info.set_py(coordinates.py);
+				self.info.set_width(coordinates.width);
+				self.info.set_height(coordinates.height);
+				self.info.set_resolution_mode(coordinates.resolution_mode);
+			}
 		}
+		ui.separator();
 		if ui.button("Replace").clicked() {
 			let (tx, rx) = mpsc::channel();
 			let name = self.name.clone();
@@ -1036,9 +2011,10 @@ impl TreeNode for SpriteInfoNode {
 
 	fn display_visual(
 		&mut self,
-		_ui: &mut egui::Ui,
+		ui: &mut egui::Ui,
 		rect: egui::Rect,
 	) -> Option<egui::epaint::PaintCallback> {
+		let out_of_bounds = self.out_of_bounds();
 		let texture = self.texture.try_lock().unwrap();
 
 		let w = rect.max.x - rect.min.x;
@@ -1073,7 +2049,33 @@ impl TreeNode for SpriteInfoNode {
 			}
 		};
 
-		let mip = texture.texture.get_mipmap(0, 0).unwrap();
+		if out_of_bounds {
+			let color = ui.visuals().error_fg_color;
+			ui.painter().rect_stroke(
+				rect,
+				0.0,
+				egui::Stroke::new(2.0, color),
+				egui::StrokeKind::Outside,
+			);
+			ui.painter().text(
+				rect.left_top() - egui::vec2(0.0, 4.0),
+				egui::Align2::LEFT_BOTTOM,
+				"Sprite exceeds the bounds of its texture",
+				egui::TextStyle::Body.resolve(ui.style()),
+				color,
+			);
+		}
+
+		let Some(mip) = texture.texture.get_mipmap(0, 0) else {
+			ui.painter().text(
+				rect.center(),
+				egui::Align2::CENTER_CENTER,
+				"Sprite has no texture",
+				egui::TextStyle::Body.resolve(ui.style()),
+				ui.visuals().warn_fg_color,
+			);
+			return None;
+		};
 		let x = self.info.px() / mip.width() as f32;
 		let y = (mip.height() as f32 - self.info.py() - self.info.height()) / mip.height() as f32;
 		let w = (self.info.px() + self.info.width()) / mip.width() as f32;
@@ -1083,6 +2085,8 @@ impl TreeNode for SpriteInfoNode {
 			rect,
 			WgpuSpriteCallback {
 				is_ycbcr: texture.texture.is_ycbcr(),
+				is_hdr: texture.is_hdr(),
+				exposure: texture.exposure,
 				sprite_coords: [x, y, w, h],
 				texture_index: texture.index,
 			},
@@ -1092,6 +2096,8 @@ impl TreeNode for SpriteInfoNode {
 
 struct WgpuSpriteCallback {
 	is_ycbcr: bool,
+	is_hdr: bool,
+	exposure: f32,
 	sprite_coords: [f32; 4],
 	texture_index: u32,
 }
@@ -1117,9 +2123,13 @@ impl egui_wgpu::CallbackTrait for WgpuSpriteCallback {
 			],
 			color: [1.0, 1.0, 1.0, 1.0],
 			is_ycbcr: if self.is_ycbcr { 1 } else { 0 },
-			_padding_0: 0,
-			_padding_1: 0,
-			_padding_2: 0,
+			brightness: 0.0,
+			contrast: 1.0,
+			gamma: 1.0,
+			is_placeholder: 0,
+			is_hdr: if self.is_hdr { 1 } else { 0 },
+			exposure: self.exposure,
+			_padding: 0,
 		};
 
 		queue.write_buffer(
@@ -1142,7 +2152,10 @@ impl egui_wgpu::CallbackTrait for WgpuSpriteCallback {
 		render_pass.set_pipeline(&resources.pipeline_normal);
 		render_pass.set_bind_group(
 			0,
-			&texture.fragment_bind_group[self.texture_index as usize].1,
+			texture
+				.fragment_bind_group
+				.get(self.texture_index as usize)
+				.map_or(&texture.empty_texture, |(_, bind_group)| bind_group),
 			&[],
 		);
 		render_pass.set_bind_group(1, &resources.uniform_buffers[0].1, &[]);
@@ -1150,3 +2163,152 @@ impl egui_wgpu::CallbackTrait for WgpuSpriteCallback {
 		render_pass.draw(0..6, 0..1);
 	}
 }
+
+fn draw_text(
+	image: &mut image::RgbaImage,
+	font: &ab_glyph::FontRef,
+	text: &str,
+	x: u32,
+	y: u32,
+	size: f32,
+	max_width: u32,
+) {
+	use ab_glyph::{Font, ScaleFont};
+
+	let scaled = font.as_scaled(size);
+	let mut caret = x as f32;
+	for c in text.chars() {
+		let mut glyph = scaled.scaled_glyph(c);
+		glyph.position = ab_glyph::point(caret, y as f32 + scaled.ascent());
+		caret += scaled.h_advance(glyph.id);
+		if caret > (x + max_width) as f32 {
+			break;
+		}
+
+		let Some(outlined) = font.outline_glyph(glyph) else {
+			continue;
+		};
+		let bounds = outlined.px_bounds();
+		outlined.draw(|gx, gy, coverage| {
+			let px = bounds.min.x as i32 + gx as i32;
+			let py = bounds.min.y as i32 + gy as i32;
+			if px < 0 || py < 0 || px >= image.width() as i32 || py >= image.height() as i32 {
+				return;
+			}
+
+			let pixel = image.get_pixel_mut(px as u32, py as u32);
+			for channel in &mut pixel.0[..3] {
+				*channel = (*channel as f32 + (255.0 - *channel as f32) * coverage) as u8;
+			}
+		});
+	}
+}
+
+// Screen heights of each spr::ResolutionMode in declaration order
+const RESOLUTION_HEIGHTS: [u32; 33] = [
+	240, 480, 600, 768, 1024, 1050, 1200, 480, 600, 768, 768, 1200, 1536, 720, 1080, 2160, 1620,
+	1620, 1440, 272, 544, 864, 664, 960, 900, 960, 768, 900, 900, 1024, 1050, 1440, 1152,
+];
+
+const EXPORT_RESOLUTIONS: [(&str, u32); 4] = [
+	("720p", 720),
+	("1080p", 1080),
+	("1440p", 1440),
+	("2160p", 2160),
+];
+
+fn resolution_height(mode: spr::ResolutionMode) -> Option<u32> {
+	RESOLUTION_HEIGHTS.get(mode as usize).copied()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::txp::tests::*;
+
+	fn sprite(
+		texid: u32,
+		[px, py, width, height]: [f32; 4],
+		mode: spr::ResolutionMode,
+	) -> spr::Info {
+		let mut info = spr::Info::new();
+		info.set_texid(texid);
+		info.set_px(px);
+		info.set_py(py);
+		info.set_width(width);
+		info.set_height(height);
+		info.set_resolution_mode(mode);
+		info
+	}
+
+	#[test]
+	fn spr_round_trip() {
+		// 720p, the mode of most older sets
+		let hd: spr::ResolutionMode = unsafe { std::mem::transmute(13) };
+		let textures = [
+			(String::from("TEX_A"), gradient_texture(64, 32)),
+			(String::from("TEX_B"), gradient_texture(16, 16)),
+		];
+		let sprites = [
+			(
+				String::from("SPR_A"),
+				sprite(0, [2.0, 2.0, 30.0, 12.0], spr::ResolutionMode::FHD),
+			),
+			(
+				String::from("SPR_B"),
+				sprite(0, [34.0, 2.0, 28.0, 28.0], hd),
+			),
+			(
+				String::from("SPR_C"),
+				sprite(1, [0.0, 0.0, 16.0, 16.0], spr::ResolutionMode::FHD),
+			),
+		];
+
+		let mut txp_set = txp::Set::new();
+		for (_, texture) in &textures {
+			txp_set.add_file(texture);
+		}
+		let mut set = spr::Set::new();
+		for (name, info) in &sprites {
+			set.add_spr(info, name);
+		}
+		set.set_txp(
+			&txp_set,
+			textures.iter().map(|(name, _)| name.clone()).collect(),
+		);
+		set.set_ready(true);
+		set.set_modern(false);
+		set.set_big_endian(false);
+		set.set_is_x(false);
+		set.set_flag(0);
+		let data = set.to_buf().unwrap();
+
+		// Saving an unedited set writes back the same sprites and textures
+		let node = SpriteSetNode::read("spr_test.bin", &data);
+		let saved = spr::Set::from_buf(&node.raw_data(), false);
+		assert_eq!(saved.modern(), set.modern());
+		assert_eq!(saved.big_endian(), set.big_endian());
+		assert_eq!(saved.is_x(), set.is_x());
+		assert_eq!(saved.flag(), set.flag());
+
+		assert_eq!(saved.textures().count(), textures.len());
+		for ((name, texture), (saved_name, saved)) in textures.iter().zip(saved.textures()) {
+			assert_eq!(*name, saved_name);
+			assert_same_texture(texture, &saved);
+		}
+
+		assert_eq!(saved.sprites().count(), sprites.len());
+		for ((name, info), (saved_name, saved)) in sprites.iter().zip(saved.sprites()) {
+			assert_eq!(*name, saved_name);
+			assert_eq!(info.texid(), saved.texid());
+			assert_eq!(info.px(), saved.px());
+			assert_eq!(info.py(), saved.py());
+			assert_eq!(info.width(), saved.width());
+			assert_eq!(info.height(), saved.height());
+			assert_eq!(
+				info.resolution_mode() as u32,
+				saved.resolution_mode() as u32
+			);
+		}
+	}
+}
@@ -12,13 +12,99 @@ pub struct SprDbNode {
 	pub big_endian: bool,
 	pub is_x: bool,
 	pub sets: Vec<Rc<Mutex<SprDbSetNode>>>,
+	pub unsaved: bool,
+	pub undos: Vec<SprDbUndo>,
+	pub redos: Vec<SprDbUndo>,
+	// The selected node before the edits in progress, and the last change while they settle
+	pub edit: Option<(SprDbUndo, Option<(f64, SprDbUndo)>)>,
 }
 
+// Edits to the options of the database, one of its sets or one of its entries
+#[derive(Clone)]
+pub enum SprDbUndo {
+	Db(DbState),
+	Set(Rc<Mutex<SprDbSetNode>>, SetState),
+	Entry(Rc<Mutex<SprDbEntryNode>>, EntryState),
+}
+
+#[derive(Clone, PartialEq)]
+pub struct DbState {
+	modern: bool,
+	big_endian: bool,
+	is_x: bool,
+}
+
+#[derive(Clone, PartialEq)]
+pub struct SetState {
+	id: u32,
+	name: String,
+	file_name: String,
+}
+
+#[derive(Clone, PartialEq)]
+pub struct EntryState {
+	id: u32,
+	name: String,
+	index: u16,
+	texture: bool,
+}
+
+impl SprDbUndo {
+	fn set(set: &Rc<Mutex<SprDbSetNode>>) -> Self {
+		let node = set.try_lock().unwrap();
+		SprDbUndo::Set(
+			set.clone(),
+			SetState {
+				id: node.id,
+				name: node.name.clone(),
+				file_name: node.file_name.clone(),
+			},
+		)
+	}
+
+	fn entry(entry: &Rc<Mutex<SprDbEntryNode>>) -> Self {
+		let node = entry.try_lock().unwrap();
+		SprDbUndo::Entry(
+			entry.clone(),
+			EntryState {
+				id: node.id,
+				name: node.name.clone(),
+				index: node.index,
+				texture: node.texture,
+			},
+		)
+	}
+
+	fn same_node(&self, other: &Self) -> bool {
+		match (self, other) {
+			(SprDbUndo::Db(_), SprDbUndo::Db(_)) => true,
+			(SprDbUndo::Set(a, _), SprDbUndo::Set(b, _)) => Rc::ptr_eq(a, b),
+			(SprDbUndo::Entry(a, _), SprDbUndo::Entry(b, _)) => Rc::ptr_eq(a, b),
+			_ => false,
+		}
+	}
+
+	fn same_state(&self, other: &Self) -> bool {
+		match (self, other) {
+			(SprDbUndo::Db(a), SprDbUndo::Db(b)) => a == b,
+			(SprDbUndo::Set(_, a), SprDbUndo::Set(_, b)) => a == b,
+			(SprDbUndo::Entry(_, a), SprDbUndo::Entry(_, b)) => a == b,
+			_ => false,
+		}
+	}
+}
+
+const UNDO_LIMIT: usize = 100;
+
 impl TreeNode for SprDbNode {
 	fn label(&self) -> &str {
 		&self.filename
 	}
 
+	fn label_sameline(&mut self, ui: &mut egui::Ui) {
+		crate::app::unsaved_marker(ui, self.unsaved);
+	}
+
 	fn has_children(&self) -> bool {
 		true
 	}
@@ -131,6 +217,132 @@ impl SprDbNode {
 					}))
 				})
 				.collect(),
+			unsaved: false,
+			undos: Vec::new(),
+			redos: Vec::new(),
+			edit: None,
+		}
+	}
+
+	fn db_state(&self) -> SprDbUndo {
+		SprDbUndo::Db(DbState {
+			modern: self.modern,
+			big_endian: self.big_endian,
+			is_x: self.is_x,
+		})
+	}
+
+	fn push_undo(&mut self, undo: SprDbUndo) {
+		self.undos.push(undo);
+		if self.undos.len() > UNDO_LIMIT {
+			self.undos.remove(0);
+		}
+		self.redos.clear();
+	}
+
+	fn settle_edit(&mut self) {
+		if let Some((original, Some(_))) = self.edit.take() {
+			self.push_undo(original);
+		}
+	}
+
+	// The same as the given state but as things are now
+	fn current_state(&self, undo: &SprDbUndo) -> SprDbUndo {
+		match undo {
+			SprDbUndo::Db(_) => self.db_state(),
+			SprDbUndo::Set(set, _) => SprDbUndo::set(set),
+			SprDbUndo::Entry(entry, _) => SprDbUndo::entry(entry),
+		}
+	}
+
+	fn restore(&mut self, undo: SprDbUndo) {
+		match undo {
+			SprDbUndo::Db(state) => {
+				self.modern = state.modern;
+				self.big_endian = state.big_endian;
+				self.is_x = state.is_x;
+			}
+			SprDbUndo::Set(set, state) => {
+				let mut set = set.try_lock().unwrap();
+				set.id = state.id;
+				set.name = state.name;
+				set.file_name = state.file_name;
+			}
+			SprDbUndo::Entry(entry, state) => {
+				let mut entry = entry.try_lock().unwrap();
+				entry.id = state.id;
+				entry.name = state.name;
+				entry.index = state.index;
+				entry.texture = state.texture;
+			}
+		}
+	}
+
+	pub fn has_undo(&self) -> bool {
+		!self.undos.is_empty() || matches!(self.edit, Some((_, Some(_))))
+	}
+
+	pub fn has_redo(&self) -> bool {
+		!self.redos.is_empty() && !matches!(self.edit, Some((_, Some(_))))
+	}
+
+	pub fn undo(&mut self) {
+		self.settle_edit();
+		let Some(undo) = self.undos.pop() else {
+			return;
+		};
+		self.redos.push(self.current_state(&undo));
+		self.restore(undo);
+	}
+
+	pub fn redo(&mut self) {
+		let Some(redo) = self.redos.pop() else {
+			return;
+		};
+		self.undos.push(self.current_state(&redo));
+		self.restore(redo);
+		self.edit = None;
+	}
+
+	// Called every frame, edits count as one once nothing changed for a second
+	pub fn feed_state(&mut self, current_time: f64, selected: &[usize]) {
+		let target = match selected {
+			[2] => Some(self.db_state()),
+			[2, set] => self.sets.get(*set).map(SprDbUndo::set),
+			[2, set, entry] => self.sets.get(*set).and_then(|set| {
+				set.try_lock()
+					.unwrap()
+					.entries
+					.get(*entry)
+					.map(SprDbUndo::entry)
+			}),
+			_ => None,
+		};
+
+		let Some(target) = target else {
+			self.settle_edit();
+			return;
+		};
+		let settled = match &mut self.edit {
+			Some((original, flux)) if original.same_node(&target) => match flux {
+				Some((time, last)) if !last.same_state(&target) => {
+					*time = current_time;
+					*last = target.clone();
+					false
+				}
+				Some((time, _)) => current_time >= *time + 1.0,
+				None => {
+					if !original.same_state(&target) {
+						*flux = Some((current_time, target.clone()));
+					}
+					false
+				}
+			},
+			_ => true,
+		};
+		if settled {
+			self.settle_edit();
+			self.edit = Some((target, None));
 		}
 	}
 }
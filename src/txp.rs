@@ -92,12 +92,15 @@ impl TreeNode for TextureSetNode {
 	fn display_ctx_menu(&mut self, ui: &mut egui::Ui) {
 		if ui.button("Add").clicked() {
 			let name = format!("Texture {:03}", self.children.len());
+			let [width, height] = crate::settings::Defaults::get(ui.ctx())
+				.texture_size
+				.map(|size| size.clamp(1, crate::settings::Defaults::MAX_TEXTURE_SIZE));
 
 			let mut mip = txp::Mipmap::new();
-			mip.set_height(16);
-			mip.set_width(16);
+			mip.set_height(height as _);
+			mip.set_width(width as _);
 			mip.set_format(txp::Format::RGBA8);
-			mip.set_data(&[0u8; 16 * 16 * 4]);
+			mip.set_data(&vec![0u8; width as usize * height as usize * 4]);
 
 			let mut texture = txp::Texture::new();
 			texture.set_array_size(1);
@@ -116,7 +119,11 @@ impl TreeNode for TextureSetNode {
 				texture_updated: true,
 				db_entry: None,
 				file_picker_result: None,
+				mip_folder_result: None,
 				error: None,
+				adjustment: ColorAdjustment::default(),
+				exposure: 0.0,
+				want_sprite_filter: false,
 				want_deletion: false,
 			})));
 		}
@@ -161,7 +168,11 @@ impl TextureSetNode {
 						texture_updated: false,
 						db_entry: None,
 						file_picker_result: None,
+						mip_folder_result: None,
 						error: None,
+						adjustment: ColorAdjustment::default(),
+						exposure: 0.0,
+						want_sprite_filter: false,
 						want_deletion: false,
 					}))
 				})
@@ -190,7 +201,11 @@ impl TextureSetNode {
 						texture_updated: false,
 						db_entry: None,
 						file_picker_result: None,
+						mip_folder_result: None,
 						error: None,
+						adjustment: ColorAdjustment::default(),
+						exposure: 0.0,
+						want_sprite_filter: false,
 						want_deletion: false,
 					}))
 				})
@@ -208,11 +223,113 @@ pub struct TextureNode {
 	pub texture_updated: bool,
 	pub db_entry: Option<Rc<Mutex<SprDbEntryNode>>>,
 	pub file_picker_result: Option<mpsc::Receiver<Option<(std::path::PathBuf, Vec<u8>)>>>,
+	pub mip_folder_result: Option<mpsc::Receiver<Vec<(std::path::PathBuf, Vec<u8>)>>>,
 	pub error: Option<String>,
+	// Only affects the preview until baked
+	pub adjustment: ColorAdjustment,
+	// Stops applied before tone mapping HDR textures, preview only
+	pub exposure: f32,
+	pub want_sprite_filter: bool,
 	pub want_deletion: bool,
 }
 
+#[derive(Clone, Copy, PartialEq)]
+pub struct ColorAdjustment {
+	pub brightness: f32,
+	pub contrast: f32,
+	pub gamma: f32,
+}
+
+impl Default for ColorAdjustment {
+	fn default() -> Self {
+		Self {
+			brightness: 0.0,
+			contrast: 1.0,
+			gamma: 1.0,
+		}
+	}
+}
+
+impl ColorAdjustment {
+	// Must match adjust in shader.wgsl
+	pub fn apply(&self, rgba: &mut [u8]) {
+		for pixel in rgba.chunks_exact_mut(4) {
+			for channel in &mut pixel[..3] {
+				let value = (*channel as f32 / 255.0 - 0.5) * self.contrast + 0.5 + self.brightness;
+				let value = value.max(0.0).powf(1.0 / self.gamma).min(1.0);
+				*channel = (value * 255.0).round() as u8;
+			}
+		}
+	}
+}
+
 impl TextureNode {
+	// Mips are regenerated from the adjusted first one, all re-encoded in the original format
+	fn bake_adjustment(&mut self, frame: &mut eframe::Frame) {
+		let Some(mip) = self.texture.get_mipmap(0, 0) else {
+			self.error = Some(String::from("Texture has no data"));
+			return;
+		};
+		let (width, height) = (mip.width(), mip.height());
+		let rgba = if self.texture.is_ycbcr() {
+			self.texture.decode_ycbcr()
+		} else {
+			mip.rgba()
+		};
+		let Some(mut rgba) = rgba else {
+			self.error = Some(String::from("Could not adjust texture"));
+			return;
+		};
+		self.adjustment.apply(&mut rgba);
+
+		if self.texture.is_ycbcr() {
+			// YCbCr textures are stored as their luma and chroma planes rather than a mip chain
+			let mut adjusted = txp::Mipmap::new();
+			adjusted.set_width(width);
+			adjusted.set_height(height);
+			adjusted.set_format(txp::Format::RGBA8);
+			adjusted.set_data(&rgba);
+
+			let mut texture = txp::Texture::new();
+			texture.set_array_size(1);
+			texture.set_mipmaps_count(1);
+			texture.set_has_cube_map(false);
+			texture.add_mipmap(&adjusted);
+
+			let Some(texture) = convert_texture(&texture, 0x90, frame) else {
+				self.error = Some(String::from("Could not adjust texture"));
+				return;
+			};
+			self.texture = texture;
+			self.texture_updated = true;
+		} else {
+			let Some(image) = image::RgbaImage::from_raw(width as u32, height as u32, rgba) else {
+				self.error = Some(String::from("Could not adjust texture"));
+				return;
+			};
+			// Stored upside down, replace_mips takes the image upright
+			self.replace_mips(&image::DynamicImage::ImageRgba8(image).flipv(), &[], frame);
+		}
+
+		if self.error.is_none() {
+			self.adjustment = ColorAdjustment::default();
+		}
+	}
+
+	pub fn is_hdr(&self) -> bool {
+		self.texture
+			.get_mipmap(0, 0)
+			.is_some_and(|mip| mip.format() as u32 == txp::Format::BC6H as u32)
+	}
+
+	// BC6H and BC7 are only read by modern games
+	pub fn is_modern_only(&self) -> bool {
+		self.texture.get_mipmap(0, 0).is_some_and(|mip| {
+			mip.format() as u32 == txp::Format::BC6H as u32
+				|| mip.format() as u32 == txp::Format::BC7 as u32
+		})
+	}
+
 	fn pick_file(&mut self, path: &std::path::PathBuf, data: &[u8], frame: &mut eframe::Frame) {
 		let extension = path.extension().unwrap_or_default();
 		let Some(format) = image::ImageFormat::from_extension(extension) else {
@@ -220,8 +337,6 @@ impl TextureNode {
 			return;
 		};
 
-		let mip = self.texture.get_mipmap(0, 0).unwrap();
-
 		let Ok(image) = image::load(std::io::Cursor::new(data), format) else {
 			self.error = Some(format!("Could not read {:?} as image", path));
 			return;
@@ -258,67 +373,139 @@ impl TextureNode {
 				self.texture_updated = true;
 			}
 		} else {
-			let mut texture = txp::Texture::new();
-			texture.set_has_cube_map(false);
-			texture.set_array_size(1);
-			texture.set_mipmaps_count(self.texture.mipmaps_count());
-
-			for i in 0..self.texture.mipmaps_count() {
-				let scale = 2_u32.pow(i as u32);
-				let (width, height) = if scale == 0 {
-					(image.width(), image.height())
-				} else {
-					(image.width() / scale, image.height() / scale)
+			self.replace_mips(&image, &[], frame);
+		}
+	}
+
+	// Levels without an image of their own are downscaled from the base image
+	fn replace_mips(
+		&mut self,
+		image: &image::DynamicImage,
+		levels: &[Option<image::DynamicImage>],
+		frame: &mut eframe::Frame,
+	) {
+		let Some(mip) = self.texture.get_mipmap(0, 0) else {
+			self.error = Some(String::from("Texture has no data"));
+			return;
+		};
+
+		let mut texture = txp::Texture::new();
+		texture.set_has_cube_map(false);
+		texture.set_array_size(1);
+		let mipmaps_count = self.texture.mipmaps_count().max(levels.len() as _);
+		texture.set_mipmaps_count(mipmaps_count);
+
+		for i in 0..mipmaps_count {
+			let scale = 2_u32.pow(i as u32);
+			let (width, height) = if scale == 0 {
+				(image.width(), image.height())
+			} else {
+				(image.width() / scale, image.height() / scale)
+			};
+
+			if width == 0 || height == 0 {
+				texture.set_mipmaps_count(i);
+				break;
+			}
+
+			let level = match levels.get(i as usize).and_then(Option::as_ref) {
+				Some(level) => level.flipv(),
+				None => image
+					.flipv()
+					.resize(width, height, image::imageops::FilterType::Lanczos3),
+			};
+
+			#[cfg(feature = "directxtex")]
+			{
+				let Some(mipmap) = txp::Mipmap::from_rgba(
+					width as i32,
+					height as i32,
+					level.to_rgba8().as_bytes(),
+					mip.format(),
+				) else {
+					self.error = Some(String::from("Could not encode image"));
+					return;
 				};
 
-				if width == 0 || height == 0 {
-					texture.set_mipmaps_count(i);
-					break;
-				}
+				texture.add_mipmap(&mipmap);
+			}
+			#[cfg(not(feature = "directxtex"))]
+			{
+				let render_state = &frame.wgpu_render_state().unwrap();
+				let Some(mipmap) = txp::Mipmap::from_rgba_gpu(
+					width as i32,
+					height as i32,
+					level.to_rgba8().as_bytes(),
+					mip.format(),
+					&render_state.device,
+					&render_state.queue,
+				) else {
+					self.error = Some(String::from("Could not encode image"));
+					return;
+				};
 
-				#[cfg(feature = "directxtex")]
-				{
-					let Some(mipmap) = txp::Mipmap::from_rgba(
-						width as i32,
-						height as i32,
-						image
-							.flipv()
-							.resize(width, height, image::imageops::FilterType::Lanczos3)
-							.to_rgba8()
-							.as_bytes(),
-						mip.format(),
-					) else {
-						self.error = Some(String::from("Could not encode image"));
-						return;
-					};
-
-					texture.add_mipmap(&mipmap);
-				}
-				#[cfg(not(feature = "directxtex"))]
-				{
-					let render_state = &frame.wgpu_render_state().unwrap();
-					let Some(mipmap) = txp::Mipmap::from_rgba_gpu(
-						width as i32,
-						height as i32,
-						image
-							.flipv()
-							.resize(width, height, image::imageops::FilterType::Lanczos3)
-							.to_rgba8()
-							.as_bytes(),
-						mip.format(),
-						&render_state.device,
-						&render_state.queue,
-					) else {
-						self.error = Some(String::from("Could not encode image"));
-						return;
-					};
-
-					texture.add_mipmap(&mipmap);
-				}
+				texture.add_mipmap(&mipmap);
 			}
-			self.texture = texture;
-			self.texture_updated = true;
 		}
+		self.texture = texture;
+		self.texture_updated = true;
+	}
+
+	// Files are named mip0, mip1, ... with mip0 required and every level half the size of the last
+	fn pick_mip_folder(
+		&mut self,
+		files: Vec<(std::path::PathBuf, Vec<u8>)>,
+		frame: &mut eframe::Frame,
+	) {
+		let mut levels: Vec<Option<image::DynamicImage>> = Vec::new();
+		for (path, data) in files {
+			let Some(level) = path
+				.file_stem()
+				.and_then(|stem| stem.to_str())
+				.and_then(|stem| stem.strip_prefix("mip"))
+				.and_then(|level| level.parse::<usize>().ok())
+			else {
+				continue;
+			};
+			let Some(format) =
+				image::ImageFormat::from_extension(path.extension().unwrap_or_default())
+			else {
+				continue;
+			};
+			let Ok(image) = image::load(std::io::Cursor::new(data), format) else {
+				self.error = Some(format!("Could not read {:?} as image", path));
+				return;
+			};
+
+			if levels.len() <= level {
+				levels.resize(level + 1, None);
+			}
+			levels[level] = Some(image);
+		}
+
+		let Some(Some(base)) = levels.first().cloned() else {
+			self.error = Some(String::from("Folder has no mip0 image"));
+			return;
+		};
+
+		for (i, level) in levels.iter().enumerate() {
+			let Some(level) = level else {
+				continue;
+			};
+			let expected = (base.width() >> i, base.height() >> i);
+			if (level.width(), level.height()) != expected {
+				self.error = Some(format!(
+					"mip{i} is {}x{}, expected {}x{}",
+					level.width(),
+					level.height(),
+					expected.0,
+					expected.1
+				));
+				return;
+			}
+		}
+
+		self.replace_mips(&base, &levels, frame);
 	}
 }
 
@@ -332,8 +519,14 @@ impl TreeNode for TextureNode {
 	}
 
 	fn display_ctx_menu(&mut self, ui: &mut egui::Ui) {
+		if ui.button("Show its sprites").clicked() {
+			self.want_sprite_filter = true;
+		}
 		if ui.button("Export").clicked() {
-			let mip = self.texture.get_mipmap(0, 0).unwrap();
+			let Some(mip) = self.texture.get_mipmap(0, 0) else {
+				self.error = Some(String::from("Texture has no data"));
+				return;
+			};
 
 			let rgba = if self.texture.is_ycbcr() {
 				self.texture.decode_ycbcr()
@@ -420,6 +613,44 @@ impl TreeNode for TextureNode {
 
 			self.file_picker_result = Some(rx);
 		}
+		if ui
+			.add_enabled(
+				!self.texture.is_ycbcr(),
+				egui::Button::new("Replace with mip folder"),
+			)
+			.on_hover_text(
+				"Use mip0.png, mip1.png, ... from a folder, missing levels are generated",
+			)
+			.clicked()
+		{
+			let (tx, rx) = mpsc::channel();
+			std::thread::spawn(move || {
+				tokio::runtime::Builder::new_current_thread()
+					.enable_io()
+					.build()
+					.unwrap()
+					.block_on(async {
+						let Some(folder) = rfd::AsyncFileDialog::new().pick_folder().await else {
+							return;
+						};
+
+						let files = std::fs::read_dir(folder.path())
+							.into_iter()
+							.flatten()
+							.flatten()
+							.map(|entry| entry.path())
+							.filter(|path| {
+								path.file_stem()
+									.is_some_and(|stem| stem.to_string_lossy().starts_with("mip"))
+							})
+							.filter_map(|path| Some((path.clone(), std::fs::read(&path).ok()?)))
+							.collect();
+						tx.send(files).unwrap();
+					});
+			});
+
+			self.mip_folder_result = Some(rx);
+		}
 		if ui.button("Remove").clicked() {
 			self.want_deletion = true;
 		}
@@ -451,9 +682,20 @@ impl TreeNode for TextureNode {
 			self.file_picker_result = None;
 		}
 
+		if let Some(rx) = &mut self.mip_folder_result
+			&& let Ok(files) = rx.try_recv()
+		{
+			self.pick_mip_folder(files, frame);
+			self.mip_folder_result = None;
+		}
+
 		let height = ui.text_style_height(&egui::TextStyle::Body);
-		let mip = self.texture.get_mipmap(0, 0).unwrap();
+		let Some(mip) = self.texture.get_mipmap(0, 0) else {
+			ui.label("Texture has no data");
+			return;
+		};
 		let mut replacement_texture = None;
+		let mut want_bake = false;
 		egui_extras::TableBuilder::new(ui)
 			.column(egui_extras::Column::remainder())
 			.column(egui_extras::Column::remainder())
@@ -564,108 +806,7 @@ impl TreeNode for TextureNode {
 							});
 
 						if format != old_format {
-							if format == 0x90 {
-								#[cfg(feature = "directxtex")]
-								{
-									let rgba = mip.rgba().unwrap_or_default();
-									replacement_texture = txp::Texture::encode_ycbcr(
-										mip.width(),
-										mip.height(),
-										&rgba,
-									);
-								}
-								#[cfg(not(feature = "directxtex"))]
-								{
-									let render_state = &frame.wgpu_render_state().unwrap();
-									let rgba = mip
-										.to_rgba_gpu(&render_state.device, &render_state.queue)
-										.unwrap_or_default();
-									replacement_texture = txp::Texture::encode_ycbcr(
-										mip.width() as u32,
-										mip.height() as u32,
-										&rgba,
-										&render_state.device,
-										&render_state.queue,
-									);
-								}
-							} else if old_format == 0x90 {
-								let rgba = self.texture.decode_ycbcr().unwrap_or_default();
-								#[cfg(feature = "directxtex")]
-								{
-									if let Some(mip) = txp::Mipmap::from_rgba(
-										mip.width(),
-										mip.height(),
-										&rgba,
-										unsafe { std::mem::transmute(format) },
-									) {
-										let mut tex = txp::Texture::new();
-										tex.set_has_cube_map(false);
-										tex.set_array_size(1);
-										tex.set_mipmaps_count(1);
-										tex.add_mipmap(&mip);
-										replacement_texture = Some(tex);
-									}
-								}
-								#[cfg(not(feature = "directxtex"))]
-								{
-									let render_state = &frame.wgpu_render_state().unwrap();
-									if let Some(mip) = txp::Mipmap::from_rgba_gpu(
-										mip.width(),
-										mip.height(),
-										&rgba,
-										unsafe { std::mem::transmute(format) },
-										&render_state.device,
-										&render_state.queue,
-									) {
-										let mut tex = txp::Texture::new();
-										tex.set_has_cube_map(false);
-										tex.set_array_size(1);
-										tex.set_mipmaps_count(1);
-										tex.add_mipmap(&mip);
-										replacement_texture = Some(tex);
-									}
-								}
-							} else {
-								let mut tex = txp::Texture::new();
-								tex.set_has_cube_map(self.texture.has_cube_map());
-								tex.set_array_size(self.texture.array_size());
-								tex.set_mipmaps_count(self.texture.mipmaps_count());
-								for mip in self.texture.mipmaps() {
-									if mip.width() < 4 || mip.height() < 4 {
-										break;
-									}
-									#[cfg(feature = "directxtex")]
-									{
-										let rgba = mip.rgba().unwrap_or_default();
-										if let Some(mip) = txp::Mipmap::from_rgba(
-											mip.width(),
-											mip.height(),
-											&rgba,
-											unsafe { std::mem::transmute(format) },
-										) {
-											tex.add_mipmap(&mip);
-										}
-									}
-									#[cfg(not(feature = "directxtex"))]
-									{
-										let render_state = &frame.wgpu_render_state().unwrap();
-										let rgba = mip
-											.to_rgba_gpu(&render_state.device, &render_state.queue)
-											.unwrap_or_default();
-										if let Some(mip) = txp::Mipmap::from_rgba_gpu(
-											mip.width(),
-											mip.height(),
-											&rgba,
-											unsafe { std::mem::transmute(format) },
-											&render_state.device,
-											&render_state.queue,
-										) {
-											tex.add_mipmap(&mip);
-										}
-									}
-								}
-								replacement_texture = Some(tex);
-							}
+							replacement_texture = convert_texture(&self.texture, format, frame);
 						}
 					});
 				});
@@ -692,10 +833,63 @@ impl TreeNode for TextureNode {
 				}
 			});
 
+		egui::CollapsingHeader::new("Preview adjustment").show(ui, |ui| {
+			let old_adjustment = self.adjustment;
+			let old_exposure = self.exposure;
+			egui::Grid::new("ColorAdjustmentGrid").show(ui, |ui| {
+				ui.label("Brightness");
+				ui.add(egui::Slider::new(
+					&mut self.adjustment.brightness,
+					-1.0..=1.0,
+				));
+				ui.end_row();
+
+				ui.label("Contrast");
+				ui.add(egui::Slider::new(&mut self.adjustment.contrast, 0.0..=4.0));
+				ui.end_row();
+
+				ui.label("Gamma");
+				ui.add(egui::Slider::new(&mut self.adjustment.gamma, 0.1..=4.0));
+				ui.end_row();
+
+				if self.is_hdr() {
+					ui.label("Exposure");
+					ui.add(egui::Slider::new(&mut self.exposure, -8.0..=8.0).suffix(" EV"))
+						.on_hover_text("HDR values are scaled by 2^exposure then tone mapped");
+					ui.end_row();
+				}
+			});
+
+			ui.horizontal(|ui| {
+				let adjusted = self.adjustment != ColorAdjustment::default();
+				if ui
+					.add_enabled(adjusted, egui::Button::new("Reset"))
+					.clicked()
+				{
+					self.adjustment = ColorAdjustment::default();
+				}
+				if ui
+					.add_enabled(adjusted, egui::Button::new("Apply"))
+					.on_hover_text("Write the adjustment into the texture data")
+					.clicked()
+				{
+					want_bake = true;
+				}
+			});
+
+			if self.adjustment != old_adjustment || self.exposure != old_exposure {
+				self.selected(frame);
+			}
+		});
+
 		if let Some(tex) = replacement_texture {
 			self.texture = tex;
 			self.texture_updated = true;
 		}
+
+		if want_bake {
+			self.bake_adjustment(frame);
+		}
 	}
 
 	fn selected(&mut self, frame: &mut eframe::Frame) {
@@ -744,9 +938,13 @@ impl TreeNode for TextureNode {
 			tex_coords: [[0.0, 1.0], [1.0, 1.0], [0.0, 0.0], [1.0, 0.0]],
 			color: [1.0, 1.0, 1.0, 1.0],
 			is_ycbcr: if self.texture.is_ycbcr() { 1 } else { 0 },
-			_padding_0: 0,
-			_padding_1: 0,
-			_padding_2: 0,
+			brightness: self.adjustment.brightness,
+			contrast: self.adjustment.contrast,
+			gamma: self.adjustment.gamma,
+			is_placeholder: 0,
+			is_hdr: if self.is_hdr() { 1 } else { 0 },
+			exposure: self.exposure,
+			_padding: 0,
 		};
 
 		render_state.queue.write_buffer(
@@ -761,7 +959,7 @@ impl TreeNode for TextureNode {
 		_ui: &mut egui::Ui,
 		rect: egui::Rect,
 	) -> Option<egui::epaint::PaintCallback> {
-		let mip = self.texture.get_mipmap(0, 0).unwrap();
+		let mip = self.texture.get_mipmap(0, 0)?;
 
 		let w = rect.max.x - rect.min.x;
 		let h = rect.max.y - rect.min.y;
@@ -820,7 +1018,10 @@ impl egui_wgpu::CallbackTrait for WgpuTextureCallback {
 		render_pass.set_pipeline(&resources.pipeline_normal);
 		render_pass.set_bind_group(
 			0,
-			&texture.fragment_bind_group[self.texture_index as usize].1,
+			texture
+				.fragment_bind_group
+				.get(self.texture_index as usize)
+				.map_or(&texture.empty_texture, |(_, bind_group)| bind_group),
 			&[],
 		);
 		render_pass.set_bind_group(1, &resources.uniform_buffers[0].1, &[]);
@@ -829,16 +1030,193 @@ impl egui_wgpu::CallbackTrait for WgpuTextureCallback {
 	}
 }
 
+// Re-encodes every mip of the texture into `format`, 0x90 being YCbCr
+pub fn convert_texture(
+	texture: &txp::Texture,
+	format: u32,
+	frame: &eframe::Frame,
+) -> Option<txp::Texture> {
+	let mip = texture.get_mipmap(0, 0)?;
+	let mut replacement_texture = None;
+	if format == 0x90 {
+		#[cfg(feature = "directxtex")]
+		{
+			let rgba = mip.rgba().unwrap_or_default();
+			replacement_texture = txp::Texture::encode_ycbcr(mip.width(), mip.height(), &rgba);
+		}
+		#[cfg(not(feature = "directxtex"))]
+		{
+			let render_state = &frame.wgpu_render_state().unwrap();
+			let rgba = mip
+				.to_rgba_gpu(&render_state.device, &render_state.queue)
+				.unwrap_or_default();
+			replacement_texture = txp::Texture::encode_ycbcr(
+				mip.width() as u32,
+				mip.height() as u32,
+				&rgba,
+				&render_state.device,
+				&render_state.queue,
+			);
+		}
+	} else if texture.is_ycbcr() {
+		let rgba = texture.decode_ycbcr().unwrap_or_default();
+		#[cfg(feature = "directxtex")]
+		{
+			if let Some(mip) = txp::Mipmap::from_rgba(mip.width(), mip.height(), &rgba, unsafe {
+				std::mem::transmute(format)
+			}) {
+				let mut tex = txp::Texture::new();
+				tex.set_has_cube_map(false);
+				tex.set_array_size(1);
+				tex.set_mipmaps_count(1);
+				tex.add_mipmap(&mip);
+				replacement_texture = Some(tex);
+			}
+		}
+		#[cfg(not(feature = "directxtex"))]
+		{
+			let render_state = &frame.wgpu_render_state().unwrap();
+			if let Some(mip) = txp::Mipmap::from_rgba_gpu(
+				mip.width(),
+				mip.height(),
+				&rgba,
+				unsafe { std::mem::transmute(format) },
+				&render_state.device,
+				&render_state.queue,
+			) {
+				let mut tex = txp::Texture::new();
+				tex.set_has_cube_map(false);
+				tex.set_array_size(1);
+				tex.set_mipmaps_count(1);
+				tex.add_mipmap(&mip);
+				replacement_texture = Some(tex);
+			}
+		}
+	} else {
+		let mut tex = txp::Texture::new();
+		tex.set_has_cube_map(texture.has_cube_map());
+		tex.set_array_size(texture.array_size());
+		tex.set_mipmaps_count(texture.mipmaps_count());
+		for mip in texture.mipmaps() {
+			if mip.width() < 4 || mip.height() < 4 {
+				break;
+			}
+			#[cfg(feature = "directxtex")]
+			{
+				let rgba = mip.rgba().unwrap_or_default();
+				if let Some(mip) =
+					txp::Mipmap::from_rgba(mip.width(), mip.height(), &rgba, unsafe {
+						std::mem::transmute(format)
+					}) {
+					tex.add_mipmap(&mip);
+				}
+			}
+			#[cfg(not(feature = "directxtex"))]
+			{
+				let render_state = &frame.wgpu_render_state().unwrap();
+				let rgba = mip
+					.to_rgba_gpu(&render_state.device, &render_state.queue)
+					.unwrap_or_default();
+				if let Some(mip) = txp::Mipmap::from_rgba_gpu(
+					mip.width(),
+					mip.height(),
+					&rgba,
+					unsafe { std::mem::transmute(format) },
+					&render_state.device,
+					&render_state.queue,
+				) {
+					tex.add_mipmap(&mip);
+				}
+			}
+		}
+		replacement_texture = Some(tex);
+	}
+	replacement_texture
+}
+
+// Uniform buffers are pooled across frames, these let the UI read the pool size back from the renderer
+pub static UNIFORM_BUFFERS_ALLOCATED: atomic::AtomicUsize = atomic::AtomicUsize::new(0);
+pub static UNIFORM_BUFFERS_USED: atomic::AtomicUsize = atomic::AtomicUsize::new(0);
+// The pool is only shrunk once it holds more than this many buffers
+pub const UNIFORM_BUFFERS_MIN_RETAINED: usize = 256;
+
 pub struct WgpuRenderResources {
 	pub pipeline_normal: wgpu::RenderPipeline,
-	pub pipeline_screen: wgpu::RenderPipeline,
-	pub pipeline_add: wgpu::RenderPipeline,
-	// Multiply and overlay currently unimplemented
+	pub pipeline_premultiplied_normal: wgpu::RenderPipeline,
+	pub pipeline_premultiplied_screen: wgpu::RenderPipeline,
+	pub pipeline_premultiplied_add: wgpu::RenderPipeline,
+	pub pipeline_premultiplied_multiply: wgpu::RenderPipeline,
+	pub pipeline_overlay: wgpu::RenderPipeline,
+	// Draws a premultiplied composition onto egui's surface
+	pub pipeline_composite: wgpu::RenderPipeline,
+	pub target_format: wgpu::TextureFormat,
+	pub composite_target: Option<CompositeTarget>,
 	pub fragment_bind_group_layout: wgpu::BindGroupLayout,
 	pub uniform_bind_group_layout: wgpu::BindGroupLayout,
 	pub vertex_buffer: wgpu::Buffer,
 	pub uniform_buffers: Vec<(wgpu::Buffer, wgpu::BindGroup)>,
 	pub sampler: wgpu::Sampler,
+	// Used to upscale compositions shown at integer scales
+	pub sampler_nearest: wgpu::Sampler,
+}
+
+// Scenes are composited here before being drawn, like the game does
+pub struct CompositeTarget {
+	pub size: [u32; 2],
+	pub nearest: bool,
+	pub view: wgpu::TextureView,
+	pub bind_group: wgpu::BindGroup,
+}
+
+impl CompositeTarget {
+	pub fn new(
+		device: &wgpu::Device,
+		resources: &WgpuRenderResources,
+		size: [u32; 2],
+		nearest: bool,
+	) -> Self {
+		let texture = device.create_texture(&wgpu::TextureDescriptor {
+			label: Some("Composite texture"),
+			size: wgpu::Extent3d {
+				width: size[0],
+				height: size[1],
+				depth_or_array_layers: 1,
+			},
+			mip_level_count: 1,
+			sample_count: 1,
+			dimension: wgpu::TextureDimension::D2,
+			format: resources.target_format,
+			usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::TEXTURE_BINDING,
+			view_formats: &[],
+		});
+		let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+		let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+			layout: &resources.fragment_bind_group_layout,
+			entries: &[
+				wgpu::BindGroupEntry {
+					binding: 0,
+					resource: wgpu::BindingResource::TextureView(&view),
+				},
+				wgpu::BindGroupEntry {
+					binding: 1,
+					resource: wgpu::BindingResource::Sampler(if nearest {
+						&resources.sampler_nearest
+					} else {
+						&resources.sampler
+					}),
+				},
+			],
+			label: Some("Composite bind group"),
+		});
+
+		Self {
+			size,
+			nearest,
+			view,
+			bind_group,
+		}
+	}
 }
 
 pub struct WgpuRenderTextures {
@@ -860,11 +1238,19 @@ pub struct SpriteInfo {
 	pub tex_coords: [[f32; 2]; 4],
 	pub color: [f32; 4],
 	pub is_ycbcr: u32,
-	pub _padding_0: u32,
-	pub _padding_1: u32,
-	pub _padding_2: u32,
+	pub brightness: f32,
+	pub contrast: f32,
+	pub gamma: f32,
+	pub is_placeholder: u32,
+	pub is_hdr: u32,
+	pub exposure: f32,
+	pub _padding: u32,
 }
 
+// Must match the SpriteInfo uniform in shader.wgsl, which rounds its size up to a multiple of 16
+const _: () = assert!(std::mem::size_of::<SpriteInfo>() == 144);
+const _: () = assert!(std::mem::size_of::<SpriteInfo>() % 16 == 0);
+
 pub fn setup_wgpu(render_state: &egui_wgpu::RenderState) {
 	let device = &render_state.device;
 
@@ -927,10 +1313,11 @@ pub fn setup_wgpu(render_state: &egui_wgpu::RenderState) {
 		},
 	};
 
-	let screen_blend_mode = wgpu::BlendState {
+	// Combiner 1, with what the source does not cover kept as the source is premultiplied
+	let premultiplied_multiply_blend_mode = wgpu::BlendState {
 		color: wgpu::BlendComponent {
-			src_factor: wgpu::BlendFactor::SrcAlpha,
-			dst_factor: wgpu::BlendFactor::OneMinusSrc,
+			src_factor: wgpu::BlendFactor::Dst,
+			dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
 			operation: wgpu::BlendOperation::Add,
 		},
 		alpha: wgpu::BlendComponent {
@@ -940,10 +1327,11 @@ pub fn setup_wgpu(render_state: &egui_wgpu::RenderState) {
 		},
 	};
 
-	let add_blend_mode = wgpu::BlendState {
+	// Combiner 2, which needs the straight source color
+	let overlay_blend_mode = wgpu::BlendState {
 		color: wgpu::BlendComponent {
 			src_factor: wgpu::BlendFactor::SrcAlpha,
-			dst_factor: wgpu::BlendFactor::One,
+			dst_factor: wgpu::BlendFactor::OneMinusSrc,
 			operation: wgpu::BlendOperation::Add,
 		},
 		alpha: wgpu::BlendComponent {
@@ -953,25 +1341,26 @@ pub fn setup_wgpu(render_state: &egui_wgpu::RenderState) {
 		},
 	};
 
-	// Combiner 1
-	let _multiply_blend_mode = wgpu::BlendState {
+	let premultiplied_normal_blend_mode = wgpu::BlendState::PREMULTIPLIED_ALPHA_BLENDING;
+
+	let premultiplied_screen_blend_mode = wgpu::BlendState {
 		color: wgpu::BlendComponent {
-			src_factor: wgpu::BlendFactor::Dst,
-			dst_factor: wgpu::BlendFactor::Zero,
+			src_factor: wgpu::BlendFactor::One,
+			dst_factor: wgpu::BlendFactor::OneMinusSrc,
 			operation: wgpu::BlendOperation::Add,
 		},
 		alpha: wgpu::BlendComponent {
-			src_factor: wgpu::BlendFactor::Zero,
-			dst_factor: wgpu::BlendFactor::One,
+			src_factor: wgpu::BlendFactor::One,
+			dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
 			operation: wgpu::BlendOperation::Add,
 		},
 	};
 
-	// Combiner 2
-	let _overlay_blend_mode = wgpu::BlendState {
+	// Adds light without covering what is below
+	let premultiplied_add_blend_mode = wgpu::BlendState {
 		color: wgpu::BlendComponent {
-			src_factor: wgpu::BlendFactor::SrcAlpha,
-			dst_factor: wgpu::BlendFactor::OneMinusSrc,
+			src_factor: wgpu::BlendFactor::One,
+			dst_factor: wgpu::BlendFactor::One,
 			operation: wgpu::BlendOperation::Add,
 		},
 		alpha: wgpu::BlendComponent {
@@ -1030,19 +1419,46 @@ pub fn setup_wgpu(render_state: &egui_wgpu::RenderState) {
 
 	let pipeline_normal = device.create_render_pipeline(&pipeline_desc);
 
-	target.blend = Some(screen_blend_mode);
+	target.blend = Some(premultiplied_normal_blend_mode);
+	let target_arr = [Some(target.clone())];
+	pipeline_desc.fragment.as_mut().unwrap().targets = &target_arr;
+	pipeline_desc.label = Some("Composite");
+
+	let pipeline_composite = device.create_render_pipeline(&pipeline_desc);
+
+	pipeline_desc.fragment.as_mut().unwrap().entry_point = Some("fs_premultiplied");
+	pipeline_desc.label = Some("Premultiplied normal blend mode");
+
+	let pipeline_premultiplied_normal = device.create_render_pipeline(&pipeline_desc);
+
+	target.blend = Some(premultiplied_screen_blend_mode);
+	let target_arr = [Some(target.clone())];
+	pipeline_desc.fragment.as_mut().unwrap().targets = &target_arr;
+	pipeline_desc.label = Some("Premultiplied screen blend mode");
+
+	let pipeline_premultiplied_screen = device.create_render_pipeline(&pipeline_desc);
+
+	target.blend = Some(premultiplied_add_blend_mode);
 	let target_arr = [Some(target.clone())];
 	pipeline_desc.fragment.as_mut().unwrap().targets = &target_arr;
-	pipeline_desc.label = Some("Screen blend mode");
+	pipeline_desc.label = Some("Premultiplied add blend mode");
 
-	let pipeline_screen = device.create_render_pipeline(&pipeline_desc);
+	let pipeline_premultiplied_add = device.create_render_pipeline(&pipeline_desc);
 
-	target.blend = Some(add_blend_mode);
+	target.blend = Some(premultiplied_multiply_blend_mode);
 	let target_arr = [Some(target.clone())];
 	pipeline_desc.fragment.as_mut().unwrap().targets = &target_arr;
-	pipeline_desc.label = Some("Add blend mode");
+	pipeline_desc.label = Some("Premultiplied multiply blend mode");
 
-	let pipeline_add = device.create_render_pipeline(&pipeline_desc);
+	let pipeline_premultiplied_multiply = device.create_render_pipeline(&pipeline_desc);
+
+	target.blend = Some(overlay_blend_mode);
+	let target_arr = [Some(target.clone())];
+	pipeline_desc.fragment.as_mut().unwrap().targets = &target_arr;
+	pipeline_desc.fragment.as_mut().unwrap().entry_point = Some("fs_main");
+	pipeline_desc.label = Some("Overlay blend mode");
+
+	let pipeline_overlay = device.create_render_pipeline(&pipeline_desc);
 
 	let vertex_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
 		label: Some("Vertex buffer"),
@@ -1082,9 +1498,13 @@ pub fn setup_wgpu(render_state: &egui_wgpu::RenderState) {
 			tex_coords: [[0.0, 0.0], [1.0, 0.0], [0.0, 1.0], [1.0, 1.0]],
 			color: [1.0, 1.0, 1.0, 1.0],
 			is_ycbcr: 0,
-			_padding_0: 0,
-			_padding_1: 0,
-			_padding_2: 0,
+			brightness: 0.0,
+			contrast: 1.0,
+			gamma: 1.0,
+			is_placeholder: 0,
+			is_hdr: 0,
+			exposure: 0.0,
+			_padding: 0,
 		}]),
 		usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::UNIFORM,
 	});
@@ -1108,19 +1528,36 @@ pub fn setup_wgpu(render_state: &egui_wgpu::RenderState) {
 		..Default::default()
 	});
 
+	let sampler_nearest = device.create_sampler(&wgpu::SamplerDescriptor {
+		address_mode_u: wgpu::AddressMode::ClampToEdge,
+		address_mode_v: wgpu::AddressMode::ClampToEdge,
+		address_mode_w: wgpu::AddressMode::ClampToEdge,
+		mag_filter: wgpu::FilterMode::Nearest,
+		min_filter: wgpu::FilterMode::Nearest,
+		mipmap_filter: wgpu::FilterMode::Nearest,
+		..Default::default()
+	});
+
 	render_state
 		.renderer
 		.write()
 		.callback_resources
 		.insert(WgpuRenderResources {
 			pipeline_normal,
-			pipeline_screen,
-			pipeline_add,
+			pipeline_premultiplied_normal,
+			pipeline_premultiplied_screen,
+			pipeline_premultiplied_add,
+			pipeline_premultiplied_multiply,
+			pipeline_overlay,
+			pipeline_composite,
+			target_format: render_state.target_format,
+			composite_target: None,
 			fragment_bind_group_layout,
 			uniform_bind_group_layout,
 			vertex_buffer,
 			uniform_buffers: vec![(base_uniform_buffer, uniform_buffer_group)],
 			sampler,
+			sampler_nearest,
 		});
 }
 
@@ -1283,3 +1720,72 @@ pub fn encode_texture_ycbcr(
 
 	data
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+	use super::*;
+
+	// An RGBA8 texture with a full mip chain, every pixel different so misplaced data shows up
+	pub(crate) fn gradient_texture(width: u32, height: u32) -> txp::Texture {
+		let mut texture = txp::Texture::new();
+		texture.set_has_cube_map(false);
+		texture.set_array_size(1);
+
+		let mut count = 0;
+		let (mut w, mut h) = (width, height);
+		while w > 0 && h > 0 {
+			let data = (0..w * h)
+				.flat_map(|i| [(i % w) as u8, (i / w) as u8, count as u8, 0xFF])
+				.collect::<Vec<_>>();
+
+			let mut mip = txp::Mipmap::new();
+			mip.set_width(w as _);
+			mip.set_height(h as _);
+			mip.set_format(txp::Format::RGBA8);
+			mip.set_data(&data);
+			texture.add_mipmap(&mip);
+
+			count += 1;
+			(w, h) = (w / 2, h / 2);
+		}
+		texture.set_mipmaps_count(count);
+		texture
+	}
+
+	pub(crate) fn assert_same_texture(a: &txp::Texture, b: &txp::Texture) {
+		assert_eq!(a.has_cube_map(), b.has_cube_map());
+		assert_eq!(a.array_size(), b.array_size());
+		assert_eq!(a.mipmaps_count(), b.mipmaps_count());
+		assert_eq!(a.mipmaps().count(), b.mipmaps().count());
+		for (a, b) in a.mipmaps().zip(b.mipmaps()) {
+			assert_eq!(a.width(), b.width());
+			assert_eq!(a.height(), b.height());
+			assert_eq!(a.format() as u32, b.format() as u32);
+			assert_eq!(a.data(), b.data());
+		}
+	}
+
+	#[test]
+	fn txp_round_trip() {
+		let textures = [gradient_texture(64, 32), gradient_texture(16, 16)];
+		let mut set = txp::Set::new();
+		for texture in &textures {
+			set.add_file(texture);
+		}
+		let data = set.to_buf(false, None).unwrap();
+
+		let node = TextureSetNode::read("test_tex.bin", &data);
+		assert!(!node.big_endian);
+		assert_eq!(node.children.len(), textures.len());
+		for (texture, child) in textures.iter().zip(&node.children) {
+			assert_same_texture(texture, &child.try_lock().unwrap().texture);
+		}
+
+		// Saving an unedited set writes back the same textures
+		let saved = txp::Set::from_buf(&node.raw_data(), false, None);
+		assert_eq!(saved.textures().count(), textures.len());
+		for (texture, saved) in textures.iter().zip(saved.textures()) {
+			assert_same_texture(texture, &saved);
+		}
+	}
+}